@@ -0,0 +1,340 @@
+use std::fmt;
+
+/// Binance market the endpoints serve, which decides the REST API paths and streams
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Market {
+    Spot,
+    /// USDⓈ-M perpetual and delivery futures
+    UsdFutures,
+    /// COIN-M perpetual and delivery futures, margined and quoted in contracts
+    CoinFutures,
+}
+
+impl Market {
+    // Path of the REST API below the base URL
+    pub fn api_path(&self) -> &'static str {
+        match self {
+            Market::Spot => "/api/v3",
+            Market::UsdFutures => "/fapi/v1",
+            Market::CoinFutures => "/dapi/v1",
+        }
+    }
+}
+
+impl fmt::Display for Market {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Market::Spot => write!(f, "Spot"),
+            Market::UsdFutures => write!(f, "USDⓈ-M Futures"),
+            Market::CoinFutures => write!(f, "COIN-M Futures"),
+        }
+    }
+}
+
+/// Exchange the endpoints belong to, which decides the feed format
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Venue {
+    Binance,
+    /// Coinbase Exchange public feed, with trades and best bid/ask only
+    Coinbase,
+}
+
+impl fmt::Display for Venue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Venue::Binance => write!(f, "Binance"),
+            Venue::Coinbase => write!(f, "Coinbase"),
+        }
+    }
+}
+
+/// WebSocket and REST base URLs the whole client talks to
+#[derive(Debug, Clone, PartialEq)]
+pub struct Endpoints {
+    /// WebSocket base, without the `/ws` or `/stream` path
+    pub ws_url: String,
+    /// REST base, without the API path
+    pub rest_url: String,
+    pub venue: Venue,
+    pub market: Market,
+    /// Request/response WebSocket API, e.g. `wss://ws-api.binance.com:443/ws-api/v3`;
+    /// not every profile has one
+    pub ws_api_url: Option<String>,
+    /// API key for the user data stream, read only from `BINANCE_API_KEY` so it stays
+    /// out of the process list
+    pub api_key: Option<String>,
+}
+
+/// Named endpoint sets, selected with `--endpoint` or `BINANCE_ENDPOINT`
+pub const PROFILES: [&str; 10] = [
+    "mainnet",
+    "mainnet-443",
+    "market-data",
+    "testnet",
+    "binance-us",
+    "usdm",
+    "usdm-testnet",
+    "coinm",
+    "coinm-testnet",
+    "coinbase",
+];
+
+impl Endpoints {
+    // Create spot Endpoints from custom base URLs, e.g. a local stand-in server
+    pub fn custom(ws_url: &str, rest_url: &str) -> Self {
+        Self {
+            ws_url: ws_url.trim_end_matches('/').to_string(),
+            rest_url: rest_url.trim_end_matches('/').to_string(),
+            venue: Venue::Binance,
+            market: Market::Spot,
+            ws_api_url: None,
+            api_key: None,
+        }
+    }
+
+    // Look up a named profile
+    pub fn profile(name: &str) -> Option<Self> {
+        let ws_api_url = match name {
+            "mainnet" | "mainnet-443" => Some("wss://ws-api.binance.com:443/ws-api/v3"),
+            "testnet" => Some("wss://ws-api.testnet.binance.vision/ws-api/v3"),
+            "binance-us" => Some("wss://ws-api.binance.us:443/ws-api/v3"),
+            _ => None,
+        };
+        Self::market_profile(name).map(|endpoints| Self {
+            ws_api_url: ws_api_url.map(str::to_string),
+            ..endpoints
+        })
+    }
+
+    // Stream and REST URLs of a named profile
+    fn market_profile(name: &str) -> Option<Self> {
+        let (ws_url, rest_url) = match name {
+            "mainnet" => ("wss://stream.binance.com:9443", "https://api.binance.com"),
+            "mainnet-443" => ("wss://stream.binance.com:443", "https://api.binance.com"),
+            // Market data only, no user data streams or trading
+            "market-data" => (
+                "wss://data-stream.binance.vision",
+                "https://data-api.binance.vision",
+            ),
+            "testnet" => (
+                "wss://stream.testnet.binance.vision",
+                "https://testnet.binance.vision",
+            ),
+            "binance-us" => ("wss://stream.binance.us:9443", "https://api.binance.us"),
+            "usdm" => {
+                return Some(Self::futures(
+                    "wss://fstream.binance.com",
+                    "https://fapi.binance.com",
+                ))
+            }
+            "usdm-testnet" => {
+                return Some(Self::futures(
+                    "wss://stream.binancefuture.com",
+                    "https://testnet.binancefuture.com",
+                ))
+            }
+            "coinm" => {
+                return Some(Self::coin_futures(
+                    "wss://dstream.binance.com",
+                    "https://dapi.binance.com",
+                ))
+            }
+            "coinm-testnet" => {
+                return Some(Self::coin_futures(
+                    "wss://dstream.binancefuture.com",
+                    "https://testnet.binancefuture.com",
+                ))
+            }
+            "coinbase" => {
+                return Some(Self {
+                    venue: Venue::Coinbase,
+                    ..Self::custom(
+                        "wss://ws-feed.exchange.coinbase.com",
+                        "https://api.exchange.coinbase.com",
+                    )
+                })
+            }
+            _ => return None,
+        };
+        Some(Self::custom(ws_url, rest_url))
+    }
+
+    // Create USDⓈ-M futures Endpoints from base URLs
+    pub fn futures(ws_url: &str, rest_url: &str) -> Self {
+        Self {
+            market: Market::UsdFutures,
+            ..Self::custom(ws_url, rest_url)
+        }
+    }
+
+    // Create COIN-M futures Endpoints from base URLs
+    pub fn coin_futures(ws_url: &str, rest_url: &str) -> Self {
+        Self {
+            market: Market::CoinFutures,
+            ..Self::custom(ws_url, rest_url)
+        }
+    }
+
+    /// Reads `--endpoint <profile>`, `--ws-url <url>`, `--rest-url <url>` and
+    /// `--ws-api-url <url>` from the command line, falling back to the `BINANCE_ENDPOINT`,
+    /// `BINANCE_WS_URL`, `BINANCE_REST_URL` and `BINANCE_WS_API_URL` variables from `env`.
+    /// The URLs override the profile's, which defaults to mainnet.
+    pub fn from_args(
+        args: impl IntoIterator<Item = String>,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, String> {
+        let mut profile = env("BINANCE_ENDPOINT");
+        let mut ws_url = env("BINANCE_WS_URL");
+        let mut rest_url = env("BINANCE_REST_URL");
+        let mut ws_api_url = env("BINANCE_WS_API_URL");
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let target = match arg.as_str() {
+                "--endpoint" => &mut profile,
+                "--ws-url" => &mut ws_url,
+                "--rest-url" => &mut rest_url,
+                "--ws-api-url" => &mut ws_api_url,
+                _ => return Err(format!("unknown argument '{}'", arg)),
+            };
+            *target = Some(args.next().ok_or(format!("{} needs a value", arg))?);
+        }
+
+        let profile = profile.unwrap_or_else(|| "mainnet".to_string());
+        let endpoints = Self::profile(&profile).ok_or(format!(
+            "unknown endpoint '{}', expected one of {}",
+            profile,
+            PROFILES.join(", ")
+        ))?;
+        Ok(Self {
+            venue: endpoints.venue,
+            market: endpoints.market,
+            ws_api_url: ws_api_url.or(endpoints.ws_api_url),
+            api_key: env("BINANCE_API_KEY"),
+            ..Self::custom(
+                ws_url.as_deref().unwrap_or(&endpoints.ws_url),
+                rest_url.as_deref().unwrap_or(&endpoints.rest_url),
+            )
+        })
+    }
+
+    // Base URL of the REST API, e.g. `https://api.binance.com/api/v3`
+    pub fn api_url(&self) -> String {
+        format!("{}{}", self.rest_url, self.market.api_path())
+    }
+
+    // URL for creating, keeping alive and closing a spot user data stream's listenKey
+    pub fn listen_key_url(&self) -> String {
+        format!("{}/userDataStream", self.api_url())
+    }
+
+    // URL of a raw stream connection; an empty stream opens one to subscribe on later
+    pub fn raw_url(&self, stream: &str) -> String {
+        format!("{}/ws/{}", self.ws_url, stream)
+    }
+
+    // URL of a combined stream connection, whose events are wrapped with their stream
+    pub fn combined_url(&self, streams: &[String]) -> String {
+        format!("{}/stream?streams={}", self.ws_url, streams.join("/"))
+    }
+}
+
+impl Default for Endpoints {
+    fn default() -> Self {
+        Self::profile("mainnet").unwrap()
+    }
+}
+
+impl fmt::Display for Endpoints {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} | {} | {}",
+            self.venue, self.market, self.ws_url, self.rest_url
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_endpoints_from_profiles_and_overrides() {
+        let no_env = |_: &str| None;
+        assert_eq!(
+            Endpoints::from_args(args(&[]), no_env).unwrap(),
+            Endpoints::default()
+        );
+
+        let us = Endpoints::from_args(args(&["--endpoint", "binance-us"]), no_env).unwrap();
+        assert_eq!(
+            us.raw_url("btcusd@trade"),
+            "wss://stream.binance.us:9443/ws/btcusd@trade"
+        );
+        assert_eq!(us.rest_url, "https://api.binance.us");
+        assert_eq!(
+            us.ws_api_url.as_deref(),
+            Some("wss://ws-api.binance.us:443/ws-api/v3")
+        );
+
+        // URLs override the profile, from the command line over the environment
+        let env = |name: &str| match name {
+            "BINANCE_ENDPOINT" => Some("testnet".to_string()),
+            "BINANCE_WS_URL" => Some("ws://127.0.0.1:1".to_string()),
+            _ => None,
+        };
+        let local = Endpoints::from_args(args(&["--ws-url", "ws://127.0.0.1:9000/"]), env).unwrap();
+        assert_eq!(
+            local.combined_url(&args(&["a@trade", "b@trade"])),
+            "ws://127.0.0.1:9000/stream?streams=a@trade/b@trade"
+        );
+        assert_eq!(local.rest_url, "https://testnet.binance.vision");
+        assert_eq!(local.api_key, None);
+        assert_eq!(
+            local.ws_api_url.as_deref(),
+            Some("wss://ws-api.testnet.binance.vision/ws-api/v3")
+        );
+        assert_eq!(
+            local.listen_key_url(),
+            "https://testnet.binance.vision/api/v3/userDataStream"
+        );
+
+        // Futures profiles keep their market when pointed elsewhere
+        let futures = Endpoints::from_args(
+            args(&["--endpoint", "usdm", "--rest-url", "http://127.0.0.1:8000"]),
+            no_env,
+        )
+        .unwrap();
+        assert_eq!(futures.market, Market::UsdFutures);
+        assert_eq!(futures.api_url(), "http://127.0.0.1:8000/fapi/v1");
+        assert_eq!(futures.raw_url(""), "wss://fstream.binance.com/ws/");
+        assert_eq!(futures.ws_api_url, None);
+
+        let coinm = Endpoints::from_args(args(&["--endpoint", "coinm"]), no_env).unwrap();
+        assert_eq!(coinm.market, Market::CoinFutures);
+        assert_eq!(coinm.api_url(), "https://dapi.binance.com/dapi/v1");
+        assert_eq!(
+            coinm.raw_url("btcusd_perp@aggTrade"),
+            "wss://dstream.binance.com/ws/btcusd_perp@aggTrade"
+        );
+
+        // Another venue keeps its feed format when pointed at a local server
+        let coinbase = Endpoints::from_args(
+            args(&["--endpoint", "coinbase", "--ws-url", "ws://127.0.0.1:9000"]),
+            no_env,
+        )
+        .unwrap();
+        assert_eq!(coinbase.venue, Venue::Coinbase);
+        assert_eq!(coinbase.ws_url, "ws://127.0.0.1:9000");
+        assert_eq!(coinbase.rest_url, "https://api.exchange.coinbase.com");
+
+        assert!(Endpoints::from_args(args(&["--endpoint", "moon"]), no_env).is_err());
+        assert!(Endpoints::from_args(args(&["--rest-url"]), no_env).is_err());
+        assert!(Endpoints::from_args(args(&["--verbose"]), no_env).is_err());
+    }
+}
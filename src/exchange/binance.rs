@@ -0,0 +1,109 @@
+use crate::endpoints::Endpoints;
+use crate::exchange::{Channel, Exchange, Reply};
+use crate::subscription::{fetch_symbols, SymbolInfo};
+use crate::websocket::client::connection::ConnectionCommand;
+use crate::websocket::client::requests::parse_reply;
+use crate::websocket::message::{parse_frame, StreamFrame};
+use crate::websocket::subscriptions::{
+    get_property_message, list_subscriptions_message, set_property_message, subscribe_message,
+    unsubscribe_message,
+};
+use futures_util::future::BoxFuture;
+use futures_util::FutureExt;
+use serde_json::Value;
+
+/// Binance spot and futures streams, through the endpoints' URLs and market
+#[derive(Debug, Clone, Default)]
+pub struct Binance {
+    endpoints: Endpoints,
+}
+
+impl Binance {
+    // Create a Binance adapter for the given endpoints
+    pub fn new(endpoints: Endpoints) -> Self {
+        Self { endpoints }
+    }
+}
+
+impl Exchange for Binance {
+    fn name(&self) -> &'static str {
+        "Binance"
+    }
+
+    fn fetch_symbols(&self) -> BoxFuture<'_, Result<Vec<SymbolInfo>, reqwest::Error>> {
+        async move { fetch_symbols(&self.endpoints.api_url()).await }.boxed()
+    }
+
+    fn stream_name(&self, symbol: &str, channel: Channel) -> String {
+        let stream_type = match channel {
+            Channel::Trades => "trade",
+            Channel::BookTicker => "bookTicker",
+        };
+        format!("{}@{}", symbol.to_lowercase(), stream_type)
+    }
+
+    fn stream_url(&self, streams: &[String]) -> String {
+        match streams {
+            [stream] => self.endpoints.raw_url(stream),
+            _ => self.endpoints.combined_url(streams),
+        }
+    }
+
+    fn request_message(&self, command: &ConnectionCommand, id: u64) -> Option<String> {
+        Some(match command {
+            ConnectionCommand::Subscribe(streams) => subscribe_message(streams, id),
+            ConnectionCommand::Unsubscribe(streams) => unsubscribe_message(streams, id),
+            ConnectionCommand::ListSubscriptions => list_subscriptions_message(id),
+            ConnectionCommand::SetCombined(combined) => {
+                set_property_message("combined", Value::Bool(*combined), id)
+            }
+            ConnectionCommand::GetProperty(property) => get_property_message(property, id),
+        })
+    }
+
+    fn parse_reply(&self, text: &str) -> Option<Reply> {
+        parse_reply(text).map(|(id, result)| (Some(id), result))
+    }
+
+    fn parse_frame(&self, text: &str) -> Result<Option<StreamFrame>, serde_json::Error> {
+        parse_frame(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::websocket::message::MarketEvent;
+
+    #[test]
+    fn test_binance_streams_and_frames() {
+        let binance = Binance::default();
+        let streams = vec![
+            binance.stream_name("BTCUSDT", Channel::Trades),
+            binance.stream_name("btcusdt", Channel::BookTicker),
+        ];
+        assert_eq!(streams, ["btcusdt@trade", "btcusdt@bookTicker"]);
+        assert_eq!(
+            binance.stream_url(&streams[..1]),
+            "wss://stream.binance.com:9443/ws/btcusdt@trade"
+        );
+
+        let subscribe = ConnectionCommand::Subscribe(streams);
+        let message: Value =
+            serde_json::from_str(&binance.request_message(&subscribe, 7).unwrap()).unwrap();
+        assert_eq!(message["method"], "SUBSCRIBE");
+        assert_eq!(message["id"], 7);
+        assert_eq!(
+            binance.parse_reply(r#"{"result":null,"id":7}"#),
+            Some((Some(7), Ok(Value::Null)))
+        );
+
+        let frame = binance
+            .parse_frame(r#"{"u":400900217,"s":"BNBUSDT","b":"25.35190000","B":"31.21000000","a":"25.36520000","A":"40.66000000"}"#)
+            .unwrap()
+            .unwrap();
+        assert!(
+            matches!(frame.event, MarketEvent::BookTicker(ticker) if ticker.ask_price == 25.3652)
+        );
+    }
+}
@@ -0,0 +1,387 @@
+use crate::endpoints::Endpoints;
+use crate::exchange::{Channel, Exchange, Reply};
+use crate::storage::trade_storage::Trade;
+use crate::subscription::SymbolInfo;
+use crate::websocket::client::connection::ConnectionCommand;
+use crate::websocket::client::requests::RequestError;
+use crate::websocket::deserialize::{datetime_from_rfc3339, f64_from_str};
+use crate::websocket::message::{BookTicker, MarketEvent, StreamFrame};
+use chrono::{DateTime, Utc};
+use futures_util::future::BoxFuture;
+use futures_util::FutureExt;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+// Coinbase refuses REST requests without a User-Agent
+const USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
+/// Coinbase Exchange public feed. Streams are named like Binance's, e.g.
+/// `btc-usd@matches`, and grouped by channel into one subscribe message.
+#[derive(Debug, Clone)]
+pub struct Coinbase {
+    endpoints: Endpoints,
+}
+
+impl Coinbase {
+    // Create a Coinbase adapter for the given endpoints
+    pub fn new(endpoints: Endpoints) -> Self {
+        Self { endpoints }
+    }
+}
+
+/// Trading pair listed by `/products`
+#[derive(Deserialize)]
+struct Product {
+    id: String,
+    base_currency: String,
+    quote_currency: String,
+    status: String,
+}
+
+/// Messages of the feed, tagged with their `type`
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+enum FeedMessage {
+    // The most recent trade is sent as `last_match` right after subscribing
+    #[serde(rename = "match", alias = "last_match")]
+    Match(Match),
+    #[serde(rename = "ticker")]
+    Ticker(Ticker),
+    #[serde(rename = "subscriptions")]
+    Subscriptions { channels: Value },
+    #[serde(rename = "error")]
+    Error {
+        message: String,
+        #[serde(default)]
+        reason: Option<String>,
+    },
+    // Heartbeats and status updates carry no market data
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize)]
+struct Match {
+    trade_id: u64,
+    // Side of the resting order, so `buy` means the buyer was the maker
+    side: String,
+    #[serde(deserialize_with = "f64_from_str")]
+    size: f64,
+    #[serde(deserialize_with = "f64_from_str")]
+    price: f64,
+    product_id: String,
+    #[serde(deserialize_with = "datetime_from_rfc3339")]
+    time: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+struct Ticker {
+    sequence: u64,
+    product_id: String,
+    #[serde(deserialize_with = "f64_from_str")]
+    best_bid: f64,
+    #[serde(deserialize_with = "f64_from_str")]
+    best_bid_size: f64,
+    #[serde(deserialize_with = "f64_from_str")]
+    best_ask: f64,
+    #[serde(deserialize_with = "f64_from_str")]
+    best_ask_size: f64,
+}
+
+// Fetch every product still trading from the REST API at `rest_url`
+async fn fetch_products(rest_url: &str) -> Result<Vec<SymbolInfo>, reqwest::Error> {
+    let client = reqwest::Client::builder().user_agent(USER_AGENT).build()?;
+    let products = client
+        .get(format!("{}/products", rest_url))
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<Vec<Product>>()
+        .await?;
+    let mut symbols: Vec<SymbolInfo> = products
+        .into_iter()
+        .filter(|product| product.status == "online")
+        .map(|product| SymbolInfo {
+            symbol: product.id.to_lowercase(),
+            base_asset: product.base_currency,
+            quote_asset: product.quote_currency,
+            pair: None,
+            contract_type: None,
+            contract_size: None,
+        })
+        .collect();
+    symbols.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+    Ok(symbols)
+}
+
+// Build a subscribe or unsubscribe message, grouping the streams' products by channel
+fn channel_message(message_type: &str, streams: &[String]) -> String {
+    let mut channels: Vec<(&str, Vec<String>)> = Vec::new();
+    for (product, channel) in streams.iter().filter_map(|stream| stream.split_once('@')) {
+        let product = product.to_uppercase();
+        match channels.iter_mut().find(|(name, _)| *name == channel) {
+            Some((_, products)) => products.push(product),
+            None => channels.push((channel, vec![product])),
+        }
+    }
+    let channels: Vec<Value> = channels
+        .into_iter()
+        .map(|(name, product_ids)| json!({ "name": name, "product_ids": product_ids }))
+        .collect();
+    json!({ "type": message_type, "channels": channels }).to_string()
+}
+
+impl Exchange for Coinbase {
+    fn name(&self) -> &'static str {
+        "Coinbase"
+    }
+
+    fn fetch_symbols(&self) -> BoxFuture<'_, Result<Vec<SymbolInfo>, reqwest::Error>> {
+        async move { fetch_products(&self.endpoints.rest_url).await }.boxed()
+    }
+
+    fn stream_name(&self, symbol: &str, channel: Channel) -> String {
+        let channel = match channel {
+            Channel::Trades => "matches",
+            Channel::BookTicker => "ticker",
+        };
+        format!("{}@{}", symbol.to_lowercase(), channel)
+    }
+
+    // The feed has a single endpoint; everything is chosen by subscribe messages
+    fn stream_url(&self, _streams: &[String]) -> String {
+        self.endpoints.ws_url.clone()
+    }
+
+    // The feed has no ids, subscription listing or payload format settings
+    fn request_message(&self, command: &ConnectionCommand, _id: u64) -> Option<String> {
+        match command {
+            ConnectionCommand::Subscribe(streams) => Some(channel_message("subscribe", streams)),
+            ConnectionCommand::Unsubscribe(streams) => {
+                Some(channel_message("unsubscribe", streams))
+            }
+            _ => None,
+        }
+    }
+
+    fn parse_reply(&self, text: &str) -> Option<Reply> {
+        // Market data is far more frequent than replies, so skip the full decode for it
+        if !text.contains("\"subscriptions\"") && !text.contains("\"error\"") {
+            return None;
+        }
+        match serde_json::from_str(text).ok()? {
+            FeedMessage::Subscriptions { channels } => Some((None, Ok(channels))),
+            FeedMessage::Error { message, reason } => {
+                let msg = match reason {
+                    Some(reason) => format!("{}: {}", message, reason),
+                    None => message,
+                };
+                Some((None, Err(RequestError::Rejected { code: 0, msg })))
+            }
+            _ => None,
+        }
+    }
+
+    fn parse_frame(&self, text: &str) -> Result<Option<StreamFrame>, serde_json::Error> {
+        let frame = match serde_json::from_str(text)? {
+            FeedMessage::Match(trade) => StreamFrame {
+                stream: Some(self.stream_name(&trade.product_id, Channel::Trades)),
+                event: MarketEvent::Trade(Trade {
+                    symbol: trade.product_id,
+                    trade_id: trade.trade_id,
+                    price: trade.price,
+                    quantity: trade.size,
+                    // Order ids are UUIDs, which the trade dashboard does not show
+                    buyer_order_id: None,
+                    seller_order_id: None,
+                    order_type: None,
+                    timestamp: trade.time,
+                    is_buyer_maker: trade.side == "buy",
+                }),
+            },
+            FeedMessage::Ticker(ticker) => StreamFrame {
+                stream: Some(self.stream_name(&ticker.product_id, Channel::BookTicker)),
+                event: MarketEvent::BookTicker(BookTicker {
+                    update_id: ticker.sequence,
+                    symbol: ticker.product_id,
+                    bid_price: ticker.best_bid,
+                    bid_quantity: ticker.best_bid_size,
+                    ask_price: ticker.best_ask,
+                    ask_quantity: ticker.best_ask_size,
+                }),
+            },
+            // Replies are handled by the connection
+            _ => return Ok(None),
+        };
+        Ok(Some(frame))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{spawn_http_server, spawn_ws_sessions};
+    use crate::websocket::client::backoff::Backoff;
+    use crate::websocket::client::connection::{run_connection, ConnectionConfig, ConnectionEvent};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::sync::{mpsc, watch};
+
+    const FEED: &str = include_str!("fixtures/coinbase_feed.jsonl");
+    const PRODUCTS: &str = include_str!("fixtures/coinbase_products.json");
+
+    fn coinbase(ws_url: &str, rest_url: &str) -> Coinbase {
+        Coinbase::new(Endpoints::custom(ws_url, rest_url))
+    }
+
+    #[test]
+    fn test_recorded_feed_decodes_into_market_events() {
+        let coinbase = coinbase("ws://127.0.0.1:1", "http://127.0.0.1:1");
+        let mut replies = Vec::new();
+        let mut frames = Vec::new();
+        for line in FEED.lines() {
+            match coinbase.parse_reply(line) {
+                Some(reply) => replies.push(reply),
+                None => frames.extend(coinbase.parse_frame(line).unwrap()),
+            }
+        }
+
+        assert!(matches!(&replies[0], (None, Ok(Value::Array(channels))) if channels.len() == 2));
+        assert_eq!(
+            replies[1],
+            (
+                None,
+                Err(RequestError::Rejected {
+                    code: 0,
+                    msg: "Failed to subscribe: DOGE-EUR is not a valid product".to_string()
+                })
+            )
+        );
+
+        let sources: Vec<String> = frames.iter().map(StreamFrame::source).collect();
+        assert_eq!(
+            sources,
+            [
+                "btc-usd@matches",
+                "eth-usd@matches",
+                "btc-usd@ticker",
+                "btc-usd@matches",
+                "btc-usd@matches",
+                "btc-usd@ticker",
+                "eth-usd@matches"
+            ]
+        );
+        match &frames[3].event {
+            MarketEvent::Trade(trade) => {
+                assert_eq!(trade.symbol, "BTC-USD");
+                assert_eq!(trade.trade_id, 640213875);
+                assert_eq!((trade.price, trade.quantity), (61852.41, 0.0125));
+                // A resting sell was hit, so the buyer took liquidity
+                assert!(!trade.is_buyer_maker);
+                assert_eq!(trade.timestamp.timestamp_micros(), 1715677923601224);
+            }
+            event => panic!("expected a trade, got {:?}", event),
+        }
+        match &frames[5].event {
+            MarketEvent::BookTicker(ticker) => {
+                assert_eq!(ticker.update_id, 78216522431);
+                assert_eq!((ticker.bid_price, ticker.bid_quantity), (61852.39, 0.5182));
+                assert_eq!(ticker.ask_price, 61852.41);
+            }
+            event => panic!("expected a book ticker, got {:?}", event),
+        }
+        assert_eq!(frames[5].stream_symbol().as_deref(), Some("BTC-USD"));
+
+        assert_eq!(
+            coinbase.parse_frame(r#"{"type":"heartbeat","sequence":90,"last_trade_id":20,"product_id":"BTC-USD","time":"2024-05-14T09:12:04.000000Z"}"#).unwrap().map(|frame| frame.source()),
+            None
+        );
+        assert!(coinbase
+            .parse_frame(r#"{"type":"match","trade_id":1}"#)
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_products_skips_delisted_ones() {
+        let rest_url = spawn_http_server(vec![("/products", PRODUCTS.to_string())]).await;
+
+        let symbols = coinbase("ws://127.0.0.1:1", &rest_url)
+            .fetch_symbols()
+            .await
+            .unwrap();
+        let names: Vec<&str> = symbols.iter().map(|info| info.symbol.as_str()).collect();
+        assert_eq!(names, ["btc-usd", "eth-usd"]);
+        assert_eq!(symbols[1].quote_asset, "USD");
+    }
+
+    #[tokio::test]
+    async fn test_subscribes_through_the_shared_connection() {
+        // The server answers the subscribe message with the first two recorded lines
+        let (url, mut received) = spawn_ws_sessions(
+            vec![FEED.lines().take(2).map(str::to_string).collect()],
+            false,
+        )
+        .await;
+        let exchange = coinbase(&url, "http://127.0.0.1:1");
+        let streams = vec![
+            exchange.stream_name("BTC-USD", Channel::Trades),
+            exchange.stream_name("eth-usd", Channel::Trades),
+            exchange.stream_name("btc-usd", Channel::BookTicker),
+        ];
+        let config = ConnectionConfig {
+            url: exchange.stream_url(&streams),
+            streams,
+            base_id: 1,
+            rotate_after: Duration::from_secs(60),
+            rotation_overlap: Duration::from_millis(100),
+            rotation_retry: Duration::from_secs(30),
+            silence_timeout: Duration::from_secs(60),
+            request_timeout: Duration::from_secs(10),
+            combined: None,
+            exchange: Arc::new(exchange),
+        };
+        let (events_tx, mut events_rx) = mpsc::channel(16);
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let (_commands_tx, commands_rx) = mpsc::channel(16);
+        let connection = tokio::spawn(run_connection(
+            config.clone(),
+            events_tx,
+            commands_rx,
+            shutdown_rx,
+            Backoff::new(Duration::from_millis(10), Duration::from_millis(20)),
+        ));
+
+        let subscribe: Value = serde_json::from_str(&received.recv().await.unwrap()).unwrap();
+        assert_eq!(
+            subscribe,
+            json!({
+                "type": "subscribe",
+                "channels": [
+                    { "name": "matches", "product_ids": ["BTC-USD", "ETH-USD"] },
+                    { "name": "ticker", "product_ids": ["BTC-USD"] }
+                ]
+            })
+        );
+
+        // The id-less reply resolves the subscription, then market data follows
+        let mut reply = None;
+        let text = loop {
+            match events_rx.recv().await.unwrap() {
+                ConnectionEvent::Reply { command, result } => reply = Some((command, result)),
+                ConnectionEvent::Text(text) => break text,
+                _ => {}
+            }
+        };
+        let (command, result) = reply.unwrap();
+        assert_eq!(
+            command,
+            ConnectionCommand::Subscribe(config.streams.clone())
+        );
+        assert!(result.is_ok());
+        let frame = config.exchange.parse_frame(&text).unwrap().unwrap();
+        assert!(matches!(frame.event, MarketEvent::Trade(trade) if trade.trade_id == 640213874));
+
+        shutdown_tx.send(true).unwrap();
+        connection.await.unwrap();
+    }
+}
@@ -0,0 +1,73 @@
+//! Adapters for the venues the dashboards can show. Everything that differs between
+//! exchanges — symbol discovery, stream names, the subscription protocol and the payload
+//! format — lives behind `Exchange`, which decodes frames into the same `MarketEvent`s
+//! the storage and dashboards already handle.
+
+pub mod binance;
+pub mod coinbase;
+
+use crate::endpoints::{Endpoints, Venue};
+use crate::subscription::SymbolInfo;
+use crate::websocket::client::connection::ConnectionCommand;
+use crate::websocket::client::requests::RequestError;
+use crate::websocket::message::StreamFrame;
+use binance::Binance;
+use coinbase::Coinbase;
+use futures_util::future::BoxFuture;
+use serde_json::Value;
+use std::fmt;
+use std::sync::Arc;
+
+/// Kind of market data a stream carries, named the same way on every venue
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Channel {
+    /// Every executed trade
+    Trades,
+    /// Best bid and ask with their quantities
+    BookTicker,
+}
+
+impl fmt::Display for Channel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Channel::Trades => write!(f, "Trades"),
+            Channel::BookTicker => write!(f, "Best bid/ask"),
+        }
+    }
+}
+
+/// Reply to a control request. Venues without request ids answer in the order the
+/// requests were sent, so their replies carry `None` and resolve the oldest request.
+pub type Reply = (Option<u64>, Result<Value, RequestError>);
+
+/// What the connection layer and dashboards need to know about a venue
+pub trait Exchange: fmt::Debug + Send + Sync {
+    // Name shown in errors about requests the venue does not support
+    fn name(&self) -> &'static str;
+
+    // Every symbol the venue lists, lowercased
+    fn fetch_symbols(&self) -> BoxFuture<'_, Result<Vec<SymbolInfo>, reqwest::Error>>;
+
+    // Name of the stream carrying `channel` for `symbol`
+    fn stream_name(&self, symbol: &str, channel: Channel) -> String;
+
+    // URL of a connection that will carry `streams`
+    fn stream_url(&self, streams: &[String]) -> String;
+
+    // Text of the request carrying out `command` under `id`, if the venue supports it
+    fn request_message(&self, command: &ConnectionCommand, id: u64) -> Option<String>;
+
+    // Decode a reply to a control request; `None` for anything else
+    fn parse_reply(&self, text: &str) -> Option<Reply>;
+
+    // Decode a text frame into a market event; `Ok(None)` for frames without market data
+    fn parse_frame(&self, text: &str) -> Result<Option<StreamFrame>, serde_json::Error>;
+}
+
+/// Returns the adapter for the venue the endpoints belong to
+pub fn for_endpoints(endpoints: &Endpoints) -> Arc<dyn Exchange> {
+    match endpoints.venue {
+        Venue::Binance => Arc::new(Binance::new(endpoints.clone())),
+        Venue::Coinbase => Arc::new(Coinbase::new(endpoints.clone())),
+    }
+}
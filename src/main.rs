@@ -1,16 +1,32 @@
+mod endpoints;
+mod exchange;
 mod menu;
+mod rest;
 mod storage;
 mod subscription;
+#[cfg(test)]
+mod test_support;
 mod ui;
+mod user_data;
 mod websocket;
 
+use endpoints::Endpoints;
 use menu::show_menu;
 
 #[tokio::main]
 async fn main() {
-    let symbols = subscription::fetch_symbols().await;
+    let endpoints =
+        match Endpoints::from_args(std::env::args().skip(1), |name| std::env::var(name).ok()) {
+            Ok(endpoints) => endpoints,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                return;
+            }
+        };
+
+    let symbols = exchange::for_endpoints(&endpoints).fetch_symbols().await;
     match symbols {
-        Ok(symbols) => show_menu(&symbols).await,
+        Ok(symbols) => show_menu(&symbols, &endpoints).await,
         Err(e) => eprintln!("Error fetching symbols: {}", e),
     }
 }
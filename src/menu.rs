@@ -1,40 +1,155 @@
-use crate::storage::aggtrade_storage::AggTradeStorage;
-use crate::websocket::client::run::run;
-use crate::websocket::client::{BINANCE_WS_COMBINED_URL, BINANCE_WS_URL};
+use crate::endpoints::{Endpoints, Market, Venue};
+use crate::exchange::{self, Channel};
+use crate::storage::contract_sizes::ContractSizes;
+use crate::storage::market_storage::MarketStorage;
+use crate::subscription::SymbolInfo;
+use crate::websocket::client::pool::connections_needed;
+use crate::websocket::client::requests::RequestError;
+use crate::websocket::client::run::{run, run_sharded, run_with_standby};
+use crate::websocket::client::ws_api::WsApiClient;
 use inquire::{MultiSelect, Select};
 use std::io::{self, Write};
 use std::sync::{Arc, RwLock};
+use std::time::Duration;
 use tokio::time::Sleep;
 
+// Time the WebSocket API has to answer a query
+const WS_API_TIMEOUT: Duration = Duration::from_secs(10);
+
 /// Displays the main menu and processes user selections
-pub async fn show_menu(symbols: &Vec<String>) {
-    const OPTIONS: [&str; 7] = [
+pub async fn show_menu(symbol_infos: &[SymbolInfo], endpoints: &Endpoints) {
+    const OPTIONS: [&str; 13] = [
         "Subscribe to aggTrade",
         "Subscribe to trade",
         "Subscribe to kline",
+        "Subscribe to rolling window ticker",
+        "Subscribe to depth",
+        "Subscribe to order book",
+        "Subscribe to bookTicker",
+        "Market Overview",
         "Custom Subscribe",
+        "Query Symbol",
         "List Symbols",
         "List Subscriptions",
         "Exit",
     ];
+    // USDⓈ-M futures have no trade or rolling window streams, but add funding and liquidations
+    const FUTURES_OPTIONS: [&str; 14] = [
+        "Subscribe to aggTrade",
+        "Subscribe to kline",
+        "Subscribe to continuous kline",
+        "Subscribe to mark price",
+        "Subscribe to liquidations",
+        "Subscribe to composite index",
+        "Subscribe to depth",
+        "Subscribe to order book",
+        "Subscribe to bookTicker",
+        "Market Overview",
+        "Custom Subscribe",
+        "List Symbols",
+        "List Subscriptions",
+        "Exit",
+    ];
+    // Other venues offer the normalized channels only
+    const VENUE_OPTIONS: [&str; 5] = [
+        "Subscribe to trade",
+        "Subscribe to bookTicker",
+        "Custom Subscribe",
+        "List Symbols",
+        "Exit",
+    ];
+    let options = match endpoints.market {
+        _ if endpoints.venue != Venue::Binance => VENUE_OPTIONS.to_vec(),
+        Market::Spot => OPTIONS.to_vec(),
+        Market::UsdFutures => FUTURES_OPTIONS.to_vec(),
+        // COIN-M has no composite indexes
+        Market::CoinFutures => FUTURES_OPTIONS
+            .into_iter()
+            .filter(|option| *option != "Subscribe to composite index")
+            .collect(),
+    };
 
-    let storage = Arc::new(RwLock::new(AggTradeStorage::new(1000)));
+    let symbols: Vec<String> = symbol_infos
+        .iter()
+        .map(|info| info.symbol.clone())
+        .collect();
+    let symbols = symbols.as_slice();
+    // Continuous contract and COIN-M mark price streams are named after the pair
+    let mut pairs: Vec<String> = symbol_infos
+        .iter()
+        .map(SymbolInfo::pair_or_symbol)
+        .collect();
+    pairs.sort();
+    pairs.dedup();
+    let pairs = pairs.as_slice();
+    let storage = Arc::new(RwLock::new(MarketStorage::new(1000)));
+    storage
+        .write()
+        .unwrap()
+        .set_contract_sizes(ContractSizes::new(
+            symbol_infos
+                .iter()
+                .filter_map(|info| Some((info.pair_or_symbol(), info.contract_size?))),
+        ));
+    storage.write().unwrap().tickers.set_quote_assets(
+        symbol_infos
+            .iter()
+            .map(|info| (info.symbol.clone(), info.quote_asset.clone())),
+    );
 
     loop {
         clear_screen();
         println!("==============================");
         println!("       Binance WebSocket      ");
         println!("==============================");
+        println!("Endpoints: {}", endpoints);
 
-        let choice = Select::new("Choose an option:", OPTIONS.to_vec()).prompt();
+        let choice = Select::new("Choose an option:", options.clone()).prompt();
 
         match choice {
             Ok(option) => match option {
-                "Subscribe to aggTrade" => subscribe("aggTrade", &storage, symbols).await,
-                "Subscribe to trade" => subscribe("trade", &storage, symbols).await,
-                "Subscribe to kline" => subscribe_with_interval("kline", &storage, symbols).await,
-                "Custom Subscribe" => custom_subscribe(&storage, symbols).await,
-                "List Symbols" => list_symbols(symbols),
+                "Subscribe to trade" if endpoints.venue != Venue::Binance => {
+                    subscribe_to_channel(Channel::Trades, &storage, symbols, endpoints).await
+                }
+                "Subscribe to bookTicker" if endpoints.venue != Venue::Binance => {
+                    subscribe_to_channel(Channel::BookTicker, &storage, symbols, endpoints).await
+                }
+                "Custom Subscribe" if endpoints.venue != Venue::Binance => {
+                    subscribe_to_channels(&storage, symbols, endpoints).await
+                }
+                "Subscribe to aggTrade" => {
+                    subscribe("aggTrade", &storage, symbols, endpoints).await
+                }
+                "Subscribe to trade" => subscribe("trade", &storage, symbols, endpoints).await,
+                "Subscribe to kline" => {
+                    subscribe_with_interval("kline", &storage, symbols, endpoints).await
+                }
+                "Subscribe to rolling window ticker" => {
+                    subscribe_to_window_ticker(&storage, symbols, endpoints).await
+                }
+                "Subscribe to depth" => subscribe_to_depth(&storage, symbols, endpoints).await,
+                "Subscribe to order book" => {
+                    subscribe_to_order_book(&storage, symbols, endpoints).await
+                }
+                "Subscribe to bookTicker" => {
+                    subscribe_to_book_ticker(&storage, symbols, endpoints).await
+                }
+                "Subscribe to continuous kline" => {
+                    subscribe_to_continuous_kline(&storage, pairs, endpoints).await
+                }
+                "Subscribe to mark price" => {
+                    subscribe_to_mark_price(&storage, symbols, pairs, endpoints).await
+                }
+                "Subscribe to liquidations" => {
+                    subscribe_to_liquidations(&storage, symbols, endpoints).await
+                }
+                "Subscribe to composite index" => {
+                    subscribe_to_composite_index(&storage, symbols, endpoints).await
+                }
+                "Market Overview" => market_overview(&storage, symbol_infos, endpoints).await,
+                "Custom Subscribe" => custom_subscribe(&storage, symbols, endpoints).await,
+                "Query Symbol" => query_symbol(symbols, endpoints).await,
+                "List Symbols" => list_symbols(symbol_infos),
                 "List Subscriptions" => list_subscriptions(&storage),
                 "Exit" => break,
                 _ => unreachable!(),
@@ -50,20 +165,28 @@ pub async fn show_menu(symbols: &Vec<String>) {
 /// Subscribes to a single stream type (aggTrade, trade)
 async fn subscribe(
     stream_type: &str,
-    storage: &Arc<RwLock<AggTradeStorage>>,
-    symbols: &Vec<String>,
+    storage: &Arc<RwLock<MarketStorage>>,
+    symbols: &[String],
+    endpoints: &Endpoints,
 ) {
     if let Some(symbol) = select_symbol(symbols) {
-        let url = format!("{}{}@{}", BINANCE_WS_URL, symbol, stream_type);
-        process_subscription(&url, &vec![format!("{}@{}", symbol, stream_type)], storage).await;
+        let url = endpoints.raw_url(&format!("{}@{}", symbol, stream_type));
+        process_subscription(
+            endpoints,
+            &url,
+            &vec![format!("{}@{}", symbol, stream_type)],
+            storage,
+        )
+        .await;
     }
 }
 
 /// Subscribes to a stream type with interval (kline)
 async fn subscribe_with_interval(
     stream_type: &str,
-    storage: &Arc<RwLock<AggTradeStorage>>,
-    symbols: &Vec<String>,
+    storage: &Arc<RwLock<MarketStorage>>,
+    symbols: &[String],
+    endpoints: &Endpoints,
 ) {
     let symbol_selection = select_symbol(symbols);
     if let Some(symbol) = symbol_selection {
@@ -72,8 +195,9 @@ async fn subscribe_with_interval(
             "1w", "1M",
         ];
         if let Some(interval) = select_interval(INTERVALS.to_vec()) {
-            let url = format!("{}{}@{}_{}", BINANCE_WS_URL, symbol, stream_type, interval);
+            let url = endpoints.raw_url(&format!("{}@{}_{}", symbol, stream_type, interval));
             process_subscription(
+                endpoints,
                 &url,
                 &vec![format!("{}@{}_{}", symbol, stream_type, interval)],
                 storage,
@@ -83,12 +207,269 @@ async fn subscribe_with_interval(
     }
 }
 
+/// Subscribes to rolling window statistics for one or more window sizes
+async fn subscribe_to_window_ticker(
+    storage: &Arc<RwLock<MarketStorage>>,
+    symbols: &[String],
+    endpoints: &Endpoints,
+) {
+    if let Some(symbol) = select_symbol(symbols) {
+        let windows = MultiSelect::new("Choose window sizes:", vec!["1h", "4h", "1d"])
+            .prompt()
+            .unwrap_or_default();
+        if windows.is_empty() {
+            return;
+        }
+        let streams: Vec<String> = windows
+            .iter()
+            .map(|window| format!("{}@ticker_{}", symbol, window))
+            .collect();
+        let url = endpoints.combined_url(&streams);
+        process_subscription(endpoints, &url, &streams, storage).await;
+    }
+}
+
+/// Subscribes to a partial book depth stream (depth5/10/20)
+async fn subscribe_to_depth(
+    storage: &Arc<RwLock<MarketStorage>>,
+    symbols: &[String],
+    endpoints: &Endpoints,
+) {
+    if let Some(symbol) = select_symbol(symbols) {
+        if let Some(depth_stream) = select_depth() {
+            let stream = format!("{}@{}", symbol, depth_stream);
+            let url = endpoints.raw_url(&stream);
+            process_subscription(endpoints, &url, &vec![stream], storage).await;
+        }
+    }
+}
+
+/// Subscribes to the diff depth stream and maintains a local order book
+async fn subscribe_to_order_book(
+    storage: &Arc<RwLock<MarketStorage>>,
+    symbols: &[String],
+    endpoints: &Endpoints,
+) {
+    if let Some(symbol) = select_symbol(symbols) {
+        if let Some(speed) = select_speed() {
+            let stream = format!("{}@{}", symbol, diff_depth_stream(&speed));
+            let url = endpoints.raw_url(&stream);
+            process_subscription(endpoints, &url, &vec![stream], storage).await;
+        }
+    }
+}
+
+/// Subscribes to best bid/ask updates for one symbol or the whole market
+async fn subscribe_to_book_ticker(
+    storage: &Arc<RwLock<MarketStorage>>,
+    symbols: &[String],
+    endpoints: &Endpoints,
+) {
+    let scope = Select::new("Choose symbols:", vec!["Single symbol", "All symbols"])
+        .prompt()
+        .ok();
+    let stream = match scope {
+        Some("Single symbol") => {
+            select_symbol(symbols).map(|symbol| format!("{}@bookTicker", symbol))
+        }
+        Some(_) => Some("!bookTicker".to_string()),
+        None => None,
+    };
+    if let Some(stream) = stream {
+        let url = endpoints.raw_url(&stream);
+        process_subscription(endpoints, &url, &vec![stream], storage).await;
+    }
+}
+
+/// Subscribes to a perpetual or quarterly contract's candles for a pair
+async fn subscribe_to_continuous_kline(
+    storage: &Arc<RwLock<MarketStorage>>,
+    pairs: &[String],
+    endpoints: &Endpoints,
+) {
+    if let Some(pair) = select_symbol(pairs) {
+        let Ok(contract_type) = Select::new(
+            "Choose a contract:",
+            vec!["perpetual", "current_quarter", "next_quarter"],
+        )
+        .prompt() else {
+            return;
+        };
+        const INTERVALS: [&str; 15] = [
+            "1m", "3m", "5m", "15m", "30m", "1h", "2h", "4h", "6h", "8h", "12h", "1d", "3d", "1w",
+            "1M",
+        ];
+        if let Some(interval) = select_interval(INTERVALS.to_vec()) {
+            let stream = format!("{}_{}@continuousKline_{}", pair, contract_type, interval);
+            let url = endpoints.raw_url(&stream);
+            process_subscription(endpoints, &url, &vec![stream], storage).await;
+        }
+    }
+}
+
+/// Subscribes to mark price and funding for one contract or the whole market; COIN-M
+/// has no all-market stream, so there it covers every contract of a pair
+async fn subscribe_to_mark_price(
+    storage: &Arc<RwLock<MarketStorage>>,
+    symbols: &[String],
+    pairs: &[String],
+    endpoints: &Endpoints,
+) {
+    let stream = match select_scope() {
+        Some("Single symbol") => {
+            select_symbol(symbols).map(|symbol| format!("{}@markPrice@1s", symbol))
+        }
+        Some(_) if endpoints.market == Market::CoinFutures => {
+            select_symbol(pairs).map(|pair| format!("{}@markPrice@1s", pair))
+        }
+        Some(_) => Some("!markPrice@arr@1s".to_string()),
+        None => None,
+    };
+    if let Some(stream) = stream {
+        let url = endpoints.raw_url(&stream);
+        process_subscription(endpoints, &url, &vec![stream], storage).await;
+    }
+}
+
+/// Subscribes to liquidation orders for one contract or the whole market
+async fn subscribe_to_liquidations(
+    storage: &Arc<RwLock<MarketStorage>>,
+    symbols: &[String],
+    endpoints: &Endpoints,
+) {
+    let stream = match select_scope() {
+        Some("Single symbol") => {
+            select_symbol(symbols).map(|symbol| format!("{}@forceOrder", symbol))
+        }
+        Some(_) => Some("!forceOrder@arr".to_string()),
+        None => None,
+    };
+    if let Some(stream) = stream {
+        let url = endpoints.raw_url(&stream);
+        process_subscription(endpoints, &url, &vec![stream], storage).await;
+    }
+}
+
+/// Subscribes to a composite index such as DEFIUSDT and its constituents
+async fn subscribe_to_composite_index(
+    storage: &Arc<RwLock<MarketStorage>>,
+    symbols: &[String],
+    endpoints: &Endpoints,
+) {
+    if let Some(symbol) = select_symbol(symbols) {
+        let stream = format!("{}@compositeIndex", symbol);
+        let url = endpoints.raw_url(&stream);
+        process_subscription(endpoints, &url, &vec![stream], storage).await;
+    }
+}
+
+/// Subscribes to the all-market ticker array, filtered to a quote asset
+async fn market_overview(
+    storage: &Arc<RwLock<MarketStorage>>,
+    symbol_infos: &[SymbolInfo],
+    endpoints: &Endpoints,
+) {
+    let mut quote_assets: Vec<String> = symbol_infos
+        .iter()
+        .map(|info| info.quote_asset.clone())
+        .collect();
+    quote_assets.sort();
+    quote_assets.dedup();
+    quote_assets.insert(0, "All".to_string());
+
+    let Ok(quote_asset) = Select::new("Choose a quote asset:", quote_assets).prompt() else {
+        return;
+    };
+    let Ok(stream) =
+        Select::new("Choose a ticker:", vec!["!ticker@arr", "!miniTicker@arr"]).prompt()
+    else {
+        return;
+    };
+
+    storage
+        .write()
+        .unwrap()
+        .tickers
+        .set_quote_filter(Some(quote_asset).filter(|asset| asset != "All"));
+    let url = endpoints.raw_url(stream);
+    process_subscription(endpoints, &url, &vec![stream.to_string()], storage).await;
+}
+
+/// Subscribes to one channel of a symbol, named the way the venue names it
+async fn subscribe_to_channel(
+    channel: Channel,
+    storage: &Arc<RwLock<MarketStorage>>,
+    symbols: &[String],
+    endpoints: &Endpoints,
+) {
+    if let Some(symbol) = select_symbol(symbols) {
+        let exchange = exchange::for_endpoints(endpoints);
+        let streams = vec![exchange.stream_name(&symbol, channel)];
+        let url = exchange.stream_url(&streams);
+        process_subscription(endpoints, &url, &streams, storage).await;
+    }
+}
+
+/// Subscribes to several channels of several symbols over one connection
+async fn subscribe_to_channels(
+    storage: &Arc<RwLock<MarketStorage>>,
+    symbols: &[String],
+    endpoints: &Endpoints,
+) {
+    let selected_symbols = MultiSelect::new("Choose symbols:", symbols.to_vec())
+        .prompt()
+        .unwrap_or_default();
+    let channels = MultiSelect::new(
+        "Choose channels:",
+        vec![Channel::Trades, Channel::BookTicker],
+    )
+    .prompt()
+    .unwrap_or_default();
+
+    let exchange = exchange::for_endpoints(endpoints);
+    let streams: Vec<String> = selected_symbols
+        .iter()
+        .flat_map(|symbol| {
+            channels
+                .iter()
+                .map(|&channel| exchange.stream_name(symbol, channel))
+        })
+        .collect();
+    if streams.is_empty() {
+        return;
+    }
+    let url = exchange.stream_url(&streams);
+    process_subscription(endpoints, &url, &streams, storage).await;
+}
+
 /// Subscribes to multiple custom streams
-async fn custom_subscribe(storage: &Arc<RwLock<AggTradeStorage>>, symbols: &Vec<String>) {
+async fn custom_subscribe(
+    storage: &Arc<RwLock<MarketStorage>>,
+    symbols: &[String],
+    endpoints: &Endpoints,
+) {
     let selected_symbols = MultiSelect::new("Choose symbols:", symbols.to_vec())
         .prompt()
         .unwrap_or_default();
-    let stream_types = vec!["aggTrade", "trade", "kline"];
+    let stream_types = match endpoints.market {
+        Market::Spot => vec![
+            "aggTrade",
+            "trade",
+            "kline",
+            "depth",
+            "order book",
+            "bookTicker",
+        ],
+        Market::UsdFutures | Market::CoinFutures => vec![
+            "aggTrade",
+            "kline",
+            "depth",
+            "order book",
+            "bookTicker",
+            "markPrice",
+            "forceOrder",
+        ],
+    };
     let selected_streams = MultiSelect::new("Choose stream types:", stream_types)
         .prompt()
         .unwrap_or_default();
@@ -105,6 +486,18 @@ async fn custom_subscribe(storage: &Arc<RwLock<AggTradeStorage>>, symbols: &Vec<
                     streams.push(format!("{}@{}_{}", symbol, stream, interval));
                 }
             }
+        } else if *stream == "depth" {
+            if let Some(depth_stream) = select_depth() {
+                for symbol in &selected_symbols {
+                    streams.push(format!("{}@{}", symbol, depth_stream));
+                }
+            }
+        } else if *stream == "order book" {
+            if let Some(speed) = select_speed() {
+                for symbol in &selected_symbols {
+                    streams.push(format!("{}@{}", symbol, diff_depth_stream(&speed)));
+                }
+            }
         } else {
             for symbol in &selected_symbols {
                 streams.push(format!("{}@{}", symbol, stream));
@@ -112,23 +505,54 @@ async fn custom_subscribe(storage: &Arc<RwLock<AggTradeStorage>>, symbols: &Vec<
         }
     }
 
-    let combined_streams = streams.join("/");
-    let url = format!("{}{}", BINANCE_WS_COMBINED_URL, combined_streams);
-    process_subscription(&url, &streams, storage).await;
+    // Too many streams for one connection are spread across several
+    if connections_needed(&streams) > 1 {
+        clear_screen();
+        println!(
+            "Subscribing to {} streams over {} connections...",
+            streams.len(),
+            connections_needed(&streams)
+        );
+        if let Err(e) = run_sharded(endpoints, &streams, storage).await {
+            eprintln!("Error: {}", e);
+        }
+        return;
+    }
+
+    let url = endpoints.combined_url(&streams);
+
+    // Extra connections stand by with the same streams so a reconnect loses nothing
+    let copies = Select::new("Connections (extra ones are hot standbys):", vec![1, 2, 3])
+        .prompt()
+        .unwrap_or(1);
+    if copies > 1 {
+        clear_screen();
+        println!(
+            "Subscribing to {} streams over {} redundant connections...",
+            streams.len(),
+            copies
+        );
+        if let Err(e) = run_with_standby(endpoints, &url, &streams, copies, storage).await {
+            eprintln!("Error: {}", e);
+        }
+        return;
+    }
+    process_subscription(endpoints, &url, &streams, storage).await;
 }
 
 /// Processes the WebSocket subscription
 async fn process_subscription(
+    endpoints: &Endpoints,
     url: &str,
     streams: &Vec<String>,
-    storage: &Arc<RwLock<AggTradeStorage>>,
+    storage: &Arc<RwLock<MarketStorage>>,
 ) {
     clear_screen();
     println!("Subscribing to streams...");
     println!("Streams: {:?}", streams);
     println!("Combined URL: {}", url);
 
-    if let Err(e) = run(url, streams, 1, storage).await {
+    if let Err(e) = run(endpoints, url, streams, 1, storage).await {
         eprintln!("Error: {}", e);
     }
 }
@@ -136,11 +560,18 @@ async fn process_subscription(
 /// Waits for shutdown signal and completes all tasks
 fn wait_for_shutdown() -> Sleep {
     // Wait for a small amount of time to ensure all tasks complete
-    return tokio::time::sleep(tokio::time::Duration::from_millis(100));
+    tokio::time::sleep(tokio::time::Duration::from_millis(100))
+}
+
+/// Selects whether to subscribe to one symbol or the whole market
+fn select_scope() -> Option<&'static str> {
+    Select::new("Choose symbols:", vec!["Single symbol", "All symbols"])
+        .prompt()
+        .ok()
 }
 
 /// Selects a symbol from the list of symbols
-fn select_symbol(symbols: &Vec<String>) -> Option<String> {
+fn select_symbol(symbols: &[String]) -> Option<String> {
     Select::new("Choose a symbol:", symbols.to_vec())
         .prompt()
         .ok()
@@ -154,24 +585,139 @@ fn select_interval(intervals: Vec<&str>) -> Option<String> {
         .map(|s| s.to_string())
 }
 
+/// Selects the partial depth levels and update speed, e.g. `depth10@100ms`
+fn select_depth() -> Option<String> {
+    let levels = Select::new("Choose depth levels:", vec!["5", "10", "20"])
+        .prompt()
+        .ok()?;
+    let speed = select_speed()?;
+    match speed.as_str() {
+        "100ms" => Some(format!("depth{}@100ms", levels)),
+        _ => Some(format!("depth{}", levels)),
+    }
+}
+
+/// Selects the update speed of a depth stream
+fn select_speed() -> Option<String> {
+    Select::new("Choose update speed:", vec!["1000ms", "100ms"])
+        .prompt()
+        .ok()
+        .map(|s| s.to_string())
+}
+
+/// Builds the diff depth stream name for an update speed, e.g. `depth@100ms`
+fn diff_depth_stream(speed: &str) -> String {
+    match speed {
+        "100ms" => "depth@100ms".to_string(),
+        _ => "depth".to_string(),
+    }
+}
+
+/// Queries a symbol's price, book, candles and trades over the WebSocket API
+async fn query_symbol(symbols: &[String], endpoints: &Endpoints) {
+    let Some(url) = &endpoints.ws_api_url else {
+        println!("These endpoints have no WebSocket API; set one with --ws-api-url.");
+        pause();
+        return;
+    };
+    let Some(symbol) = select_symbol(symbols) else {
+        return;
+    };
+
+    clear_screen();
+    match WsApiClient::connect(url, WS_API_TIMEOUT).await {
+        Ok(client) => {
+            if let Err(e) = print_symbol_summary(&client, &symbol).await {
+                eprintln!("Query failed: {}", e);
+            }
+        }
+        Err(e) => eprintln!("Failed to connect to {}: {}", url, e),
+    }
+    pause();
+}
+
+// Print a snapshot of a symbol from WebSocket API queries
+async fn print_symbol_summary(client: &WsApiClient, symbol: &str) -> Result<(), RequestError> {
+    let (time, price, depth, klines, trades) = tokio::join!(
+        client.server_time(),
+        client.ticker_price(symbol),
+        client.depth(symbol, 5),
+        client.klines(symbol, "1m", 5),
+        client.agg_trades(symbol, None, 5)
+    );
+    println!("{} at {} (server time)", symbol, time?.format("%H:%M:%S"));
+    println!("Last price: {}", price?);
+
+    let depth = depth?;
+    println!("\nOrder book (update {}):", depth.last_update_id);
+    for ask in depth.asks.iter().rev() {
+        println!("  ask {:>14} {:>14}", ask.price, ask.quantity);
+    }
+    for bid in &depth.bids {
+        println!("  bid {:>14} {:>14}", bid.price, bid.quantity);
+    }
+
+    println!("\nLast 1m candles:");
+    for kline in klines? {
+        println!(
+            "  {} O {} H {} L {} C {} V {}",
+            kline.open_time.format("%H:%M"),
+            kline.open,
+            kline.high,
+            kline.low,
+            kline.close,
+            kline.volume
+        );
+    }
+
+    println!("\nLast aggregate trades:");
+    for trade in trades? {
+        println!(
+            "  #{} {} {} @ {}",
+            trade.trade_id,
+            if trade.is_buyer_maker { "sell" } else { "buy " },
+            trade.quantity,
+            trade.price
+        );
+    }
+    Ok(())
+}
+
 /// Lists all available symbols
-fn list_symbols(symbols: &Vec<String>) {
+fn list_symbols(symbol_infos: &[SymbolInfo]) {
     clear_screen();
     println!("Available symbols:");
-    for symbol in symbols {
-        println!("{}", symbol);
+    for info in symbol_infos {
+        match (&info.contract_type, info.contract_size) {
+            (Some(contract_type), Some(size)) => println!(
+                "{} ({}/{}, {}, {} {} per contract)",
+                info.symbol,
+                info.base_asset,
+                info.quote_asset,
+                contract_type,
+                size,
+                info.quote_asset
+            ),
+            (Some(contract_type), None) => println!(
+                "{} ({}/{}, {})",
+                info.symbol, info.base_asset, info.quote_asset, contract_type
+            ),
+            _ => println!("{} ({}/{})", info.symbol, info.base_asset, info.quote_asset),
+        }
     }
     pause();
 }
 
-/// Lists current subscriptions (placeholder)
-fn list_subscriptions(storage: &Arc<RwLock<AggTradeStorage>>) {
+/// Lists the streams the server confirmed during the last dashboard session
+fn list_subscriptions(storage: &Arc<RwLock<MarketStorage>>) {
     clear_screen();
-    println!("Listing subscriptions...");
+    println!("Streams confirmed by the server (LIST_SUBSCRIPTIONS) during the last session:");
     let read = storage.read().unwrap();
-    let trades = read.get_trades();
-    for trade in trades {
-        println!("{:?}", trade);
+    if read.subscriptions.is_empty() {
+        println!("  none");
+    }
+    for stream in &read.subscriptions {
+        println!("  {}", stream);
     }
     pause();
 }
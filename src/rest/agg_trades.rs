@@ -0,0 +1,55 @@
+use crate::storage::aggtrade_storage::AggTrade;
+use reqwest::Error;
+
+/// Fetches up to `limit` aggregate trades starting at `from_id` from the `aggTrades`
+/// endpoint of the API at `api_url`
+pub async fn fetch_agg_trades(
+    api_url: &str,
+    symbol: &str,
+    from_id: u64,
+    limit: u16,
+) -> Result<Vec<AggTrade>, Error> {
+    let url = format!(
+        "{}/aggTrades?symbol={}&fromId={}&limit={}",
+        api_url,
+        symbol.to_uppercase(),
+        from_id,
+        limit
+    );
+    let mut trades = reqwest::get(url)
+        .await?
+        .error_for_status()?
+        .json::<Vec<AggTrade>>()
+        .await?;
+    for trade in &mut trades {
+        trade.symbol = symbol.to_uppercase();
+    }
+    Ok(trades)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::spawn_http_server;
+
+    #[tokio::test]
+    async fn test_fetch_agg_trades() {
+        let base_url = spawn_http_server(vec![(
+            "/api/v3/aggTrades?symbol=BNBBTC&fromId=26129&limit=2",
+            r#"[{"a":26129,"p":"0.01633102","q":"4.70443515","f":27781,"l":27781,"T":1498793709153,"m":true,"M":true},{"a":26130,"p":"0.01633103","q":"1.00000000","f":27782,"l":27783,"T":1498793709160,"m":false,"M":true}]"#.to_string(),
+        )])
+        .await;
+
+        let api_url = format!("{}/api/v3", base_url);
+        let trades = fetch_agg_trades(&api_url, "bnbbtc", 26129, 2)
+            .await
+            .unwrap();
+        assert_eq!(trades.len(), 2);
+        assert_eq!(trades[0].symbol, "BNBBTC");
+        assert_eq!(trades[0].trade_id, 26129);
+        assert_eq!(trades[1].last_trade_id, 27783);
+        assert!(!trades[1].is_buyer_maker);
+
+        assert!(fetch_agg_trades(&api_url, "ethbtc", 1, 2).await.is_err());
+    }
+}
@@ -0,0 +1,48 @@
+use crate::storage::depth_storage::DepthSnapshot;
+use reqwest::Error;
+
+/// Fetches an order book snapshot from the `depth` endpoint of the API at `api_url`
+pub async fn fetch_depth_snapshot(
+    api_url: &str,
+    symbol: &str,
+    limit: u16,
+) -> Result<DepthSnapshot, Error> {
+    let url = format!(
+        "{}/depth?symbol={}&limit={}",
+        api_url,
+        symbol.to_uppercase(),
+        limit
+    );
+    let mut snapshot = reqwest::get(url)
+        .await?
+        .error_for_status()?
+        .json::<DepthSnapshot>()
+        .await?;
+    snapshot.symbol = symbol.to_uppercase();
+    Ok(snapshot)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::spawn_http_server;
+
+    #[tokio::test]
+    async fn test_fetch_depth_snapshot() {
+        let base_url = spawn_http_server(vec![(
+            "/api/v3/depth?symbol=BNBBTC&limit=100",
+            r#"{"lastUpdateId":1027024,"bids":[["4.00000000","431.00000000"]],"asks":[["4.00000200","12.00000000"]]}"#.to_string(),
+        )])
+        .await;
+
+        let api_url = format!("{}/api/v3", base_url);
+        let snapshot = fetch_depth_snapshot(&api_url, "bnbbtc", 100).await.unwrap();
+        assert_eq!(snapshot.symbol, "BNBBTC");
+        assert_eq!(snapshot.last_update_id, 1027024);
+        assert_eq!(snapshot.bids[0].quantity, 431.0);
+        assert_eq!(snapshot.asks[0].price, 4.000002);
+
+        // Unknown paths answer 404 and surface as errors
+        assert!(fetch_depth_snapshot(&api_url, "ethbtc", 100).await.is_err());
+    }
+}
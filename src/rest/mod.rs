@@ -0,0 +1,2 @@
+pub mod agg_trades;
+pub mod depth;
@@ -1,65 +1,52 @@
-use chrono::{DateTime, TimeZone, Utc};
-use serde::{Deserialize, Deserializer};
-use std::collections::VecDeque;
+use crate::storage::contract_sizes::ContractSizes;
+use crate::websocket::deserialize::{datetime_from_millis, f64_from_str};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::collections::{HashMap, VecDeque};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct AggTrade {
+    // Absent from REST responses, which are for a single symbol
+    #[serde(rename = "s", default)]
     pub symbol: String,
+    #[serde(rename = "a")]
     pub trade_id: u64,
+    #[serde(rename = "p", deserialize_with = "f64_from_str")]
     pub price: f64,
+    #[serde(rename = "q", deserialize_with = "f64_from_str")]
     pub quantity: f64,
+    #[serde(rename = "f")]
     pub first_trade_id: u64,
+    #[serde(rename = "l")]
     pub last_trade_id: u64,
+    #[serde(rename = "T", deserialize_with = "datetime_from_millis")]
     pub timestamp: DateTime<Utc>,
+    #[serde(rename = "m")]
     pub is_buyer_maker: bool,
 }
 
-// Deserialize AggTrade from JSON
-impl<'de> Deserialize<'de> for AggTrade {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        let v = serde_json::Value::deserialize(deserializer)?;
-        let timestamp = v
-            .get("T")
-            .and_then(|t| t.as_i64())
-            .ok_or_else(|| serde::de::Error::custom("Missing timestamp"))?;
-        let datetime = Utc
-            .timestamp_millis_opt(timestamp)
-            .single()
-            .ok_or_else(|| serde::de::Error::custom("Invalid timestamp"))?;
-
-        Ok(AggTrade {
-            symbol: v
-                .get("s")
-                .and_then(|s| s.as_str())
-                .unwrap_or_default()
-                .to_string(),
-            trade_id: v.get("a").and_then(|a| a.as_u64()).unwrap_or_default(),
-            price: v
-                .get("p")
-                .and_then(|p| p.as_str())
-                .and_then(|p| p.parse().ok())
-                .unwrap_or_default(),
-            quantity: v
-                .get("q")
-                .and_then(|q| q.as_str())
-                .and_then(|q| q.parse().ok())
-                .unwrap_or_default(),
-            first_trade_id: v.get("f").and_then(|f| f.as_u64()).unwrap_or_default(),
-            last_trade_id: v.get("l").and_then(|l| l.as_u64()).unwrap_or_default(),
-            timestamp: datetime,
-            is_buyer_maker: v.get("m").and_then(|m| m.as_bool()).unwrap_or_default(),
-        })
-    }
+/// Aggregate trade ids missing from a symbol's stream, to be fetched over REST
+#[derive(Debug, Clone, PartialEq)]
+pub struct TradeGap {
+    pub symbol: String,
+    pub from_id: u64,
+    pub to_id: u64,
 }
 
 pub struct AggTradeStorage {
     trades: VecDeque<AggTrade>,
     capacity: usize,
+    // Highest aggregate trade id seen per symbol
+    last_ids: HashMap<String, u64>,
+    gaps: Vec<TradeGap>,
+    pub gap_count: u64,
+    pub backfilled_count: u64,
+    pub backfill_failures: u64,
+    contract_sizes: ContractSizes,
     total_price: f64,
+    // In the base asset, converted from contracts for COIN-M symbols
     total_volume: f64,
+    total_contracts: f64,
     buyer_maker_true: usize,
     buyer_maker_false: usize,
     price_sum_squares: f64,
@@ -73,8 +60,15 @@ impl AggTradeStorage {
         Self {
             trades: VecDeque::with_capacity(capacity),
             capacity,
+            last_ids: HashMap::new(),
+            gaps: Vec::new(),
+            gap_count: 0,
+            backfilled_count: 0,
+            backfill_failures: 0,
+            contract_sizes: ContractSizes::default(),
             total_price: 0.0,
             total_volume: 0.0,
+            total_contracts: 0.0,
             buyer_maker_true: 0,
             buyer_maker_false: 0,
             price_sum_squares: 0.0,
@@ -83,30 +77,113 @@ impl AggTradeStorage {
         }
     }
 
-    // Add a trade to the storage
+    // Count the quantities of COIN-M symbols in contracts of these sizes
+    pub fn set_contract_sizes(&mut self, contract_sizes: ContractSizes) {
+        self.contract_sizes = contract_sizes;
+    }
+
+    // Get the base asset quantity of a trade
+    fn base_quantity(&self, trade: &AggTrade) -> f64 {
+        self.contract_sizes
+            .base_quantity(&trade.symbol, trade.quantity, trade.price)
+    }
+
+    // Get the contracts traded, counted only for COIN-M symbols
+    fn contracts(&self, trade: &AggTrade) -> f64 {
+        match self.contract_sizes.get(&trade.symbol) {
+            Some(_) => trade.quantity,
+            None => 0.0,
+        }
+    }
+
+    // Add a trade to the storage, noting a gap if trades before it were missed
     pub fn add_trade(&mut self, trade: AggTrade) {
-        if self.trades.len() == self.capacity {
-            if let Some(old_trade) = self.trades.pop_front() {
-                self.total_price -= old_trade.price;
-                self.total_volume -= old_trade.quantity;
-                self.price_sum_squares -= old_trade.price * old_trade.price;
-                if old_trade.is_buyer_maker {
-                    self.buyer_maker_true -= 1;
-                } else {
-                    self.buyer_maker_false -= 1;
-                }
-
-                if old_trade.price == self.max_price {
-                    self.max_price = self.trades.iter().map(|t| t.price).fold(f64::MIN, f64::max);
-                }
-                if old_trade.price == self.min_price {
-                    self.min_price = self.trades.iter().map(|t| t.price).fold(f64::MAX, f64::min);
-                }
+        match self.last_ids.get(&trade.symbol) {
+            // Already stored, e.g. delivered again after a reconnect
+            Some(&last_id) if trade.trade_id <= last_id => return,
+            Some(&last_id) if trade.trade_id > last_id + 1 => {
+                // Trades older than the storage holds would be evicted right away
+                let from_id =
+                    (last_id + 1).max(trade.trade_id.saturating_sub(self.capacity as u64));
+                self.gaps.push(TradeGap {
+                    symbol: trade.symbol.clone(),
+                    from_id,
+                    to_id: trade.trade_id - 1,
+                });
+                self.gap_count += 1;
+            }
+            _ => {}
+        }
+        self.last_ids.insert(trade.symbol.clone(), trade.trade_id);
+        self.evict_oldest_if_full();
+        self.record(&trade);
+        self.trades.push_back(trade);
+    }
+
+    // Insert trades fetched for a gap, each before the first later trade of its symbol
+    pub fn insert_backfill(&mut self, trades: Vec<AggTrade>) {
+        for trade in trades {
+            let already_stored = self
+                .trades
+                .iter()
+                .any(|stored| stored.symbol == trade.symbol && stored.trade_id == trade.trade_id);
+            if already_stored {
+                continue;
             }
+            self.evict_oldest_if_full();
+            let position = self
+                .trades
+                .iter()
+                .position(|stored| {
+                    stored.symbol == trade.symbol && stored.trade_id > trade.trade_id
+                })
+                .unwrap_or(self.trades.len());
+            self.record(&trade);
+            self.trades.insert(position, trade);
+            self.backfilled_count += 1;
         }
+    }
 
+    // Take the gaps found since the last call
+    pub fn take_gaps(&mut self) -> Vec<TradeGap> {
+        std::mem::take(&mut self.gaps)
+    }
+
+    // Record a backfill request that failed; the gap is left open
+    pub fn backfill_failed(&mut self) {
+        self.backfill_failures += 1;
+    }
+
+    // Drop the oldest trade when the storage is full
+    fn evict_oldest_if_full(&mut self) {
+        if self.trades.len() < self.capacity {
+            return;
+        }
+        if let Some(old_trade) = self.trades.pop_front() {
+            self.total_price -= old_trade.price;
+            self.total_volume -= self.base_quantity(&old_trade);
+            self.total_contracts -= self.contracts(&old_trade);
+            self.price_sum_squares -= old_trade.price * old_trade.price;
+            if old_trade.is_buyer_maker {
+                self.buyer_maker_true -= 1;
+            } else {
+                self.buyer_maker_false -= 1;
+            }
+
+            if old_trade.price == self.max_price {
+                self.max_price = self.trades.iter().map(|t| t.price).fold(f64::MIN, f64::max);
+            }
+            if old_trade.price == self.min_price {
+                self.min_price = self.trades.iter().map(|t| t.price).fold(f64::MAX, f64::min);
+            }
+        }
+    }
+
+    // Add a trade to the running statistics
+    fn record(&mut self, trade: &AggTrade) {
         self.total_price += trade.price;
-        self.total_volume += trade.quantity;
+        self.total_volume += self.base_quantity(trade);
+        self.total_contracts += self.contracts(trade);
         self.price_sum_squares += trade.price * trade.price;
         self.max_price = self.max_price.max(trade.price);
         self.min_price = self.min_price.min(trade.price);
@@ -115,7 +192,6 @@ impl AggTradeStorage {
         } else {
             self.buyer_maker_false += 1;
         }
-        self.trades.push_back(trade);
     }
 
     // Get all trades
@@ -131,11 +207,16 @@ impl AggTradeStorage {
         Some(self.total_price / self.trades.len() as f64)
     }
 
-    // Calculate the total volume of trades
+    // Calculate the total base asset volume of trades
     pub fn total_volume(&self) -> f64 {
         self.total_volume
     }
 
+    // Calculate the total contracts traded, for COIN-M symbols only
+    pub fn total_contracts(&self) -> Option<f64> {
+        Some(self.total_contracts).filter(|contracts| *contracts > 0.0)
+    }
+
     // Calculate the median price of trades
     pub fn calculate_median_price(&self) -> Option<f64> {
         if self.trades.is_empty() {
@@ -144,7 +225,7 @@ impl AggTradeStorage {
         let mut prices: Vec<f64> = self.trades.iter().map(|trade| trade.price).collect();
         prices.sort_by(|a, b| a.partial_cmp(b).unwrap());
         let mid = prices.len() / 2;
-        if prices.len() % 2 == 0 {
+        if prices.len().is_multiple_of(2) {
             Some((prices[mid - 1] + prices[mid]) / 2.0)
         } else {
             Some(prices[mid])
@@ -169,7 +250,7 @@ impl AggTradeStorage {
         let total_price_volume: f64 = self
             .trades
             .iter()
-            .map(|trade| trade.price * trade.quantity)
+            .map(|trade| trade.price * self.base_quantity(trade))
             .sum();
         Some(total_price_volume / self.total_volume)
     }
@@ -253,3 +334,104 @@ impl AggTradeStorage {
         (self.buyer_maker_true, self.buyer_maker_false)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn agg_trade(symbol: &str, trade_id: u64, price: f64) -> AggTrade {
+        AggTrade {
+            symbol: symbol.to_string(),
+            trade_id,
+            price,
+            quantity: 1.0,
+            first_trade_id: trade_id * 10,
+            last_trade_id: trade_id * 10,
+            timestamp: Utc.timestamp_millis_opt(trade_id as i64).unwrap(),
+            is_buyer_maker: false,
+        }
+    }
+
+    fn ids(storage: &AggTradeStorage) -> Vec<u64> {
+        storage
+            .get_trades()
+            .iter()
+            .map(|trade| trade.trade_id)
+            .collect()
+    }
+
+    #[test]
+    fn test_gaps_are_detected_per_symbol_and_backfilled_in_order() {
+        let mut storage = AggTradeStorage::new(10);
+        storage.add_trade(agg_trade("BTCUSDT", 1, 100.0));
+        storage.add_trade(agg_trade("ETHUSDT", 50, 10.0));
+        storage.add_trade(agg_trade("BTCUSDT", 2, 101.0));
+        storage.add_trade(agg_trade("BTCUSDT", 5, 104.0));
+        storage.add_trade(agg_trade("ETHUSDT", 51, 11.0));
+        // Repeated trades are not stored twice
+        storage.add_trade(agg_trade("BTCUSDT", 5, 104.0));
+
+        assert_eq!(storage.gap_count, 1);
+        assert_eq!(
+            storage.take_gaps(),
+            [TradeGap {
+                symbol: "BTCUSDT".to_string(),
+                from_id: 3,
+                to_id: 4,
+            }]
+        );
+        assert!(storage.take_gaps().is_empty());
+
+        storage.insert_backfill(vec![
+            agg_trade("BTCUSDT", 3, 102.0),
+            agg_trade("BTCUSDT", 4, 103.0),
+        ]);
+        assert_eq!(ids(&storage), [1, 50, 2, 3, 4, 5, 51]);
+        assert_eq!(storage.backfilled_count, 2);
+        assert_eq!(storage.total_volume(), 7.0);
+        assert_eq!(storage.calculate_max_price(), Some(104.0));
+    }
+
+    #[test]
+    fn test_gap_is_limited_to_what_the_storage_holds() {
+        let mut storage = AggTradeStorage::new(3);
+        storage.add_trade(agg_trade("BTCUSDT", 1, 100.0));
+        storage.add_trade(agg_trade("BTCUSDT", 100, 100.0));
+        assert_eq!(
+            storage.take_gaps(),
+            [TradeGap {
+                symbol: "BTCUSDT".to_string(),
+                from_id: 97,
+                to_id: 99,
+            }]
+        );
+
+        // A full storage evicts its oldest trade to make room
+        storage.insert_backfill(vec![
+            agg_trade("BTCUSDT", 98, 100.0),
+            agg_trade("BTCUSDT", 99, 100.0),
+        ]);
+        assert_eq!(ids(&storage), [98, 99, 100]);
+    }
+
+    #[test]
+    fn test_coin_futures_volume_is_converted_from_contracts() {
+        let mut storage = AggTradeStorage::new(10);
+        storage.set_contract_sizes(ContractSizes::new([("btcusd".to_string(), 100.0)]));
+        let mut trade = |trade_id, price| {
+            storage.add_trade(AggTrade {
+                quantity: 10.0,
+                ..agg_trade("BTCUSD_PERP", trade_id, price)
+            })
+        };
+        // 10 contracts of 100 USD each at 50000 and 25000
+        trade(1, 50_000.0);
+        trade(2, 25_000.0);
+
+        assert!((storage.total_volume() - 0.06).abs() < 1e-12);
+        assert_eq!(storage.total_contracts(), Some(20.0));
+        // Weighted by the BTC bought, so the lower price counts twice
+        assert!((storage.calculate_vwap().unwrap() - 100_000.0 / 3.0).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,191 @@
+use crate::websocket::message::BookTicker;
+use std::collections::{HashMap, VecDeque};
+
+/// Best bid/ask for a single symbol with a rolling window of spreads
+pub struct QuoteSeries {
+    latest: BookTicker,
+    spreads: VecDeque<f64>,
+    capacity: usize,
+    total_spread: f64,
+}
+
+impl QuoteSeries {
+    // Create a new QuoteSeries starting from its first update
+    fn new(book_ticker: BookTicker, capacity: usize) -> Self {
+        let mut series = Self {
+            latest: book_ticker,
+            spreads: VecDeque::with_capacity(capacity),
+            capacity,
+            total_spread: 0.0,
+        };
+        series.record_spread();
+        series
+    }
+
+    // Replace the quote unless the update is older than the stored one
+    fn update(&mut self, book_ticker: BookTicker) {
+        if book_ticker.update_id < self.latest.update_id {
+            return;
+        }
+        self.latest = book_ticker;
+        self.record_spread();
+    }
+
+    // Push the current spread into the rolling window
+    fn record_spread(&mut self) {
+        if self.spreads.len() == self.capacity {
+            if let Some(old_spread) = self.spreads.pop_front() {
+                self.total_spread -= old_spread;
+            }
+        }
+        let spread = self.calculate_spread();
+        self.total_spread += spread;
+        self.spreads.push_back(spread);
+    }
+
+    // Get the latest best bid/ask
+    pub fn latest(&self) -> &BookTicker {
+        &self.latest
+    }
+
+    // Get the spreads in the rolling window, oldest first
+    pub fn get_spreads(&self) -> &VecDeque<f64> {
+        &self.spreads
+    }
+
+    // Calculate the spread between best ask and best bid
+    pub fn calculate_spread(&self) -> f64 {
+        self.latest.ask_price - self.latest.bid_price
+    }
+
+    // Calculate the spread in basis points of the mid price
+    pub fn calculate_spread_bps(&self) -> f64 {
+        to_bps(self.calculate_spread(), self.calculate_mid_price())
+    }
+
+    // Calculate the mid price between best bid and best ask
+    pub fn calculate_mid_price(&self) -> f64 {
+        (self.latest.ask_price + self.latest.bid_price) / 2.0
+    }
+
+    // Calculate the mid price weighted towards the side with less quantity
+    pub fn calculate_microprice(&self) -> f64 {
+        let quote = &self.latest;
+        let total_quantity = quote.bid_quantity + quote.ask_quantity;
+        if total_quantity == 0.0 {
+            return self.calculate_mid_price();
+        }
+        (quote.bid_price * quote.ask_quantity + quote.ask_price * quote.bid_quantity)
+            / total_quantity
+    }
+
+    // Calculate the average spread over the rolling window
+    pub fn calculate_average_spread(&self) -> f64 {
+        self.total_spread / self.spreads.len() as f64
+    }
+
+    // Calculate the average spread in basis points of the current mid price
+    pub fn calculate_average_spread_bps(&self) -> f64 {
+        to_bps(self.calculate_average_spread(), self.calculate_mid_price())
+    }
+}
+
+// Express an amount in basis points of a price; an empty book has no price
+fn to_bps(amount: f64, price: f64) -> f64 {
+    if price == 0.0 {
+        return 0.0;
+    }
+    amount / price * 10_000.0
+}
+
+pub struct BookTickerStorage {
+    quotes: HashMap<String, QuoteSeries>,
+    capacity: usize,
+    last_updated: Option<String>,
+}
+
+impl BookTickerStorage {
+    // Create a new BookTickerStorage keeping `capacity` spreads per symbol
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            quotes: HashMap::new(),
+            capacity,
+            last_updated: None,
+        }
+    }
+
+    // Add a best bid/ask update to the matching symbol
+    pub fn add_book_ticker(&mut self, book_ticker: BookTicker) {
+        let symbol = book_ticker.symbol.clone();
+        match self.quotes.get_mut(&symbol) {
+            Some(series) => series.update(book_ticker),
+            None => {
+                let series = QuoteSeries::new(book_ticker, self.capacity);
+                self.quotes.insert(symbol.clone(), series);
+            }
+        }
+        self.last_updated = Some(symbol);
+    }
+
+    // Get the quote for a symbol
+    pub fn get_quote(&self, symbol: &str) -> Option<&QuoteSeries> {
+        self.quotes.get(symbol)
+    }
+
+    // Get the most recently updated quote
+    pub fn last_updated_quote(&self) -> Option<&QuoteSeries> {
+        self.get_quote(self.last_updated.as_ref()?)
+    }
+
+    // Get every quote, sorted by symbol
+    pub fn get_quotes(&self) -> Vec<&QuoteSeries> {
+        let mut quotes: Vec<_> = self.quotes.values().collect();
+        quotes.sort_by(|a, b| a.latest.symbol.cmp(&b.latest.symbol));
+        quotes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn book_ticker(update_id: u64, bid: (f64, f64), ask: (f64, f64)) -> BookTicker {
+        BookTicker {
+            update_id,
+            symbol: "BTCUSDT".to_string(),
+            bid_price: bid.0,
+            bid_quantity: bid.1,
+            ask_price: ask.0,
+            ask_quantity: ask.1,
+        }
+    }
+
+    #[test]
+    fn test_spread_mid_and_microprice() {
+        let mut storage = BookTickerStorage::new(10);
+        storage.add_book_ticker(book_ticker(1, (99.0, 3.0), (101.0, 1.0)));
+
+        let quote = storage.get_quote("BTCUSDT").unwrap();
+        assert_eq!(quote.calculate_spread(), 2.0);
+        assert_eq!(quote.calculate_mid_price(), 100.0);
+        assert_eq!(quote.calculate_spread_bps(), 200.0);
+        // Heavier bid pulls the microprice towards the ask
+        assert_eq!(quote.calculate_microprice(), 100.5);
+    }
+
+    #[test]
+    fn test_rolling_spread_window() {
+        let mut storage = BookTickerStorage::new(2);
+        storage.add_book_ticker(book_ticker(1, (99.0, 1.0), (101.0, 1.0)));
+        storage.add_book_ticker(book_ticker(2, (99.0, 1.0), (100.0, 1.0)));
+        // Out-of-order updates are ignored
+        storage.add_book_ticker(book_ticker(1, (90.0, 1.0), (110.0, 1.0)));
+        storage.add_book_ticker(book_ticker(3, (99.5, 1.0), (100.5, 1.0)));
+
+        let quote = storage.last_updated_quote().unwrap();
+        assert_eq!(quote.get_spreads().len(), 2);
+        assert_eq!(quote.calculate_average_spread(), 1.0);
+        assert_eq!(quote.calculate_average_spread_bps(), 100.0);
+        assert_eq!(quote.latest().update_id, 3);
+    }
+}
@@ -0,0 +1,35 @@
+use std::collections::HashMap;
+
+/// Quote value of one COIN-M contract per pair, whose trade and candle quantities are
+/// counted in contracts rather than the base asset
+#[derive(Debug, Clone, Default)]
+pub struct ContractSizes {
+    // Keyed by uppercase pair, e.g. `BTCUSD`
+    sizes: HashMap<String, f64>,
+}
+
+impl ContractSizes {
+    // Create ContractSizes from `(pair, size)` entries
+    pub fn new(sizes: impl IntoIterator<Item = (String, f64)>) -> Self {
+        Self {
+            sizes: sizes
+                .into_iter()
+                .map(|(pair, size)| (pair.to_uppercase(), size))
+                .collect(),
+        }
+    }
+
+    // Get the contract size of a symbol such as `BTCUSD_PERP` or `BTCUSD_PERPETUAL`
+    pub fn get(&self, symbol: &str) -> Option<f64> {
+        let pair = symbol.split('_').next().unwrap_or(symbol);
+        self.sizes.get(&pair.to_uppercase()).copied()
+    }
+
+    // Convert a contract quantity at `price` into base asset volume
+    pub fn base_quantity(&self, symbol: &str, quantity: f64, price: f64) -> f64 {
+        match self.get(symbol) {
+            Some(size) if price > 0.0 => quantity * size / price,
+            _ => quantity,
+        }
+    }
+}
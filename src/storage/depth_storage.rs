@@ -0,0 +1,148 @@
+use crate::websocket::deserialize::PriceLevel;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Top levels of the book as sent by the partial depth streams
+#[derive(Debug, Clone, Deserialize)]
+pub struct DepthSnapshot {
+    // Partial depth payloads carry no symbol; it is taken from the stream name
+    #[serde(skip)]
+    pub symbol: String,
+    #[serde(rename = "lastUpdateId")]
+    pub last_update_id: u64,
+    pub bids: Vec<PriceLevel>,
+    pub asks: Vec<PriceLevel>,
+}
+
+impl DepthSnapshot {
+    // Get the best bid level
+    pub fn best_bid(&self) -> Option<&PriceLevel> {
+        self.bids.first()
+    }
+
+    // Get the best ask level
+    pub fn best_ask(&self) -> Option<&PriceLevel> {
+        self.asks.first()
+    }
+
+    // Calculate the spread between best ask and best bid
+    pub fn calculate_spread(&self) -> Option<f64> {
+        Some(self.best_ask()?.price - self.best_bid()?.price)
+    }
+
+    // Calculate the mid price between best bid and best ask
+    pub fn calculate_mid_price(&self) -> Option<f64> {
+        Some((self.best_ask()?.price + self.best_bid()?.price) / 2.0)
+    }
+
+    // Calculate the total quantity on the bid side
+    pub fn bid_volume(&self) -> f64 {
+        self.bids.iter().map(|level| level.quantity).sum()
+    }
+
+    // Calculate the total quantity on the ask side
+    pub fn ask_volume(&self) -> f64 {
+        self.asks.iter().map(|level| level.quantity).sum()
+    }
+
+    // Calculate the order book imbalance in [-1, 1]; positive means more bids
+    pub fn calculate_imbalance(&self) -> Option<f64> {
+        let total = self.bid_volume() + self.ask_volume();
+        if total == 0.0 {
+            return None;
+        }
+        Some((self.bid_volume() - self.ask_volume()) / total)
+    }
+}
+
+pub struct DepthStorage {
+    snapshots: HashMap<String, DepthSnapshot>,
+    last_updated: Option<String>,
+}
+
+impl DepthStorage {
+    // Create a new, empty DepthStorage
+    pub fn new() -> Self {
+        Self {
+            snapshots: HashMap::new(),
+            last_updated: None,
+        }
+    }
+
+    // Replace the snapshot for the symbol, ignoring out-of-order updates
+    pub fn update(&mut self, snapshot: DepthSnapshot) {
+        if let Some(current) = self.snapshots.get(&snapshot.symbol) {
+            if snapshot.last_update_id < current.last_update_id {
+                return;
+            }
+        }
+        self.last_updated = Some(snapshot.symbol.clone());
+        self.snapshots.insert(snapshot.symbol.clone(), snapshot);
+    }
+
+    // Get the latest snapshot for a symbol
+    pub fn get_snapshot(&self, symbol: &str) -> Option<&DepthSnapshot> {
+        self.snapshots.get(symbol)
+    }
+
+    // Get the most recently updated snapshot
+    pub fn last_updated_snapshot(&self) -> Option<&DepthSnapshot> {
+        self.get_snapshot(self.last_updated.as_ref()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(symbol: &str, last_update_id: u64, bid: f64, ask: f64) -> DepthSnapshot {
+        DepthSnapshot {
+            symbol: symbol.to_string(),
+            last_update_id,
+            bids: vec![
+                PriceLevel {
+                    price: bid,
+                    quantity: 3.0,
+                },
+                PriceLevel {
+                    price: bid - 1.0,
+                    quantity: 1.0,
+                },
+            ],
+            asks: vec![PriceLevel {
+                price: ask,
+                quantity: 2.0,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_snapshot_statistics() {
+        let snapshot = snapshot("BTCUSDT", 1, 100.0, 102.0);
+        assert_eq!(snapshot.calculate_spread(), Some(2.0));
+        assert_eq!(snapshot.calculate_mid_price(), Some(101.0));
+        assert_eq!(snapshot.bid_volume(), 4.0);
+        assert_eq!(snapshot.calculate_imbalance(), Some((4.0 - 2.0) / 6.0));
+    }
+
+    #[test]
+    fn test_latest_snapshot_per_symbol() {
+        let mut storage = DepthStorage::new();
+        storage.update(snapshot("BTCUSDT", 10, 100.0, 102.0));
+        storage.update(snapshot("ETHUSDT", 5, 10.0, 11.0));
+        // Stale snapshots are dropped
+        storage.update(snapshot("BTCUSDT", 9, 90.0, 92.0));
+
+        assert_eq!(
+            storage
+                .get_snapshot("BTCUSDT")
+                .unwrap()
+                .best_bid()
+                .unwrap()
+                .price,
+            100.0
+        );
+        assert_eq!(storage.last_updated_snapshot().unwrap().symbol, "ETHUSDT");
+        assert!(storage.get_snapshot("BNBUSDT").is_none());
+    }
+}
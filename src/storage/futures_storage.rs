@@ -0,0 +1,198 @@
+use crate::websocket::message::{CompositeIndex, Liquidation, MarkPrice};
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, VecDeque};
+
+/// Mark prices and funding per contract, recent liquidations and composite indexes
+pub struct FuturesStorage {
+    mark_prices: HashMap<String, MarkPrice>,
+    // Mark price history per contract as (event time in ms, price)
+    mark_history: HashMap<String, VecDeque<(f64, f64)>>,
+    liquidations: VecDeque<Liquidation>,
+    indexes: HashMap<String, CompositeIndex>,
+    capacity: usize,
+    last_updated: Option<String>,
+    last_index: Option<String>,
+}
+
+impl FuturesStorage {
+    // Create a FuturesStorage keeping `capacity` liquidations and mark prices per contract
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            mark_prices: HashMap::new(),
+            mark_history: HashMap::new(),
+            liquidations: VecDeque::with_capacity(capacity),
+            indexes: HashMap::new(),
+            capacity,
+            last_updated: None,
+            last_index: None,
+        }
+    }
+
+    // Replace the mark price of a contract, ignoring updates older than the stored one
+    pub fn update_mark_price(&mut self, mark_price: MarkPrice) {
+        if let Some(stored) = self.mark_prices.get(&mark_price.symbol) {
+            if mark_price.event_time < stored.event_time {
+                return;
+            }
+        }
+        let history = self
+            .mark_history
+            .entry(mark_price.symbol.clone())
+            .or_default();
+        if history.len() == self.capacity {
+            history.pop_front();
+        }
+        history.push_back((
+            mark_price.event_time.timestamp_millis() as f64,
+            mark_price.mark_price,
+        ));
+        self.last_updated = Some(mark_price.symbol.clone());
+        self.mark_prices
+            .insert(mark_price.symbol.clone(), mark_price);
+    }
+
+    // Add a liquidation to the tape
+    pub fn add_liquidation(&mut self, liquidation: Liquidation) {
+        if self.liquidations.len() == self.capacity {
+            self.liquidations.pop_front();
+        }
+        self.liquidations.push_back(liquidation);
+    }
+
+    // Replace the price and constituents of a composite index, ignoring older updates
+    pub fn update_index(&mut self, index: CompositeIndex) {
+        if let Some(stored) = self.indexes.get(&index.symbol) {
+            if index.event_time < stored.event_time {
+                return;
+            }
+        }
+        self.last_index = Some(index.symbol.clone());
+        self.indexes.insert(index.symbol.clone(), index);
+    }
+
+    // Get the mark price of a contract
+    pub fn get_mark_price(&self, symbol: &str) -> Option<&MarkPrice> {
+        self.mark_prices.get(symbol)
+    }
+
+    // Get every mark price, highest funding rate first
+    pub fn mark_prices_by_funding(&self) -> Vec<&MarkPrice> {
+        let mut mark_prices: Vec<_> = self.mark_prices.values().collect();
+        mark_prices.sort_by(|a, b| {
+            let rate = |mark: &MarkPrice| mark.funding_rate.unwrap_or(f64::MIN);
+            rate(b).total_cmp(&rate(a))
+        });
+        mark_prices
+    }
+
+    // Get the mark price history of a contract, oldest first
+    pub fn mark_history(&self, symbol: &str) -> Vec<(f64, f64)> {
+        self.mark_history
+            .get(symbol)
+            .map(|history| history.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    // Get the liquidations on the tape, oldest first
+    pub fn liquidations(&self) -> &VecDeque<Liquidation> {
+        &self.liquidations
+    }
+
+    // Total notional of liquidated longs (sell orders) and shorts (buy orders) on the tape
+    pub fn liquidated_notional(&self) -> (f64, f64) {
+        self.liquidations
+            .iter()
+            .fold((0.0, 0.0), |(longs, shorts), liquidation| {
+                if liquidation.side == "SELL" {
+                    (longs + liquidation.notional(), shorts)
+                } else {
+                    (longs, shorts + liquidation.notional())
+                }
+            })
+    }
+
+    // Get the composite index updated most recently
+    pub fn last_index(&self) -> Option<&CompositeIndex> {
+        self.indexes.get(self.last_index.as_deref()?)
+    }
+
+    // Get the contract whose mark price was updated most recently
+    pub fn last_updated_symbol(&self) -> Option<&str> {
+        self.last_updated.as_deref()
+    }
+}
+
+/// Time left until the next funding payment, or `None` for contracts without funding
+pub fn time_to_funding(mark_price: &MarkPrice, now: DateTime<Utc>) -> Option<chrono::Duration> {
+    mark_price.funding_rate?;
+    Some((mark_price.next_funding_time - now).max(chrono::Duration::zero()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn mark_price(symbol: &str, event_time: i64, funding_rate: Option<f64>) -> MarkPrice {
+        MarkPrice {
+            event_time: Utc.timestamp_millis_opt(event_time).unwrap(),
+            symbol: symbol.to_string(),
+            mark_price: event_time as f64,
+            index_price: Some(100.0),
+            estimated_settle_price: 100.0,
+            funding_rate,
+            next_funding_time: Utc.timestamp_millis_opt(8 * 60 * 60 * 1000).unwrap(),
+        }
+    }
+
+    fn liquidation(side: &str, quantity: f64, price: f64) -> Liquidation {
+        Liquidation {
+            symbol: "BTCUSDT".to_string(),
+            side: side.to_string(),
+            average_price: price,
+            filled_quantity: quantity,
+            trade_time: Utc.timestamp_millis_opt(0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_mark_prices_are_ordered_by_funding() {
+        let mut storage = FuturesStorage::new(2);
+        storage.update_mark_price(mark_price("BTCUSDT", 1_000, Some(0.0001)));
+        storage.update_mark_price(mark_price("ETHUSDT", 1_000, Some(0.0003)));
+        storage.update_mark_price(mark_price("BTCUSDT_240628", 1_000, None));
+        storage.update_mark_price(mark_price("BTCUSDT", 2_000, Some(-0.0002)));
+        storage.update_mark_price(mark_price("BTCUSDT", 3_000, Some(-0.0002)));
+        // Older updates do not replace newer ones
+        storage.update_mark_price(mark_price("BTCUSDT", 1_500, Some(0.01)));
+
+        let symbols: Vec<_> = storage
+            .mark_prices_by_funding()
+            .iter()
+            .map(|mark| mark.symbol.as_str())
+            .collect();
+        assert_eq!(symbols, ["ETHUSDT", "BTCUSDT", "BTCUSDT_240628"]);
+        assert_eq!(
+            storage.mark_history("BTCUSDT"),
+            [(2_000.0, 2_000.0), (3_000.0, 3_000.0)]
+        );
+        assert_eq!(storage.last_updated_symbol(), Some("BTCUSDT"));
+
+        let now = Utc.timestamp_millis_opt(7 * 60 * 60 * 1000).unwrap();
+        let btc = storage.get_mark_price("BTCUSDT").unwrap();
+        assert_eq!(time_to_funding(btc, now), Some(chrono::Duration::hours(1)));
+        let delivery = storage.get_mark_price("BTCUSDT_240628").unwrap();
+        assert_eq!(time_to_funding(delivery, now), None);
+    }
+
+    #[test]
+    fn test_liquidation_tape_keeps_the_latest_orders() {
+        let mut storage = FuturesStorage::new(2);
+        storage.add_liquidation(liquidation("SELL", 1.0, 100.0));
+        storage.add_liquidation(liquidation("SELL", 2.0, 100.0));
+        storage.add_liquidation(liquidation("BUY", 1.0, 50.0));
+
+        assert_eq!(storage.liquidations().len(), 2);
+        assert_eq!(storage.liquidated_notional(), (200.0, 50.0));
+    }
+}
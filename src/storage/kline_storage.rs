@@ -0,0 +1,258 @@
+use crate::storage::contract_sizes::ContractSizes;
+use crate::websocket::deserialize::{datetime_from_millis, f64_from_str};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::collections::{HashMap, VecDeque};
+
+/// Candle fields, nested under `k` in kline events
+#[derive(Debug, Clone, Deserialize)]
+pub struct Kline {
+    // Continuous contract candles carry no symbol, it is filled in from the event
+    #[serde(rename = "s", default)]
+    pub symbol: String,
+    #[serde(rename = "i")]
+    pub interval: String,
+    #[serde(rename = "t", deserialize_with = "datetime_from_millis")]
+    pub open_time: DateTime<Utc>,
+    #[serde(rename = "T", deserialize_with = "datetime_from_millis")]
+    pub close_time: DateTime<Utc>,
+    #[serde(rename = "o", deserialize_with = "f64_from_str")]
+    pub open: f64,
+    #[serde(rename = "h", deserialize_with = "f64_from_str")]
+    pub high: f64,
+    #[serde(rename = "l", deserialize_with = "f64_from_str")]
+    pub low: f64,
+    #[serde(rename = "c", deserialize_with = "f64_from_str")]
+    pub close: f64,
+    #[serde(rename = "v", deserialize_with = "f64_from_str")]
+    pub volume: f64,
+    #[serde(rename = "q", deserialize_with = "f64_from_str")]
+    pub quote_volume: f64,
+    #[serde(rename = "n")]
+    pub trade_count: u64,
+    #[serde(rename = "V", deserialize_with = "f64_from_str")]
+    pub taker_buy_base_volume: f64,
+    #[serde(rename = "Q", deserialize_with = "f64_from_str")]
+    pub taker_buy_quote_volume: f64,
+    #[serde(rename = "x")]
+    pub is_closed: bool,
+}
+
+/// Candles for a single symbol and interval, oldest first
+pub struct CandleSeries {
+    candles: VecDeque<Kline>,
+    capacity: usize,
+}
+
+impl CandleSeries {
+    // Create a new CandleSeries with specified capacity
+    fn new(capacity: usize) -> Self {
+        Self {
+            candles: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    // Update the open candle in place or append a new one
+    fn update(&mut self, kline: Kline) {
+        if let Some(last) = self.candles.back_mut() {
+            if last.open_time == kline.open_time {
+                // Once a candle is closed it is final; ignore late updates
+                if !last.is_closed {
+                    *last = kline;
+                }
+                return;
+            }
+            if kline.open_time < last.open_time {
+                return;
+            }
+            // A newer candle started, so the previous one can no longer change
+            last.is_closed = true;
+        }
+
+        if self.candles.len() == self.capacity {
+            self.candles.pop_front();
+        }
+        self.candles.push_back(kline);
+    }
+
+    // Get all candles
+    pub fn get_candles(&self) -> &VecDeque<Kline> {
+        &self.candles
+    }
+
+    // Get the candle currently being built, if any
+    pub fn open_candle(&self) -> Option<&Kline> {
+        self.candles.back().filter(|kline| !kline.is_closed)
+    }
+
+    // Get the finalized candles
+    pub fn closed_candles(&self) -> impl Iterator<Item = &Kline> {
+        self.candles.iter().filter(|kline| kline.is_closed)
+    }
+
+    // Calculate the total base volume across stored candles
+    pub fn total_volume(&self) -> f64 {
+        self.candles.iter().map(|kline| kline.volume).sum()
+    }
+
+    // Calculate the total quote volume across stored candles, which COIN-M sends in
+    // the base asset
+    pub fn total_quote_volume(&self) -> f64 {
+        self.candles.iter().map(|kline| kline.quote_volume).sum()
+    }
+
+    // Calculate the share of volume bought by takers
+    pub fn calculate_taker_buy_ratio(&self) -> Option<f64> {
+        let total_volume = self.total_volume();
+        if total_volume == 0.0 {
+            return None;
+        }
+        let taker_buy_volume: f64 = self
+            .candles
+            .iter()
+            .map(|kline| kline.taker_buy_base_volume)
+            .sum();
+        Some(taker_buy_volume / total_volume)
+    }
+
+    // Calculate the highest high across stored candles
+    pub fn calculate_max_price(&self) -> Option<f64> {
+        self.candles.iter().map(|kline| kline.high).reduce(f64::max)
+    }
+
+    // Calculate the lowest low across stored candles
+    pub fn calculate_min_price(&self) -> Option<f64> {
+        self.candles.iter().map(|kline| kline.low).reduce(f64::min)
+    }
+}
+
+pub struct KlineStorage {
+    series: HashMap<(String, String), CandleSeries>,
+    capacity: usize,
+    last_updated: Option<(String, String)>,
+    contract_sizes: ContractSizes,
+}
+
+impl KlineStorage {
+    // Create a new KlineStorage keeping `capacity` candles per symbol and interval
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            series: HashMap::new(),
+            capacity,
+            last_updated: None,
+            contract_sizes: ContractSizes::default(),
+        }
+    }
+
+    // Treat the volumes of COIN-M symbols as counted in contracts of these sizes
+    pub fn set_contract_sizes(&mut self, contract_sizes: ContractSizes) {
+        self.contract_sizes = contract_sizes;
+    }
+
+    // Check whether a symbol's candle volume is counted in contracts
+    pub fn counts_contracts(&self, symbol: &str) -> bool {
+        self.contract_sizes.get(symbol).is_some()
+    }
+
+    // Add a kline update to the matching series
+    pub fn add_kline(&mut self, kline: Kline) {
+        let key = (kline.symbol.clone(), kline.interval.clone());
+        let capacity = self.capacity;
+        self.series
+            .entry(key.clone())
+            .or_insert_with(|| CandleSeries::new(capacity))
+            .update(kline);
+        self.last_updated = Some(key);
+    }
+
+    // Get the candles for a symbol and interval
+    pub fn get_series(&self, symbol: &str, interval: &str) -> Option<&CandleSeries> {
+        self.series.get(&(symbol.to_string(), interval.to_string()))
+    }
+
+    // Get the most recently updated series
+    pub fn last_updated_series(&self) -> Option<&CandleSeries> {
+        let (symbol, interval) = self.last_updated.as_ref()?;
+        self.get_series(symbol, interval)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn kline(open_time: i64, close: f64, is_closed: bool) -> Kline {
+        Kline {
+            symbol: "BTCUSDT".to_string(),
+            interval: "1m".to_string(),
+            open_time: Utc.timestamp_millis_opt(open_time).unwrap(),
+            close_time: Utc.timestamp_millis_opt(open_time + 59_999).unwrap(),
+            open: 100.0,
+            high: close.max(100.0),
+            low: close.min(100.0),
+            close,
+            volume: 1.0,
+            quote_volume: close,
+            trade_count: 1,
+            taker_buy_base_volume: 0.5,
+            taker_buy_quote_volume: close / 2.0,
+            is_closed,
+        }
+    }
+
+    #[test]
+    fn test_open_candle_is_updated_in_place() {
+        let mut storage = KlineStorage::new(10);
+        storage.add_kline(kline(0, 101.0, false));
+        storage.add_kline(kline(0, 102.0, false));
+
+        let series = storage.get_series("BTCUSDT", "1m").unwrap();
+        assert_eq!(series.get_candles().len(), 1);
+        assert_eq!(series.open_candle().unwrap().close, 102.0);
+    }
+
+    #[test]
+    fn test_closed_candle_is_final() {
+        let mut storage = KlineStorage::new(10);
+        storage.add_kline(kline(0, 101.0, true));
+        storage.add_kline(kline(0, 150.0, false));
+        storage.add_kline(kline(60_000, 103.0, false));
+
+        let series = storage.last_updated_series().unwrap();
+        assert_eq!(series.get_candles().len(), 2);
+        assert_eq!(series.closed_candles().count(), 1);
+        assert_eq!(series.get_candles()[0].close, 101.0);
+        assert_eq!(series.open_candle().unwrap().close, 103.0);
+    }
+
+    #[test]
+    fn test_series_are_keyed_by_symbol_and_interval() {
+        let mut storage = KlineStorage::new(1);
+        let mut hourly = kline(0, 101.0, false);
+        hourly.interval = "1h".to_string();
+        storage.add_kline(kline(0, 101.0, false));
+        storage.add_kline(hourly);
+        storage.add_kline(kline(60_000, 102.0, false));
+
+        // Capacity applies per series
+        assert_eq!(
+            storage
+                .get_series("BTCUSDT", "1m")
+                .unwrap()
+                .get_candles()
+                .len(),
+            1
+        );
+        assert_eq!(
+            storage
+                .get_series("BTCUSDT", "1h")
+                .unwrap()
+                .get_candles()
+                .len(),
+            1
+        );
+        assert!(storage.get_series("ETHUSDT", "1m").is_none());
+    }
+}
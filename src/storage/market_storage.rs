@@ -0,0 +1,52 @@
+use crate::storage::aggtrade_storage::AggTradeStorage;
+use crate::storage::book_ticker_storage::BookTickerStorage;
+use crate::storage::contract_sizes::ContractSizes;
+use crate::storage::depth_storage::DepthStorage;
+use crate::storage::futures_storage::FuturesStorage;
+use crate::storage::kline_storage::KlineStorage;
+use crate::storage::order_book::OrderBookStorage;
+use crate::storage::ticker_storage::TickerStorage;
+use crate::storage::trade_storage::TradeStorage;
+use crate::storage::user_data_storage::UserDataStorage;
+use crate::storage::window_ticker_storage::WindowTickerStorage;
+
+/// Holds the rolling storage for every market stream shown on the dashboard
+pub struct MarketStorage {
+    pub agg_trades: AggTradeStorage,
+    pub trades: TradeStorage,
+    pub klines: KlineStorage,
+    pub depth: DepthStorage,
+    pub order_books: OrderBookStorage,
+    pub book_tickers: BookTickerStorage,
+    pub tickers: TickerStorage,
+    pub window_tickers: WindowTickerStorage,
+    pub futures: FuturesStorage,
+    pub user_data: UserDataStorage,
+    // Streams the server last listed as subscribed
+    pub subscriptions: Vec<String>,
+}
+
+impl MarketStorage {
+    // Create a new MarketStorage where each rolling storage keeps `capacity` entries
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            agg_trades: AggTradeStorage::new(capacity),
+            trades: TradeStorage::new(capacity),
+            klines: KlineStorage::new(capacity),
+            depth: DepthStorage::new(),
+            order_books: OrderBookStorage::new(),
+            book_tickers: BookTickerStorage::new(capacity),
+            tickers: TickerStorage::new(),
+            window_tickers: WindowTickerStorage::new(),
+            futures: FuturesStorage::new(capacity),
+            user_data: UserDataStorage::new(capacity),
+            subscriptions: Vec::new(),
+        }
+    }
+
+    // Count COIN-M trade and candle quantities in contracts of these sizes
+    pub fn set_contract_sizes(&mut self, contract_sizes: ContractSizes) {
+        self.agg_trades.set_contract_sizes(contract_sizes.clone());
+        self.klines.set_contract_sizes(contract_sizes);
+    }
+}
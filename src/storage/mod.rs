@@ -1 +1,12 @@
 pub mod aggtrade_storage;
+pub mod book_ticker_storage;
+pub mod contract_sizes;
+pub mod depth_storage;
+pub mod futures_storage;
+pub mod kline_storage;
+pub mod market_storage;
+pub mod order_book;
+pub mod ticker_storage;
+pub mod trade_storage;
+pub mod user_data_storage;
+pub mod window_ticker_storage;
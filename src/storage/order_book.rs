@@ -0,0 +1,467 @@
+use crate::storage::depth_storage::DepthSnapshot;
+use crate::websocket::deserialize::PriceLevel;
+use crate::websocket::message::DepthUpdate;
+use chrono::{DateTime, Utc};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+// Maximum number of diff events kept while waiting for a snapshot
+const MAX_BUFFERED_UPDATES: usize = 1000;
+// Minimum delay between two snapshot requests for the same symbol
+const SNAPSHOT_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Price key with a total order so it can be used in a BTreeMap
+#[derive(Debug, Clone, Copy)]
+struct Price(f64);
+
+impl PartialEq for Price {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Price {}
+
+impl PartialOrd for Price {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Price {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+#[derive(Debug)]
+pub enum OrderBookError {
+    // The snapshot is older than the first buffered event and must be fetched again
+    StaleSnapshot {
+        last_update_id: u64,
+        first_buffered_id: u64,
+    },
+}
+
+impl fmt::Display for OrderBookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OrderBookError::StaleSnapshot {
+                last_update_id,
+                first_buffered_id,
+            } => write!(
+                f,
+                "snapshot lastUpdateId {} is older than first buffered event {}",
+                last_update_id, first_buffered_id
+            ),
+        }
+    }
+}
+
+impl std::error::Error for OrderBookError {}
+
+/// Full order book with price levels sorted on both sides
+#[derive(Debug, Clone)]
+pub struct OrderBook {
+    pub symbol: String,
+    pub last_update_id: u64,
+    pub last_event_time: Option<DateTime<Utc>>,
+    bids: BTreeMap<Price, f64>,
+    asks: BTreeMap<Price, f64>,
+}
+
+impl OrderBook {
+    // Create an empty order book
+    pub fn new(symbol: &str) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            last_update_id: 0,
+            last_event_time: None,
+            bids: BTreeMap::new(),
+            asks: BTreeMap::new(),
+        }
+    }
+
+    // Create an order book from a REST depth snapshot
+    pub fn from_snapshot(symbol: &str, snapshot: &DepthSnapshot) -> Self {
+        let mut book = Self::new(symbol);
+        book.last_update_id = snapshot.last_update_id;
+        set_levels(&mut book.bids, &snapshot.bids);
+        set_levels(&mut book.asks, &snapshot.asks);
+        book
+    }
+
+    // Apply the levels of a diff event; a zero quantity removes the level
+    fn apply_update(&mut self, update: &DepthUpdate) {
+        set_levels(&mut self.bids, &update.bids);
+        set_levels(&mut self.asks, &update.asks);
+        self.last_update_id = update.final_update_id;
+        self.last_event_time = Some(update.event_time);
+    }
+
+    // Get the best bid level
+    pub fn best_bid(&self) -> Option<PriceLevel> {
+        self.bids.iter().next_back().map(to_level)
+    }
+
+    // Get the best ask level
+    pub fn best_ask(&self) -> Option<PriceLevel> {
+        self.asks.iter().next().map(to_level)
+    }
+
+    // Calculate the mid price between best bid and best ask
+    pub fn calculate_mid_price(&self) -> Option<f64> {
+        Some((self.best_ask()?.price + self.best_bid()?.price) / 2.0)
+    }
+
+    // Get the best `levels` bids, highest price first
+    pub fn top_bids(&self, levels: usize) -> Vec<PriceLevel> {
+        self.bids.iter().rev().take(levels).map(to_level).collect()
+    }
+
+    // Get the best `levels` asks, lowest price first
+    pub fn top_asks(&self, levels: usize) -> Vec<PriceLevel> {
+        self.asks.iter().take(levels).map(to_level).collect()
+    }
+
+    // Calculate the bid quantity at or above a price
+    pub fn bid_depth_to(&self, price: f64) -> f64 {
+        self.bids.range(Price(price)..).map(|(_, qty)| qty).sum()
+    }
+
+    // Calculate the ask quantity at or below a price
+    pub fn ask_depth_to(&self, price: f64) -> f64 {
+        self.asks.range(..=Price(price)).map(|(_, qty)| qty).sum()
+    }
+
+    // Get the number of levels on each side
+    pub fn level_count(&self) -> (usize, usize) {
+        (self.bids.len(), self.asks.len())
+    }
+
+    // Get the top of the book in the shape used by the depth ladder
+    pub fn to_snapshot(&self, levels: usize) -> DepthSnapshot {
+        DepthSnapshot {
+            symbol: self.symbol.clone(),
+            last_update_id: self.last_update_id,
+            bids: self.top_bids(levels),
+            asks: self.top_asks(levels),
+        }
+    }
+}
+
+fn set_levels(side: &mut BTreeMap<Price, f64>, levels: &[PriceLevel]) {
+    for level in levels {
+        if level.quantity == 0.0 {
+            side.remove(&Price(level.price));
+        } else {
+            side.insert(Price(level.price), level.quantity);
+        }
+    }
+}
+
+fn to_level((price, quantity): (&Price, &f64)) -> PriceLevel {
+    PriceLevel {
+        price: price.0,
+        quantity: *quantity,
+    }
+}
+
+/// Keeps an order book in sync with the diff depth stream:
+/// buffers events until a REST snapshot arrives, drops events older than the
+/// snapshot, checks `U`/`u` sequencing and asks for a new snapshot on gaps.
+pub struct OrderBookSync {
+    book: OrderBook,
+    buffer: VecDeque<DepthUpdate>,
+    synced: bool,
+    needs_snapshot: bool,
+    snapshot_requested_at: Option<Instant>,
+    snapshot_in_flight: bool,
+    pub resync_count: u64,
+}
+
+impl OrderBookSync {
+    // Create a new OrderBookSync waiting for its first snapshot
+    pub fn new(symbol: &str) -> Self {
+        Self {
+            book: OrderBook::new(symbol),
+            buffer: VecDeque::new(),
+            synced: false,
+            needs_snapshot: true,
+            snapshot_requested_at: None,
+            snapshot_in_flight: false,
+            resync_count: 0,
+        }
+    }
+
+    // Get the order book; only meaningful once synced
+    pub fn book(&self) -> &OrderBook {
+        &self.book
+    }
+
+    // Check whether the book reflects the stream
+    pub fn is_synced(&self) -> bool {
+        self.synced
+    }
+
+    // Get the number of events waiting for a snapshot
+    pub fn buffered_count(&self) -> usize {
+        self.buffer.len()
+    }
+
+    // Apply a diff event, buffering it while no snapshot is available
+    pub fn apply_update(&mut self, update: DepthUpdate) {
+        if !self.synced {
+            if self.buffer.len() == MAX_BUFFERED_UPDATES {
+                self.buffer.pop_front();
+            }
+            self.buffer.push_back(update);
+            return;
+        }
+
+        // Events already contained in the book
+        if update.final_update_id <= self.book.last_update_id {
+            return;
+        }
+        // A missing event means the book can no longer be trusted. Futures update ids
+        // are not contiguous, there each event names the final id of the one before.
+        let missed = match update.previous_final_update_id {
+            Some(previous) => previous > self.book.last_update_id,
+            None => update.first_update_id > self.book.last_update_id + 1,
+        };
+        if missed {
+            self.resync(update);
+            return;
+        }
+        self.book.apply_update(&update);
+    }
+
+    // Apply a REST snapshot and replay the buffered events on top of it
+    pub fn apply_snapshot(&mut self, snapshot: &DepthSnapshot) -> Result<(), OrderBookError> {
+        self.snapshot_in_flight = false;
+
+        if let Some(first) = self.buffer.front() {
+            if snapshot.last_update_id < first.first_update_id {
+                self.needs_snapshot = true;
+                return Err(OrderBookError::StaleSnapshot {
+                    last_update_id: snapshot.last_update_id,
+                    first_buffered_id: first.first_update_id,
+                });
+            }
+        }
+
+        self.book = OrderBook::from_snapshot(&self.book.symbol, snapshot);
+        self.synced = true;
+        self.needs_snapshot = false;
+        for update in std::mem::take(&mut self.buffer) {
+            self.apply_update(update);
+        }
+        Ok(())
+    }
+
+    // Record a failed snapshot request so it is retried
+    pub fn snapshot_failed(&mut self) {
+        self.snapshot_in_flight = false;
+        self.needs_snapshot = true;
+    }
+
+    // Check whether a snapshot should be requested now, marking it as requested
+    pub fn take_snapshot_request(&mut self) -> bool {
+        let retry_ready = self
+            .snapshot_requested_at
+            .is_none_or(|at| at.elapsed() >= SNAPSHOT_RETRY_DELAY);
+        if !self.needs_snapshot || self.snapshot_in_flight || !retry_ready {
+            return false;
+        }
+        self.needs_snapshot = false;
+        self.snapshot_in_flight = true;
+        self.snapshot_requested_at = Some(Instant::now());
+        true
+    }
+
+    // Drop the book and start buffering from the event after the gap
+    fn resync(&mut self, update: DepthUpdate) {
+        self.synced = false;
+        self.needs_snapshot = true;
+        self.resync_count += 1;
+        self.buffer.clear();
+        self.buffer.push_back(update);
+    }
+}
+
+pub struct OrderBookStorage {
+    books: HashMap<String, OrderBookSync>,
+    last_updated: Option<String>,
+}
+
+impl OrderBookStorage {
+    // Create a new, empty OrderBookStorage
+    pub fn new() -> Self {
+        Self {
+            books: HashMap::new(),
+            last_updated: None,
+        }
+    }
+
+    // Apply a diff event to the book for its symbol
+    pub fn apply_update(&mut self, update: DepthUpdate) {
+        let symbol = update.symbol.clone();
+        self.books
+            .entry(symbol.clone())
+            .or_insert_with(|| OrderBookSync::new(&symbol))
+            .apply_update(update);
+        self.last_updated = Some(symbol);
+    }
+
+    // Apply a REST snapshot to the book for a symbol
+    pub fn apply_snapshot(
+        &mut self,
+        symbol: &str,
+        snapshot: &DepthSnapshot,
+    ) -> Result<(), OrderBookError> {
+        self.books
+            .entry(symbol.to_string())
+            .or_insert_with(|| OrderBookSync::new(symbol))
+            .apply_snapshot(snapshot)
+    }
+
+    // Record a failed snapshot request for a symbol
+    pub fn snapshot_failed(&mut self, symbol: &str) {
+        if let Some(sync) = self.books.get_mut(symbol) {
+            sync.snapshot_failed();
+        }
+    }
+
+    // Get the symbols whose books need a snapshot now
+    pub fn take_snapshot_requests(&mut self) -> Vec<String> {
+        self.books
+            .iter_mut()
+            .filter_map(|(symbol, sync)| sync.take_snapshot_request().then(|| symbol.clone()))
+            .collect()
+    }
+
+    // Get the book for a symbol
+    pub fn get_book(&self, symbol: &str) -> Option<&OrderBookSync> {
+        self.books.get(symbol)
+    }
+
+    // Get the most recently updated book
+    pub fn last_updated_book(&self) -> Option<&OrderBookSync> {
+        self.get_book(self.last_updated.as_ref()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn level(price: f64, quantity: f64) -> PriceLevel {
+        PriceLevel { price, quantity }
+    }
+
+    fn update(first: u64, last: u64, bids: Vec<PriceLevel>, asks: Vec<PriceLevel>) -> DepthUpdate {
+        DepthUpdate {
+            event_time: Utc.timestamp_millis_opt(1_700_000_000_000).unwrap(),
+            symbol: "BTCUSDT".to_string(),
+            first_update_id: first,
+            final_update_id: last,
+            previous_final_update_id: None,
+            bids,
+            asks,
+        }
+    }
+
+    fn snapshot(last_update_id: u64) -> DepthSnapshot {
+        DepthSnapshot {
+            symbol: String::new(),
+            last_update_id,
+            bids: vec![level(100.0, 1.0), level(99.0, 2.0), level(98.0, 3.0)],
+            asks: vec![level(101.0, 1.5), level(102.0, 2.5)],
+        }
+    }
+
+    #[test]
+    fn test_book_queries() {
+        let book = OrderBook::from_snapshot("BTCUSDT", &snapshot(1));
+        assert_eq!(book.best_bid(), Some(level(100.0, 1.0)));
+        assert_eq!(book.best_ask(), Some(level(101.0, 1.5)));
+        assert_eq!(book.calculate_mid_price(), Some(100.5));
+        assert_eq!(book.top_bids(2), vec![level(100.0, 1.0), level(99.0, 2.0)]);
+        assert_eq!(book.bid_depth_to(99.0), 3.0);
+        assert_eq!(book.ask_depth_to(102.0), 4.0);
+        assert_eq!(book.level_count(), (3, 2));
+    }
+
+    #[test]
+    fn test_buffered_events_are_replayed_after_snapshot() {
+        let mut sync = OrderBookSync::new("BTCUSDT");
+        assert!(sync.take_snapshot_request());
+        assert!(!sync.take_snapshot_request());
+
+        // Older than the snapshot, must be dropped
+        sync.apply_update(update(1, 5, vec![level(100.0, 50.0)], vec![]));
+        // Straddles the snapshot, applied first
+        sync.apply_update(update(6, 12, vec![level(100.0, 0.0)], vec![]));
+        sync.apply_update(update(13, 14, vec![], vec![level(101.0, 9.0)]));
+        assert!(!sync.is_synced());
+        assert_eq!(sync.buffered_count(), 3);
+
+        sync.apply_snapshot(&snapshot(10)).unwrap();
+        assert!(sync.is_synced());
+        assert_eq!(sync.book().last_update_id, 14);
+        assert_eq!(sync.book().best_bid(), Some(level(99.0, 2.0)));
+        assert_eq!(sync.book().best_ask(), Some(level(101.0, 9.0)));
+    }
+
+    #[test]
+    fn test_stale_snapshot_is_rejected() {
+        let mut sync = OrderBookSync::new("BTCUSDT");
+        assert!(sync.take_snapshot_request());
+        sync.apply_update(update(20, 25, vec![], vec![]));
+
+        assert!(matches!(
+            sync.apply_snapshot(&snapshot(10)),
+            Err(OrderBookError::StaleSnapshot { .. })
+        ));
+        assert!(!sync.is_synced());
+        assert_eq!(sync.buffered_count(), 1);
+    }
+
+    #[test]
+    fn test_gap_triggers_resync() {
+        let mut sync = OrderBookSync::new("BTCUSDT");
+        sync.apply_snapshot(&snapshot(10)).unwrap();
+        sync.apply_update(update(11, 12, vec![level(97.0, 1.0)], vec![]));
+        assert_eq!(sync.book().level_count(), (4, 2));
+
+        // Events 13..=14 are missing
+        sync.apply_update(update(15, 16, vec![], vec![]));
+        assert!(!sync.is_synced());
+        assert_eq!(sync.resync_count, 1);
+        assert_eq!(sync.buffered_count(), 1);
+    }
+
+    #[test]
+    fn test_futures_updates_chain_by_previous_id() {
+        let mut sync = OrderBookSync::new("BTCUSDT");
+        sync.apply_snapshot(&snapshot(10)).unwrap();
+        let chained = |first, last, previous| DepthUpdate {
+            previous_final_update_id: Some(previous),
+            ..update(first, last, vec![], vec![])
+        };
+
+        // Ids skip between events, but each names the previous one
+        sync.apply_update(chained(8, 12, 7));
+        sync.apply_update(chained(20, 25, 12));
+        assert!(sync.is_synced());
+        assert_eq!(sync.book().last_update_id, 25);
+
+        // The event after 25 is missing
+        sync.apply_update(chained(40, 45, 30));
+        assert!(!sync.is_synced());
+        assert_eq!(sync.resync_count, 1);
+    }
+}
@@ -0,0 +1,223 @@
+use crate::websocket::message::{MiniTicker, Ticker};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+
+/// 24h rolling statistics for a symbol, from either the full or the mini ticker
+#[derive(Debug, Clone)]
+pub struct TickerSummary {
+    pub symbol: String,
+    pub last_price: f64,
+    pub price_change_percent: f64,
+    pub high_price: f64,
+    pub low_price: f64,
+    pub volume: f64,
+    pub quote_volume: f64,
+    pub event_time: DateTime<Utc>,
+}
+
+impl From<&Ticker> for TickerSummary {
+    fn from(ticker: &Ticker) -> Self {
+        Self {
+            symbol: ticker.symbol.clone(),
+            last_price: ticker.last_price,
+            price_change_percent: ticker.price_change_percent,
+            high_price: ticker.high_price,
+            low_price: ticker.low_price,
+            volume: ticker.volume,
+            quote_volume: ticker.quote_volume,
+            event_time: ticker.event_time,
+        }
+    }
+}
+
+impl From<&MiniTicker> for TickerSummary {
+    fn from(ticker: &MiniTicker) -> Self {
+        // The mini ticker has no change field, so derive it from the open price
+        let price_change_percent = if ticker.open_price == 0.0 {
+            0.0
+        } else {
+            (ticker.close_price - ticker.open_price) / ticker.open_price * 100.0
+        };
+        Self {
+            symbol: ticker.symbol.clone(),
+            last_price: ticker.close_price,
+            price_change_percent,
+            high_price: ticker.high_price,
+            low_price: ticker.low_price,
+            volume: ticker.volume,
+            quote_volume: ticker.quote_volume,
+            event_time: ticker.event_time,
+        }
+    }
+}
+
+/// Order of the market overview table
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TickerSort {
+    Gainers,
+    Losers,
+    QuoteVolume,
+}
+
+impl TickerSort {
+    // The sort order following this one
+    pub fn next(self) -> Self {
+        match self {
+            TickerSort::Gainers => TickerSort::Losers,
+            TickerSort::Losers => TickerSort::QuoteVolume,
+            TickerSort::QuoteVolume => TickerSort::Gainers,
+        }
+    }
+
+    // Name shown in the table title
+    pub fn label(self) -> &'static str {
+        match self {
+            TickerSort::Gainers => "Top Gainers",
+            TickerSort::Losers => "Top Losers",
+            TickerSort::QuoteVolume => "Top Quote Volume",
+        }
+    }
+}
+
+pub struct TickerStorage {
+    tickers: HashMap<String, TickerSummary>,
+    quote_assets: HashMap<String, String>,
+    quote_filter: Option<String>,
+    sort: TickerSort,
+}
+
+impl TickerStorage {
+    // Create an empty TickerStorage sorted by top gainers
+    pub fn new() -> Self {
+        Self {
+            tickers: HashMap::new(),
+            quote_assets: HashMap::new(),
+            quote_filter: None,
+            sort: TickerSort::Gainers,
+        }
+    }
+
+    // Replace the statistics for a symbol
+    pub fn update(&mut self, summary: TickerSummary) {
+        self.tickers.insert(summary.symbol.clone(), summary);
+    }
+
+    // Register the quote asset of each symbol, as listed by exchangeInfo
+    pub fn set_quote_assets(&mut self, quote_assets: impl IntoIterator<Item = (String, String)>) {
+        self.quote_assets = quote_assets
+            .into_iter()
+            .map(|(symbol, quote_asset)| (symbol.to_uppercase(), quote_asset))
+            .collect();
+    }
+
+    // Only show symbols quoted in `quote_asset`, or every symbol for `None`
+    pub fn set_quote_filter(&mut self, quote_asset: Option<String>) {
+        self.quote_filter = quote_asset;
+    }
+
+    // Get the quote asset filter
+    pub fn quote_filter(&self) -> Option<&str> {
+        self.quote_filter.as_deref()
+    }
+
+    // Get the sort order
+    pub fn sort(&self) -> TickerSort {
+        self.sort
+    }
+
+    // Switch to the next sort order
+    pub fn cycle_sort(&mut self) {
+        self.sort = self.sort.next();
+    }
+
+    // Get the tickers matching the quote asset filter in the current sort order
+    pub fn sorted_tickers(&self) -> Vec<&TickerSummary> {
+        let mut tickers: Vec<_> = self
+            .tickers
+            .values()
+            .filter(|ticker| match &self.quote_filter {
+                Some(quote_asset) => self.quote_assets.get(&ticker.symbol) == Some(quote_asset),
+                None => true,
+            })
+            .collect();
+        match self.sort {
+            TickerSort::Gainers => {
+                tickers.sort_by(|a, b| b.price_change_percent.total_cmp(&a.price_change_percent))
+            }
+            TickerSort::Losers => {
+                tickers.sort_by(|a, b| a.price_change_percent.total_cmp(&b.price_change_percent))
+            }
+            TickerSort::QuoteVolume => {
+                tickers.sort_by(|a, b| b.quote_volume.total_cmp(&a.quote_volume))
+            }
+        }
+        tickers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn summary(symbol: &str, price_change_percent: f64, quote_volume: f64) -> TickerSummary {
+        TickerSummary {
+            symbol: symbol.to_string(),
+            last_price: 1.0,
+            price_change_percent,
+            high_price: 1.0,
+            low_price: 1.0,
+            volume: 1.0,
+            quote_volume,
+            event_time: Utc.timestamp_millis_opt(1_700_000_000_000).unwrap(),
+        }
+    }
+
+    fn symbols(tickers: Vec<&TickerSummary>) -> Vec<&str> {
+        tickers
+            .iter()
+            .map(|ticker| ticker.symbol.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_sorted_tickers() {
+        let mut storage = TickerStorage::new();
+        storage.update(summary("BTCUSDT", 2.0, 500.0));
+        storage.update(summary("ETHBTC", -3.0, 10.0));
+        storage.update(summary("BNBUSDT", 5.0, 100.0));
+        // Newer statistics replace older ones
+        storage.update(summary("BTCUSDT", 1.0, 900.0));
+
+        assert_eq!(
+            symbols(storage.sorted_tickers()),
+            ["BNBUSDT", "BTCUSDT", "ETHBTC"]
+        );
+        storage.cycle_sort();
+        assert_eq!(
+            symbols(storage.sorted_tickers()),
+            ["ETHBTC", "BTCUSDT", "BNBUSDT"]
+        );
+        storage.cycle_sort();
+        assert_eq!(
+            symbols(storage.sorted_tickers()),
+            ["BTCUSDT", "BNBUSDT", "ETHBTC"]
+        );
+    }
+
+    #[test]
+    fn test_quote_asset_filter() {
+        let mut storage = TickerStorage::new();
+        storage.set_quote_assets([
+            ("btcusdt".to_string(), "USDT".to_string()),
+            ("ethbtc".to_string(), "BTC".to_string()),
+        ]);
+        storage.update(summary("BTCUSDT", 2.0, 500.0));
+        storage.update(summary("ETHBTC", -3.0, 10.0));
+
+        storage.set_quote_filter(Some("BTC".to_string()));
+        assert_eq!(symbols(storage.sorted_tickers()), ["ETHBTC"]);
+        storage.set_quote_filter(None);
+        assert_eq!(storage.sorted_tickers().len(), 2);
+    }
+}
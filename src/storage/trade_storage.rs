@@ -0,0 +1,188 @@
+use crate::websocket::deserialize::{datetime_from_millis, f64_from_str};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Trade {
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "t")]
+    pub trade_id: u64,
+    #[serde(rename = "p", deserialize_with = "f64_from_str")]
+    pub price: f64,
+    #[serde(rename = "q", deserialize_with = "f64_from_str")]
+    pub quantity: f64,
+    #[serde(rename = "b")]
+    pub buyer_order_id: Option<u64>,
+    #[serde(rename = "a")]
+    pub seller_order_id: Option<u64>,
+    #[serde(rename = "X")]
+    pub order_type: Option<String>,
+    #[serde(rename = "T", deserialize_with = "datetime_from_millis")]
+    pub timestamp: DateTime<Utc>,
+    #[serde(rename = "m")]
+    pub is_buyer_maker: bool,
+}
+
+pub struct TradeStorage {
+    trades: VecDeque<Trade>,
+    capacity: usize,
+    total_price: f64,
+    total_volume: f64,
+    total_price_volume: f64,
+    buyer_maker_true: usize,
+    buyer_maker_false: usize,
+    max_price: f64,
+    min_price: f64,
+}
+
+impl TradeStorage {
+    // Create a new TradeStorage with specified capacity
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            trades: VecDeque::with_capacity(capacity),
+            capacity,
+            total_price: 0.0,
+            total_volume: 0.0,
+            total_price_volume: 0.0,
+            buyer_maker_true: 0,
+            buyer_maker_false: 0,
+            max_price: f64::MIN,
+            min_price: f64::MAX,
+        }
+    }
+
+    // Add a trade to the storage
+    pub fn add_trade(&mut self, trade: Trade) {
+        if self.trades.len() == self.capacity {
+            if let Some(old_trade) = self.trades.pop_front() {
+                self.total_price -= old_trade.price;
+                self.total_volume -= old_trade.quantity;
+                self.total_price_volume -= old_trade.price * old_trade.quantity;
+                if old_trade.is_buyer_maker {
+                    self.buyer_maker_true -= 1;
+                } else {
+                    self.buyer_maker_false -= 1;
+                }
+
+                if old_trade.price == self.max_price {
+                    self.max_price = self.trades.iter().map(|t| t.price).fold(f64::MIN, f64::max);
+                }
+                if old_trade.price == self.min_price {
+                    self.min_price = self.trades.iter().map(|t| t.price).fold(f64::MAX, f64::min);
+                }
+            }
+        }
+
+        self.total_price += trade.price;
+        self.total_volume += trade.quantity;
+        self.total_price_volume += trade.price * trade.quantity;
+        self.max_price = self.max_price.max(trade.price);
+        self.min_price = self.min_price.min(trade.price);
+        if trade.is_buyer_maker {
+            self.buyer_maker_true += 1;
+        } else {
+            self.buyer_maker_false += 1;
+        }
+        self.trades.push_back(trade);
+    }
+
+    // Get all trades
+    pub fn get_trades(&self) -> &VecDeque<Trade> {
+        &self.trades
+    }
+
+    // Get the most recent trade
+    pub fn last_trade(&self) -> Option<&Trade> {
+        self.trades.back()
+    }
+
+    // Calculate the average price of trades
+    pub fn calculate_average_price(&self) -> Option<f64> {
+        if self.trades.is_empty() {
+            return None;
+        }
+        Some(self.total_price / self.trades.len() as f64)
+    }
+
+    // Calculate the total volume of trades
+    pub fn total_volume(&self) -> f64 {
+        self.total_volume
+    }
+
+    // Calculate the volume-weighted average price (VWAP)
+    pub fn calculate_vwap(&self) -> Option<f64> {
+        if self.trades.is_empty() || self.total_volume == 0.0 {
+            return None;
+        }
+        Some(self.total_price_volume / self.total_volume)
+    }
+
+    // Calculate the maximum price of trades
+    pub fn calculate_max_price(&self) -> Option<f64> {
+        if self.trades.is_empty() {
+            return None;
+        }
+        Some(self.max_price)
+    }
+
+    // Calculate the minimum price of trades
+    pub fn calculate_min_price(&self) -> Option<f64> {
+        if self.trades.is_empty() {
+            return None;
+        }
+        Some(self.min_price)
+    }
+
+    // Calculate the buyer maker count
+    pub fn calculate_buyer_maker_count(&self) -> (usize, usize) {
+        (self.buyer_maker_true, self.buyer_maker_false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn trade(trade_id: u64, price: f64, quantity: f64, is_buyer_maker: bool) -> Trade {
+        Trade {
+            symbol: "BTCUSDT".to_string(),
+            trade_id,
+            price,
+            quantity,
+            buyer_order_id: None,
+            seller_order_id: None,
+            order_type: None,
+            timestamp: Utc.timestamp_millis_opt(1_700_000_000_000).unwrap(),
+            is_buyer_maker,
+        }
+    }
+
+    #[test]
+    fn test_rolling_window_evicts_oldest_trade() {
+        let mut storage = TradeStorage::new(2);
+        storage.add_trade(trade(1, 100.0, 1.0, true));
+        storage.add_trade(trade(2, 110.0, 1.0, false));
+        storage.add_trade(trade(3, 90.0, 2.0, false));
+
+        // The first trade falls out of the window
+        assert_eq!(storage.get_trades().len(), 2);
+        assert_eq!(storage.get_trades().front().unwrap().trade_id, 2);
+        assert_eq!(storage.calculate_max_price(), Some(110.0));
+        assert_eq!(storage.calculate_min_price(), Some(90.0));
+        assert_eq!(storage.calculate_buyer_maker_count(), (0, 2));
+        assert_eq!(storage.total_volume(), 3.0);
+    }
+
+    #[test]
+    fn test_vwap() {
+        let mut storage = TradeStorage::new(10);
+        assert_eq!(storage.calculate_vwap(), None);
+        storage.add_trade(trade(1, 100.0, 1.0, true));
+        storage.add_trade(trade(2, 200.0, 3.0, false));
+        assert_eq!(storage.calculate_vwap(), Some(175.0));
+        assert_eq!(storage.calculate_average_price(), Some(150.0));
+    }
+}
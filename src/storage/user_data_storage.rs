@@ -0,0 +1,156 @@
+use crate::user_data::message::{AccountPosition, Balance, BalanceUpdate, ExecutionReport};
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+/// The account's open orders, recent fills and balances from the user data stream
+pub struct UserDataStorage {
+    // Latest report per order id, dropped once the order can no longer trade
+    open_orders: HashMap<u64, ExecutionReport>,
+    fills: VecDeque<ExecutionReport>,
+    balances: BTreeMap<String, Balance>,
+    balances_updated: Option<DateTime<Utc>>,
+    balance_updates: VecDeque<BalanceUpdate>,
+    capacity: usize,
+}
+
+impl UserDataStorage {
+    // Create a UserDataStorage keeping `capacity` fills and balance updates
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            open_orders: HashMap::new(),
+            fills: VecDeque::with_capacity(capacity),
+            balances: BTreeMap::new(),
+            balances_updated: None,
+            balance_updates: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    // Apply an order update, recording it as a fill when it traded
+    pub fn add_execution_report(&mut self, report: ExecutionReport) {
+        if report.is_fill() {
+            if self.fills.len() == self.capacity {
+                self.fills.pop_front();
+            }
+            self.fills.push_back(report.clone());
+        }
+        if report.is_open() {
+            self.open_orders.insert(report.order_id, report);
+        } else {
+            self.open_orders.remove(&report.order_id);
+        }
+    }
+
+    // Replace the balances of the assets in an account update, ignoring older updates
+    pub fn update_account(&mut self, position: AccountPosition) {
+        if self
+            .balances_updated
+            .is_some_and(|updated| position.event_time < updated)
+        {
+            return;
+        }
+        self.balances_updated = Some(position.event_time);
+        for balance in position.balances {
+            self.balances.insert(balance.asset.clone(), balance);
+        }
+    }
+
+    // Add a deposit, withdrawal or transfer
+    pub fn add_balance_update(&mut self, update: BalanceUpdate) {
+        if self.balance_updates.len() == self.capacity {
+            self.balance_updates.pop_front();
+        }
+        self.balance_updates.push_back(update);
+    }
+
+    // Get the open orders, most recently updated first
+    pub fn open_orders(&self) -> Vec<&ExecutionReport> {
+        let mut orders: Vec<_> = self.open_orders.values().collect();
+        orders.sort_by_key(|order| std::cmp::Reverse(order.transaction_time));
+        orders
+    }
+
+    // Get the fills, oldest first
+    pub fn fills(&self) -> &VecDeque<ExecutionReport> {
+        &self.fills
+    }
+
+    // Get the non-empty balances by asset
+    pub fn balances(&self) -> Vec<&Balance> {
+        self.balances
+            .values()
+            .filter(|balance| balance.free + balance.locked > 0.0)
+            .collect()
+    }
+
+    // Get the balance updates, oldest first
+    pub fn balance_updates(&self) -> &VecDeque<BalanceUpdate> {
+        &self.balance_updates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn report(order_id: u64, execution_type: &str, status: &str, time: i64) -> ExecutionReport {
+        ExecutionReport {
+            symbol: "ETHBTC".to_string(),
+            order_id,
+            side: "BUY".to_string(),
+            order_type: "LIMIT".to_string(),
+            price: 0.1,
+            quantity: 1.0,
+            execution_type: execution_type.to_string(),
+            status: status.to_string(),
+            last_quantity: 0.5,
+            last_price: 0.1,
+            filled_quantity: 0.5,
+            commission: 0.0,
+            commission_asset: None,
+            transaction_time: Utc.timestamp_millis_opt(time).unwrap(),
+            is_maker: true,
+        }
+    }
+
+    #[test]
+    fn test_orders_stay_open_until_done() {
+        let mut storage = UserDataStorage::new(10);
+        storage.add_execution_report(report(1, "NEW", "NEW", 1));
+        storage.add_execution_report(report(2, "NEW", "NEW", 2));
+        storage.add_execution_report(report(1, "TRADE", "PARTIALLY_FILLED", 3));
+        let open: Vec<u64> = storage.open_orders().iter().map(|o| o.order_id).collect();
+        assert_eq!(open, [1, 2]);
+
+        storage.add_execution_report(report(1, "TRADE", "FILLED", 4));
+        storage.add_execution_report(report(2, "CANCELED", "CANCELED", 5));
+        assert!(storage.open_orders().is_empty());
+        assert_eq!(storage.fills().len(), 2);
+    }
+
+    #[test]
+    fn test_account_updates_replace_listed_balances() {
+        let mut storage = UserDataStorage::new(10);
+        let position = |time, asset: &str, free| AccountPosition {
+            event_time: Utc.timestamp_millis_opt(time).unwrap(),
+            balances: vec![Balance {
+                asset: asset.to_string(),
+                free,
+                locked: 0.0,
+            }],
+        };
+        storage.update_account(position(2, "BTC", 1.0));
+        storage.update_account(position(3, "ETH", 5.0));
+        // Arrived late, so BTC keeps its newer balance
+        storage.update_account(position(1, "BTC", 9.0));
+        storage.update_account(position(4, "ETH", 0.0));
+
+        let balances: Vec<_> = storage
+            .balances()
+            .iter()
+            .map(|balance| (balance.asset.as_str(), balance.free))
+            .collect();
+        assert_eq!(balances, [("BTC", 1.0)]);
+    }
+}
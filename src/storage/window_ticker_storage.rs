@@ -0,0 +1,115 @@
+use crate::websocket::message::WindowTicker;
+use std::collections::HashMap;
+
+pub struct WindowTickerStorage {
+    tickers: HashMap<(String, String), WindowTicker>,
+    last_updated: Option<String>,
+}
+
+impl WindowTickerStorage {
+    // Create an empty WindowTickerStorage
+    pub fn new() -> Self {
+        Self {
+            tickers: HashMap::new(),
+            last_updated: None,
+        }
+    }
+
+    // Replace the statistics for the ticker's symbol and window
+    pub fn add_ticker(&mut self, ticker: WindowTicker) {
+        if let Some(stored) = self.get_ticker(&ticker.symbol, ticker.window()) {
+            if ticker.event_time < stored.event_time {
+                return;
+            }
+        }
+        self.last_updated = Some(ticker.symbol.clone());
+        self.tickers
+            .insert((ticker.symbol.clone(), ticker.window().to_string()), ticker);
+    }
+
+    // Get the statistics for a symbol and window
+    pub fn get_ticker(&self, symbol: &str, window: &str) -> Option<&WindowTicker> {
+        self.tickers.get(&(symbol.to_string(), window.to_string()))
+    }
+
+    // Get every window received for a symbol, shortest first
+    pub fn get_windows(&self, symbol: &str) -> Vec<&WindowTicker> {
+        let mut tickers: Vec<_> = self
+            .tickers
+            .iter()
+            .filter(|((ticker_symbol, _), _)| ticker_symbol == symbol)
+            .map(|(_, ticker)| ticker)
+            .collect();
+        tickers.sort_by_key(|ticker| window_minutes(ticker.window()));
+        tickers
+    }
+
+    // Get the symbol updated most recently
+    pub fn last_updated_symbol(&self) -> Option<&str> {
+        self.last_updated.as_deref()
+    }
+}
+
+// Length of a window such as `4h` or `1d` in minutes, for ordering
+fn window_minutes(window: &str) -> u64 {
+    let (count, unit) = window.split_at(window.len().saturating_sub(1));
+    let count: u64 = count.parse().unwrap_or(0);
+    match unit {
+        "m" => count,
+        "h" => count * 60,
+        "d" => count * 60 * 24,
+        _ => u64::MAX,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn ticker(window: &str, event_time: i64, price_change_percent: f64) -> WindowTicker {
+        WindowTicker {
+            event_type: format!("{}Ticker", window),
+            event_time: Utc.timestamp_millis_opt(event_time).unwrap(),
+            symbol: "BTCUSDT".to_string(),
+            price_change: 0.0,
+            price_change_percent,
+            open_price: 100.0,
+            high_price: 110.0,
+            low_price: 90.0,
+            last_price: 105.0,
+            weighted_avg_price: 101.0,
+            volume: 1.0,
+            quote_volume: 100.0,
+            open_time: Utc.timestamp_millis_opt(0).unwrap(),
+            trade_count: 1,
+        }
+    }
+
+    #[test]
+    fn test_tickers_are_keyed_by_symbol_and_window() {
+        let mut storage = WindowTickerStorage::new();
+        storage.add_ticker(ticker("1d", 1_000, 3.0));
+        storage.add_ticker(ticker("1h", 1_000, 1.0));
+        storage.add_ticker(ticker("4h", 1_000, 2.0));
+        storage.add_ticker(ticker("1h", 2_000, 1.5));
+        // Older statistics do not replace newer ones
+        storage.add_ticker(ticker("1h", 1_500, 9.0));
+
+        let windows: Vec<_> = storage
+            .get_windows("BTCUSDT")
+            .iter()
+            .map(|ticker| (ticker.window().to_string(), ticker.price_change_percent))
+            .collect();
+        assert_eq!(
+            windows,
+            [
+                ("1h".to_string(), 1.5),
+                ("4h".to_string(), 2.0),
+                ("1d".to_string(), 3.0)
+            ]
+        );
+        assert_eq!(storage.last_updated_symbol(), Some("BTCUSDT"));
+        assert!(storage.get_ticker("ETHUSDT", "1h").is_none());
+    }
+}
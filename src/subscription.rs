@@ -1,22 +1,87 @@
 use reqwest::Error;
 use serde::Deserialize;
 
-#[derive(Deserialize, Debug)]
-struct Symbol {
-    symbol: String,
+/// Trading pair listed in exchangeInfo
+#[derive(Deserialize, Debug, Clone)]
+pub struct SymbolInfo {
+    pub symbol: String,
+    #[serde(rename = "baseAsset")]
+    pub base_asset: String,
+    #[serde(rename = "quoteAsset")]
+    pub quote_asset: String,
+    /// Underlying pair of a futures contract, e.g. `BTCUSD` for `BTCUSD_PERP`
+    #[serde(default)]
+    pub pair: Option<String>,
+    /// `PERPETUAL`, `CURRENT_QUARTER` or `NEXT_QUARTER` for futures contracts
+    #[serde(rename = "contractType", default)]
+    pub contract_type: Option<String>,
+    /// Quote value of one COIN-M contract, whose quantities are counted in contracts
+    #[serde(rename = "contractSize", default)]
+    pub contract_size: Option<f64>,
+}
+
+impl SymbolInfo {
+    // Get the pair continuous contract streams are named after
+    pub fn pair_or_symbol(&self) -> String {
+        self.pair.as_deref().unwrap_or(&self.symbol).to_lowercase()
+    }
 }
 
 #[derive(Deserialize, Debug)]
 struct ExchangeInfo {
-    symbols: Vec<Symbol>,
+    symbols: Vec<SymbolInfo>,
 }
 
-pub async fn fetch_symbols() -> Result<Vec<String>, Error> {
-    const URL: &str = "https://api.binance.com/api/v3/exchangeInfo";
-    let response = reqwest::get(URL).await?.json::<ExchangeInfo>().await?;
+/// Fetches every listed symbol from the `exchangeInfo` endpoint of the API at `api_url`
+pub async fn fetch_symbols(api_url: &str) -> Result<Vec<SymbolInfo>, Error> {
+    let url = format!("{}/exchangeInfo", api_url);
+    let response = reqwest::get(url).await?.json::<ExchangeInfo>().await?;
     Ok(response
         .symbols
         .into_iter()
-        .map(|s| s.symbol.to_lowercase())
+        .map(|s| SymbolInfo {
+            symbol: s.symbol.to_lowercase(),
+            ..s
+        })
         .collect())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::spawn_http_server;
+
+    #[tokio::test]
+    async fn test_fetch_symbols_from_configured_endpoint() {
+        let base_url = spawn_http_server(vec![(
+            "/api/v3/exchangeInfo",
+            r#"{"timezone":"UTC","symbols":[{"symbol":"BTCUSDT","baseAsset":"BTC","quoteAsset":"USDT","status":"TRADING"}]}"#.to_string(),
+        )])
+        .await;
+
+        let symbols = fetch_symbols(&format!("{}/api/v3", base_url))
+            .await
+            .unwrap();
+        assert_eq!(symbols.len(), 1);
+        assert_eq!(symbols[0].symbol, "btcusdt");
+        assert_eq!(symbols[0].quote_asset, "USDT");
+        assert_eq!(symbols[0].contract_size, None);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_coin_futures_contracts() {
+        let base_url = spawn_http_server(vec![(
+            "/dapi/v1/exchangeInfo",
+            r#"{"timezone":"UTC","symbols":[{"symbol":"BTCUSD_PERP","pair":"BTCUSD","contractType":"PERPETUAL","baseAsset":"BTC","quoteAsset":"USD","marginAsset":"BTC","contractSize":100},{"symbol":"BTCUSD_241227","pair":"BTCUSD","contractType":"CURRENT_QUARTER","baseAsset":"BTC","quoteAsset":"USD","marginAsset":"BTC","contractSize":100}]}"#.to_string(),
+        )])
+        .await;
+
+        let symbols = fetch_symbols(&format!("{}/dapi/v1", base_url))
+            .await
+            .unwrap();
+        assert_eq!(symbols[0].symbol, "btcusd_perp");
+        assert_eq!(symbols[1].contract_type.as_deref(), Some("CURRENT_QUARTER"));
+        assert_eq!(symbols[1].contract_size, Some(100.0));
+        assert_eq!(symbols[1].pair_or_symbol(), "btcusd");
+    }
+}
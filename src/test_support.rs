@@ -0,0 +1,186 @@
+//! Local stand-ins for the Binance REST and WebSocket servers used in tests
+
+use futures_util::{Sink, SinkExt, StreamExt};
+use serde_json::Value;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::protocol::Message;
+
+/// Serves canned JSON bodies over HTTP, matching on the request path and query.
+/// Returns the base URL of the server; unknown paths answer 404.
+pub async fn spawn_http_server(routes: Vec<(&'static str, String)>) -> String {
+    spawn_recording_http_server(routes).await.0
+}
+
+/// Like `spawn_http_server`, also returning the head of every request received,
+/// with its method, path and headers
+pub async fn spawn_recording_http_server(
+    routes: Vec<(&'static str, String)>,
+) -> (String, mpsc::UnboundedReceiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let (received_tx, received_rx) = mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+            let routes = routes.clone();
+            let received_tx = received_tx.clone();
+            tokio::spawn(async move {
+                let mut buffer = vec![0u8; 8192];
+                let read = socket.read(&mut buffer).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buffer[..read]);
+                let path = request.split_whitespace().nth(1).unwrap_or("/");
+                let _ = received_tx.send(request.to_string());
+
+                let response = match routes.iter().find(|(route, _)| *route == path) {
+                    Some((_, body)) => format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    ),
+                    None => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                        .to_string(),
+                };
+                let _ = socket.write_all(response.as_bytes()).await;
+                let _ = socket.shutdown().await;
+            });
+        }
+    });
+
+    (format!("http://{}", address), received_rx)
+}
+
+/// Accepts a single WebSocket connection, sends the frames and closes it.
+/// Returns the `ws://` URL of the server.
+pub async fn spawn_ws_server(frames: Vec<String>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        let (socket, _) = listener.accept().await.unwrap();
+        let mut ws_stream = tokio_tungstenite::accept_async(socket).await.unwrap();
+        for frame in frames {
+            ws_stream.send(Message::Text(frame)).await.unwrap();
+        }
+        ws_stream.send(Message::Close(None)).await.unwrap();
+        // Drain until the client acknowledges the close
+        while let Some(Ok(_)) = ws_stream.next().await {}
+    });
+
+    format!("ws://{}/", address)
+}
+
+/// Accepts one connection per session, in order; each connection waits for the
+/// client's first message and sends the session's frames. With `close` set the
+/// server then closes the connection, otherwise it stays open until the client leaves.
+/// Returns the `ws://` URL and every text message received from clients.
+pub async fn spawn_ws_sessions(
+    sessions: Vec<Vec<String>>,
+    close: bool,
+) -> (String, mpsc::UnboundedReceiver<String>) {
+    spawn_ws_sessions_closing(sessions.into_iter().map(|frames| (frames, close)).collect()).await
+}
+
+/// Like `spawn_ws_sessions`, choosing for each session whether the server closes it
+pub async fn spawn_ws_sessions_closing(
+    sessions: Vec<(Vec<String>, bool)>,
+) -> (String, mpsc::UnboundedReceiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let (received_tx, received_rx) = mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        for (frames, close) in sessions {
+            let (socket, _) = listener.accept().await.unwrap();
+            let received_tx = received_tx.clone();
+            // Serve connections concurrently so a client can overlap them
+            tokio::spawn(async move {
+                let mut ws_stream = tokio_tungstenite::accept_async(socket).await.unwrap();
+                let forward = |message: Message| {
+                    if let Message::Text(text) = message {
+                        let _ = received_tx.send(text);
+                    }
+                };
+
+                if let Some(Ok(message)) = ws_stream.next().await {
+                    forward(message);
+                }
+                for frame in frames {
+                    ws_stream.send(Message::Text(frame)).await.unwrap();
+                }
+                if close {
+                    ws_stream.send(Message::Close(None)).await.unwrap();
+                }
+                // Drain until the connection is closed
+                while let Some(Ok(message)) = ws_stream.next().await {
+                    forward(message);
+                }
+            });
+        }
+    });
+
+    (format!("ws://{}/", address), received_rx)
+}
+
+/// Accepts WebSocket connections and answers every JSON text message with the reply
+/// `respond` gives for it, if any. Returns the `ws://` URL of the server.
+pub async fn spawn_ws_responder(respond: fn(&Value) -> Option<Value>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        while let Ok((socket, _)) = listener.accept().await {
+            tokio::spawn(async move {
+                let mut ws_stream = tokio_tungstenite::accept_async(socket).await.unwrap();
+                while let Some(Ok(message)) = ws_stream.next().await {
+                    let Message::Text(text) = message else {
+                        continue;
+                    };
+                    let Ok(request) = serde_json::from_str(&text) else {
+                        continue;
+                    };
+                    if let Some(reply) = respond(&request) {
+                        let _ = ws_stream.send(Message::Text(reply.to_string())).await;
+                    }
+                }
+            });
+        }
+    });
+
+    format!("ws://{}/", address)
+}
+
+/// Sink recording every message sent to it, in place of a WebSocket write half
+pub struct MockSink {
+    tx: mpsc::UnboundedSender<Message>,
+}
+
+impl MockSink {
+    pub fn new() -> (Self, mpsc::UnboundedReceiver<Message>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        (MockSink { tx }, rx)
+    }
+}
+
+impl Sink<Message> for MockSink {
+    type Error = mpsc::error::SendError<Message>;
+
+    fn poll_ready(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: Message) -> Result<(), Self::Error> {
+        self.tx.send(item)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+}
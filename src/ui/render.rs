@@ -1,7 +1,19 @@
 use crate::storage::aggtrade_storage::AggTrade;
-use ratatui::layout::{Constraint, Direction, Layout};
+use crate::storage::depth_storage::DepthSnapshot;
+use crate::storage::futures_storage::time_to_funding;
+use crate::storage::kline_storage::Kline;
+use crate::storage::ticker_storage::TickerSummary;
+use crate::storage::trade_storage::Trade;
+use crate::user_data::message::{Balance, BalanceUpdate, ExecutionReport};
+use crate::websocket::client::connection::ConnectionState;
+use crate::websocket::client::redundant::DeliveryStats;
+use crate::websocket::deserialize::PriceLevel;
+use crate::websocket::message::{CompositeIndex, Liquidation, MarkPrice, WindowTicker};
+use chrono::{DateTime, Utc};
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
 use ratatui::style::{Color, Style};
 use ratatui::text::{Line, Span};
+use ratatui::widgets::canvas::{Canvas, Line as CanvasLine};
 use ratatui::widgets::{Axis, Block, Borders, Cell, Chart, Dataset, Gauge, Paragraph, Row, Table};
 
 pub struct RenderData<'a> {
@@ -10,6 +22,8 @@ pub struct RenderData<'a> {
     pub median_price: f64,
     pub std_dev: f64,
     pub total_volume: f64,
+    /// Contracts traded, for COIN-M symbols whose volume is converted from them
+    pub total_contracts: Option<f64>,
     pub volume_weighted_avg_price: f64,
     pub max_price: f64,
     pub min_price: f64,
@@ -19,7 +33,130 @@ pub struct RenderData<'a> {
     pub last_price: f64,
     pub prices: &'a [(f64, f64)],
     pub buyer_maker_count: (usize, usize),
+    pub depth: Option<&'a DepthSnapshot>,
+    pub quote: Option<QuoteData<'a>>,
+    pub performance: PerformanceData<'a>,
+}
+
+pub struct TradeRenderData<'a> {
+    pub trades: &'a [Trade],
+    pub avg_price: f64,
+    pub total_volume: f64,
+    pub volume_weighted_avg_price: f64,
+    pub max_price: f64,
+    pub min_price: f64,
+    pub last_price: f64,
+    pub prices: &'a [(f64, f64)],
+    pub buyer_maker_count: (usize, usize),
+    pub depth: Option<&'a DepthSnapshot>,
+    pub quote: Option<QuoteData<'a>>,
+    pub performance: PerformanceData<'a>,
+}
+
+pub struct KlineRenderData<'a> {
+    pub candles: &'a [Kline],
+    pub current: Option<&'a Kline>,
+    pub symbol: &'a str,
+    pub interval: &'a str,
+    pub max_price: f64,
+    pub min_price: f64,
+    pub total_volume: f64,
+    /// COIN-M candles count volume in contracts and send base volume as quote volume
+    pub in_contracts: bool,
+    pub taker_buy_ratio: f64,
+    pub closed_count: usize,
+    pub performance: PerformanceData<'a>,
+}
+
+pub struct DepthRenderData<'a> {
+    pub snapshot: &'a DepthSnapshot,
+    pub performance: PerformanceData<'a>,
+}
+
+pub struct OrderBookRenderData<'a> {
+    pub snapshot: &'a DepthSnapshot,
+    pub synced: bool,
+    pub buffered_count: usize,
+    pub resync_count: u64,
+    pub bid_levels: usize,
+    pub ask_levels: usize,
+    pub bid_depth_10bps: f64,
+    pub ask_depth_10bps: f64,
+    pub last_event_time: Option<DateTime<Utc>>,
+    pub performance: PerformanceData<'a>,
+}
+
+pub struct BookTickerRenderData<'a> {
+    pub quotes: &'a [QuoteData<'a>],
+    pub current: QuoteData<'a>,
+    pub spreads: &'a [(f64, f64)],
+    pub performance: PerformanceData<'a>,
+}
+
+pub struct TickerRenderData<'a> {
+    pub tickers: &'a [&'a TickerSummary],
+    pub sort_label: &'a str,
+    pub quote_filter: Option<&'a str>,
+    pub gainers: usize,
+    pub losers: usize,
+    pub total_quote_volume: f64,
+    pub last_event_time: Option<DateTime<Utc>>,
+    pub performance: PerformanceData<'a>,
+}
+
+pub struct WindowTickerRenderData<'a> {
+    pub symbol: &'a str,
+    pub windows: &'a [&'a WindowTicker],
+    pub performance: PerformanceData<'a>,
+}
+
+pub struct FuturesRenderData<'a> {
+    pub current: Option<&'a MarkPrice>,
+    pub mark_prices: &'a [&'a MarkPrice],
+    pub mark_history: &'a [(f64, f64)],
+    pub liquidations: &'a [&'a Liquidation],
+    pub liquidated_notional: (f64, f64),
+    pub index: Option<&'a CompositeIndex>,
+    pub now: DateTime<Utc>,
+    pub performance: PerformanceData<'a>,
+}
+
+pub struct UserDataRenderData<'a> {
+    pub open_orders: &'a [&'a ExecutionReport],
+    pub fills: &'a [&'a ExecutionReport],
+    pub balances: &'a [&'a Balance],
+    pub balance_updates: &'a [&'a BalanceUpdate],
+    pub performance: PerformanceData<'a>,
+}
+
+pub struct QuoteData<'a> {
+    pub symbol: &'a str,
+    pub bid_price: f64,
+    pub bid_quantity: f64,
+    pub ask_price: f64,
+    pub ask_quantity: f64,
+    pub spread: f64,
+    pub spread_bps: f64,
+    pub average_spread: f64,
+    pub average_spread_bps: f64,
+    pub mid_price: f64,
+    pub microprice: f64,
+}
+
+pub struct PerformanceData<'a> {
     pub message_count: u64,
+    pub decode_errors: u64,
+    pub last_source: &'a str,
+    pub connection_state: &'a ConnectionState,
+    pub reconnect_count: u64,
+    pub streams: &'a [String],
+    pub prompt: Option<&'a str>,
+    pub last_request: Option<&'a str>,
+    pub confirmed_streams: Option<&'a [String]>,
+    pub deliveries: &'a [DeliveryStats],
+    pub gap_count: u64,
+    pub backfilled_count: u64,
+    pub backfill_failures: u64,
     pub avg_arrival_interval: f64,
     pub avg_processing_time: f64,
     pub arrival_intervals: &'a [(f64, f64)],
@@ -98,8 +235,8 @@ pub fn render_ui(f: &mut ratatui::Frame, data: &RenderData) {
         .header(header)
         .block(Block::default().borders(Borders::ALL).title("Trades"));
 
-    // Render table
-    f.render_widget(table, chunks[0]);
+    // Render table, with the order book ladder beside it when available
+    render_with_ladder(f, chunks[0], table, data.depth);
 
     // Layout for statistics and additional charts
     let stats_chunks = Layout::default()
@@ -139,8 +276,8 @@ pub fn render_ui(f: &mut ratatui::Frame, data: &RenderData) {
 
     f.render_widget(stats_column_1, stats_chunks[0]);
 
-    // Statistics in column 2
-    let stats_column_2 = Paragraph::new(vec![
+    // Statistics in column 2, followed by the best bid/ask when bookTicker is subscribed
+    let mut stats_column_2_lines = vec![
         Line::from(vec![Span::raw(format!(
             "VWAP: {:.2}",
             data.volume_weighted_avg_price
@@ -154,8 +291,18 @@ pub fn render_ui(f: &mut ratatui::Frame, data: &RenderData) {
             data.std_dev
         ))]),
         Line::from(vec![Span::raw(format!("RSI: {:.2}", data.rsi))]),
-    ])
-    .block(
+    ];
+    if let Some(contracts) = data.total_contracts {
+        stats_column_2_lines.insert(
+            2,
+            Line::from(vec![Span::raw(format!(
+                "Total Contracts: {:.0}",
+                contracts
+            ))]),
+        );
+    }
+    stats_column_2_lines.extend(quote_lines(data.quote.as_ref()));
+    let stats_column_2 = Paragraph::new(stats_column_2_lines).block(
         Block::default()
             .borders(Borders::ALL)
             .title("Statistics (contd.)"),
@@ -164,73 +311,1651 @@ pub fn render_ui(f: &mut ratatui::Frame, data: &RenderData) {
     // Render statistics column 2
     f.render_widget(stats_column_2, stats_chunks[1]);
 
-    // Calculate buyer maker percentages
-    let (buyer_maker_true, buyer_maker_false) = data.buyer_maker_count;
-    let total_buyer_maker = buyer_maker_true + buyer_maker_false;
-    let buyer_maker_true_percent = if total_buyer_maker > 0 {
-        (buyer_maker_true as f64 / total_buyer_maker as f64) * 100.0
-    } else {
-        0.0
-    };
-    let buyer_maker_false_percent = 100.0 - buyer_maker_true_percent;
+    // Render the buyer maker gauge
+    render_buyer_maker_gauge(f, stats_chunks[2], data.buyer_maker_count);
 
-    // Gauge for buyer maker percentages
-    let buyer_maker_gauge = Gauge::default()
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title("Buyer Maker Distribution"),
+    // Render performance statistics
+    render_performance_stats(f, stats_chunks[3], &data.performance);
+
+    // Render price chart
+    render_price_chart(f, chunks[2], data.prices);
+
+    // Render performance chart
+    render_performance_chart(f, chunks[3], &data.performance);
+}
+
+pub fn render_trade_ui(f: &mut ratatui::Frame, data: &TradeRenderData) {
+    // Layout with four vertical chunks
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints(
+            [
+                Constraint::Percentage(40),
+                Constraint::Percentage(20),
+                Constraint::Percentage(20),
+                Constraint::Percentage(20),
+            ]
+            .as_ref(),
         )
-        .gauge_style(
-            Style::default()
-                .fg(Color::Magenta)
-                .bg(Color::Black)
-                .add_modifier(ratatui::style::Modifier::ITALIC),
+        .split(f.size());
+
+    // Table header
+    let header = Row::new(vec![
+        Cell::from("Symbol"),
+        Cell::from("Trade ID"),
+        Cell::from("Price"),
+        Cell::from("Quantity"),
+        Cell::from("Buyer Order ID"),
+        Cell::from("Seller Order ID"),
+        Cell::from("Order Type"),
+        Cell::from("Timestamp"),
+        Cell::from("Buyer Maker"),
+    ])
+    .style(Style::default().fg(Color::Yellow).bg(Color::Blue));
+
+    // Table rows
+    let trades: Vec<Row> = data
+        .trades
+        .iter()
+        .map(|trade| {
+            Row::new(vec![
+                Cell::from(trade.symbol.clone()),
+                Cell::from(trade.trade_id.to_string()),
+                Cell::from(format!("{:.2}", trade.price)),
+                Cell::from(format!("{:.4}", trade.quantity)),
+                Cell::from(
+                    trade
+                        .buyer_order_id
+                        .map_or("-".to_string(), |id| id.to_string()),
+                ),
+                Cell::from(
+                    trade
+                        .seller_order_id
+                        .map_or("-".to_string(), |id| id.to_string()),
+                ),
+                Cell::from(trade.order_type.clone().unwrap_or_else(|| "-".to_string())),
+                Cell::from(format!(
+                    "{}.{}",
+                    trade.timestamp.format("%Y-%m-%d %H:%M:%S"),
+                    trade.timestamp.timestamp_subsec_millis()
+                )),
+                Cell::from(if trade.is_buyer_maker { "Buy" } else { "Sell" }.to_string()),
+            ])
+        })
+        .collect();
+
+    const WIDTHS: [Constraint; 9] = [
+        Constraint::Length(10),
+        Constraint::Length(15),
+        Constraint::Length(10),
+        Constraint::Length(10),
+        Constraint::Length(15),
+        Constraint::Length(15),
+        Constraint::Length(12),
+        Constraint::Length(24),
+        Constraint::Length(15),
+    ];
+    // Table widget
+    let table = Table::new(trades, WIDTHS)
+        .header(header)
+        .block(Block::default().borders(Borders::ALL).title("Trades"));
+
+    // Render table, with the order book ladder beside it when available
+    render_with_ladder(f, chunks[0], table, data.depth);
+
+    // Layout for statistics and additional charts
+    let stats_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .margin(1)
+        .constraints(
+            [
+                Constraint::Percentage(25),
+                Constraint::Percentage(25),
+                Constraint::Percentage(25),
+                Constraint::Percentage(25),
+            ]
+            .as_ref(),
         )
-        .percent(buyer_maker_true_percent as u16)
-        .label(Span::styled(
-            format!(
-                "Buy: {:.1}%, Sell: {:.1}%",
-                buyer_maker_true_percent, buyer_maker_false_percent
-            ),
-            Style::default().add_modifier(ratatui::style::Modifier::BOLD),
-        ));
+        .split(chunks[1]);
+
+    // Statistics paragraph
+    let stats = Paragraph::new(vec![
+        Line::from(vec![Span::raw(format!(
+            "Last Price: {:.2}",
+            data.last_price
+        ))]),
+        Line::from(vec![Span::raw(format!(
+            "Average Price: {:.2}",
+            data.avg_price
+        ))]),
+        Line::from(vec![Span::raw(format!("Max Price: {:.2}", data.max_price))]),
+        Line::from(vec![Span::raw(format!("Min Price: {:.2}", data.min_price))]),
+    ])
+    .block(Block::default().borders(Borders::ALL).title("Statistics"));
+
+    f.render_widget(stats, stats_chunks[0]);
+
+    // Statistics in column 2, followed by the best bid/ask when bookTicker is subscribed
+    let mut stats_column_2_lines = vec![
+        Line::from(vec![Span::raw(format!(
+            "VWAP: {:.2}",
+            data.volume_weighted_avg_price
+        ))]),
+        Line::from(vec![Span::raw(format!(
+            "Total Volume: {:.4}",
+            data.total_volume
+        ))]),
+    ];
+    stats_column_2_lines.extend(quote_lines(data.quote.as_ref()));
+    let stats_column_2 = Paragraph::new(stats_column_2_lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Statistics (contd.)"),
+    );
+
+    f.render_widget(stats_column_2, stats_chunks[1]);
 
     // Render the buyer maker gauge
-    f.render_widget(buyer_maker_gauge, stats_chunks[2]);
+    render_buyer_maker_gauge(f, stats_chunks[2], data.buyer_maker_count);
 
-    // Performance statistics
-    let performance_stats = Paragraph::new(vec![
+    // Render performance statistics
+    render_performance_stats(f, stats_chunks[3], &data.performance);
+
+    // Render price chart
+    render_price_chart(f, chunks[2], data.prices);
+
+    // Render performance chart
+    render_performance_chart(f, chunks[3], &data.performance);
+}
+
+pub fn render_kline_ui(f: &mut ratatui::Frame, data: &KlineRenderData) {
+    // Layout with three vertical chunks
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints(
+            [
+                Constraint::Percentage(50),
+                Constraint::Percentage(20),
+                Constraint::Percentage(30),
+            ]
+            .as_ref(),
+        )
+        .split(f.size());
+
+    // Render candlestick chart
+    render_candlestick_chart(
+        f,
+        chunks[0],
+        data.candles,
+        &format!("{} {} Candles", data.symbol.to_uppercase(), data.interval),
+    );
+
+    // Layout for statistics
+    let stats_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .margin(1)
+        .constraints(
+            [
+                Constraint::Percentage(34),
+                Constraint::Percentage(33),
+                Constraint::Percentage(33),
+            ]
+            .as_ref(),
+        )
+        .split(chunks[1]);
+
+    // Statistics for the candle being built
+    let current = data.current;
+    let stats = Paragraph::new(vec![
         Line::from(vec![Span::raw(format!(
-            "Messages Processed: {}",
-            data.message_count
+            "Open: {:.2}  Close: {:.2}",
+            current.map_or(0.0, |kline| kline.open),
+            current.map_or(0.0, |kline| kline.close)
         ))]),
         Line::from(vec![Span::raw(format!(
-            "Avg Arrival Interval: {:.2} ms",
-            data.avg_arrival_interval
+            "High: {:.2}  Low: {:.2}",
+            current.map_or(0.0, |kline| kline.high),
+            current.map_or(0.0, |kline| kline.low)
         ))]),
         Line::from(vec![Span::raw(format!(
-            "Avg Processing Time: {:.2} ms",
-            data.avg_processing_time
+            "{}: {:.4}  {}: {:.2}",
+            if data.in_contracts {
+                "Contracts"
+            } else {
+                "Volume"
+            },
+            current.map_or(0.0, |kline| kline.volume),
+            if data.in_contracts { "Base" } else { "Quote" },
+            current.map_or(0.0, |kline| kline.quote_volume)
+        ))]),
+        Line::from(vec![Span::raw(format!(
+            "Taker Buy {}: {:.2}",
+            if data.in_contracts { "Base" } else { "Quote" },
+            current.map_or(0.0, |kline| kline.taker_buy_quote_volume)
+        ))]),
+        Line::from(vec![Span::raw(format!(
+            "Trades: {}",
+            current.map_or(0, |kline| kline.trade_count)
+        ))]),
+        Line::from(vec![Span::raw(format!(
+            "Status: {} (closes {})",
+            if current.is_some_and(|kline| kline.is_closed) {
+                "Closed"
+            } else {
+                "Open"
+            },
+            current.map_or(String::from("-"), |kline| kline
+                .close_time
+                .format("%H:%M:%S")
+                .to_string())
         ))]),
     ])
     .block(
         Block::default()
             .borders(Borders::ALL)
-            .title("Performance Stats"),
+            .title("Current Candle"),
     );
 
+    f.render_widget(stats, stats_chunks[0]);
+
+    // Statistics over all stored candles
+    let series_stats = Paragraph::new(vec![
+        Line::from(vec![Span::raw(format!("Max Price: {:.2}", data.max_price))]),
+        Line::from(vec![Span::raw(format!("Min Price: {:.2}", data.min_price))]),
+        Line::from(vec![Span::raw(format!(
+            "Total Volume: {:.4}",
+            data.total_volume
+        ))]),
+        Line::from(vec![Span::raw(format!(
+            "Taker Buy Ratio: {:.1}%",
+            data.taker_buy_ratio * 100.0
+        ))]),
+        Line::from(vec![Span::raw(format!(
+            "Closed Candles: {}",
+            data.closed_count
+        ))]),
+    ])
+    .block(Block::default().borders(Borders::ALL).title("Statistics"));
+
+    f.render_widget(series_stats, stats_chunks[1]);
+
     // Render performance statistics
-    f.render_widget(performance_stats, stats_chunks[3]);
+    render_performance_stats(f, stats_chunks[2], &data.performance);
+
+    // Table header
+    let header = Row::new(vec![
+        Cell::from("Open Time"),
+        Cell::from("Open"),
+        Cell::from("High"),
+        Cell::from("Low"),
+        Cell::from("Close"),
+        Cell::from("Volume"),
+        Cell::from("Trades"),
+        Cell::from("Taker Buy Vol"),
+        Cell::from("Closed"),
+    ])
+    .style(Style::default().fg(Color::Yellow).bg(Color::Blue));
+
+    // Table rows, newest first
+    let candles: Vec<Row> = data
+        .candles
+        .iter()
+        .rev()
+        .map(|kline| {
+            Row::new(vec![
+                Cell::from(kline.open_time.format("%Y-%m-%d %H:%M:%S").to_string()),
+                Cell::from(format!("{:.2}", kline.open)),
+                Cell::from(format!("{:.2}", kline.high)),
+                Cell::from(format!("{:.2}", kline.low)),
+                Cell::from(format!("{:.2}", kline.close)),
+                Cell::from(format!("{:.4}", kline.volume)),
+                Cell::from(kline.trade_count.to_string()),
+                Cell::from(format!("{:.4}", kline.taker_buy_base_volume)),
+                Cell::from(if kline.is_closed { "Yes" } else { "No" }.to_string()),
+            ])
+        })
+        .collect();
 
+    const WIDTHS: [Constraint; 9] = [
+        Constraint::Length(20),
+        Constraint::Length(12),
+        Constraint::Length(12),
+        Constraint::Length(12),
+        Constraint::Length(12),
+        Constraint::Length(14),
+        Constraint::Length(8),
+        Constraint::Length(14),
+        Constraint::Length(8),
+    ];
+    // Table widget
+    let table = Table::new(candles, WIDTHS)
+        .header(header)
+        .block(Block::default().borders(Borders::ALL).title("Klines"));
+
+    // Render table
+    f.render_widget(table, chunks[2]);
+}
+
+/// Renders klines as a candlestick chart
+fn render_candlestick_chart(f: &mut ratatui::Frame, area: Rect, candles: &[Kline], title: &str) {
     // Calculate price bounds
-    let price_min = data
-        .prices
+    let price_min = candles
         .iter()
-        .map(|&(_, y)| y)
+        .map(|kline| kline.low)
         .fold(f64::INFINITY, f64::min);
-    let price_max = data
-        .prices
+    let price_max = candles
+        .iter()
+        .map(|kline| kline.high)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let (price_min, price_max) = if candles.is_empty() {
+        (0.0, 1.0)
+    } else {
+        (price_min, price_max)
+    };
+
+    let candle_chart = Canvas::default()
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title.to_string()),
+        )
+        .marker(ratatui::symbols::Marker::Braille)
+        .x_bounds([0.0, candles.len().max(1) as f64])
+        .y_bounds([price_min, price_max])
+        .paint(|ctx| {
+            for (i, kline) in candles.iter().enumerate() {
+                let color = if kline.close >= kline.open {
+                    Color::Green
+                } else {
+                    Color::Red
+                };
+                let center = i as f64 + 0.5;
+
+                // Wick from low to high
+                ctx.draw(&CanvasLine {
+                    x1: center,
+                    y1: kline.low,
+                    x2: center,
+                    y2: kline.high,
+                    color,
+                });
+
+                // Body from open to close, filled with vertical strokes
+                for offset in [-0.3, -0.15, 0.15, 0.3] {
+                    ctx.draw(&CanvasLine {
+                        x1: center + offset,
+                        y1: kline.open,
+                        x2: center + offset,
+                        y2: kline.close,
+                        color,
+                    });
+                }
+            }
+        });
+
+    // Render candlestick chart
+    f.render_widget(candle_chart, area);
+}
+
+pub fn render_depth_ui(f: &mut ratatui::Frame, data: &DepthRenderData) {
+    // Layout with two vertical chunks
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([Constraint::Percentage(70), Constraint::Percentage(30)].as_ref())
+        .split(f.size());
+
+    // Layout for ladder and statistics
+    let top_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(
+            [
+                Constraint::Percentage(40),
+                Constraint::Percentage(30),
+                Constraint::Percentage(30),
+            ]
+            .as_ref(),
+        )
+        .split(chunks[0]);
+
+    // Render order book ladder
+    render_depth_ladder(f, top_chunks[0], data.snapshot);
+
+    // Order book statistics
+    let snapshot = data.snapshot;
+    let book_stats = Paragraph::new(vec![
+        Line::from(vec![Span::raw(format!(
+            "Best Bid: {:.2} ({:.4})",
+            snapshot.best_bid().map_or(0.0, |level| level.price),
+            snapshot.best_bid().map_or(0.0, |level| level.quantity)
+        ))]),
+        Line::from(vec![Span::raw(format!(
+            "Best Ask: {:.2} ({:.4})",
+            snapshot.best_ask().map_or(0.0, |level| level.price),
+            snapshot.best_ask().map_or(0.0, |level| level.quantity)
+        ))]),
+        Line::from(vec![Span::raw(format!(
+            "Spread: {:.4}",
+            snapshot.calculate_spread().unwrap_or(0.0)
+        ))]),
+        Line::from(vec![Span::raw(format!(
+            "Mid Price: {:.4}",
+            snapshot.calculate_mid_price().unwrap_or(0.0)
+        ))]),
+        Line::from(vec![Span::raw(format!(
+            "Bid Volume: {:.4}",
+            snapshot.bid_volume()
+        ))]),
+        Line::from(vec![Span::raw(format!(
+            "Ask Volume: {:.4}",
+            snapshot.ask_volume()
+        ))]),
+        Line::from(vec![Span::raw(format!(
+            "Imbalance: {:+.2}",
+            snapshot.calculate_imbalance().unwrap_or(0.0)
+        ))]),
+        Line::from(vec![Span::raw(format!(
+            "Last Update ID: {}",
+            snapshot.last_update_id
+        ))]),
+    ])
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!("{} Book", snapshot.symbol)),
+    );
+
+    f.render_widget(book_stats, top_chunks[1]);
+
+    // Render performance statistics
+    render_performance_stats(f, top_chunks[2], &data.performance);
+
+    // Render cumulative depth chart
+    render_depth_chart(f, chunks[1], snapshot);
+}
+
+pub fn render_order_book_ui(f: &mut ratatui::Frame, data: &OrderBookRenderData) {
+    // Layout with two vertical chunks
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([Constraint::Percentage(70), Constraint::Percentage(30)].as_ref())
+        .split(f.size());
+
+    // Layout for ladder and statistics
+    let top_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(
+            [
+                Constraint::Percentage(40),
+                Constraint::Percentage(30),
+                Constraint::Percentage(30),
+            ]
+            .as_ref(),
+        )
+        .split(chunks[0]);
+
+    // Render order book ladder
+    render_depth_ladder(f, top_chunks[0], data.snapshot);
+
+    // Order book statistics
+    let snapshot = data.snapshot;
+    let status = if data.synced {
+        Span::styled("Synced", Style::default().fg(Color::Green))
+    } else {
+        Span::styled(
+            format!("Syncing ({} buffered)", data.buffered_count),
+            Style::default().fg(Color::Yellow),
+        )
+    };
+    let book_stats = Paragraph::new(vec![
+        Line::from(vec![Span::raw("Status: "), status]),
+        Line::from(vec![Span::raw(format!(
+            "Best Bid: {:.2} ({:.4})",
+            snapshot.best_bid().map_or(0.0, |level| level.price),
+            snapshot.best_bid().map_or(0.0, |level| level.quantity)
+        ))]),
+        Line::from(vec![Span::raw(format!(
+            "Best Ask: {:.2} ({:.4})",
+            snapshot.best_ask().map_or(0.0, |level| level.price),
+            snapshot.best_ask().map_or(0.0, |level| level.quantity)
+        ))]),
+        Line::from(vec![Span::raw(format!(
+            "Spread: {:.4}",
+            snapshot.calculate_spread().unwrap_or(0.0)
+        ))]),
+        Line::from(vec![Span::raw(format!(
+            "Mid Price: {:.4}",
+            snapshot.calculate_mid_price().unwrap_or(0.0)
+        ))]),
+        Line::from(vec![Span::raw(format!(
+            "Depth ±10bps: {:.4} / {:.4}",
+            data.bid_depth_10bps, data.ask_depth_10bps
+        ))]),
+        Line::from(vec![Span::raw(format!(
+            "Levels: {} bids / {} asks",
+            data.bid_levels, data.ask_levels
+        ))]),
+        Line::from(vec![Span::raw(format!(
+            "Last Update ID: {}",
+            snapshot.last_update_id
+        ))]),
+        Line::from(vec![Span::raw(format!(
+            "Last Event: {}",
+            data.last_event_time.map_or(String::from("-"), |time| time
+                .format("%H:%M:%S%.3f")
+                .to_string())
+        ))]),
+        Line::from(vec![Span::raw(format!("Resyncs: {}", data.resync_count))]),
+    ])
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!("{} Order Book", snapshot.symbol)),
+    );
+
+    f.render_widget(book_stats, top_chunks[1]);
+
+    // Render performance statistics
+    render_performance_stats(f, top_chunks[2], &data.performance);
+
+    // Render cumulative depth chart
+    render_depth_chart(f, chunks[1], snapshot);
+}
+
+pub fn render_book_ticker_ui(f: &mut ratatui::Frame, data: &BookTickerRenderData) {
+    // Layout with three vertical chunks
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints(
+            [
+                Constraint::Percentage(45),
+                Constraint::Percentage(25),
+                Constraint::Percentage(30),
+            ]
+            .as_ref(),
+        )
+        .split(f.size());
+
+    // Table header
+    let header = Row::new(vec![
+        Cell::from("Symbol"),
+        Cell::from("Bid Qty"),
+        Cell::from("Bid"),
+        Cell::from("Ask"),
+        Cell::from("Ask Qty"),
+        Cell::from("Spread"),
+        Cell::from("Spread (bps)"),
+        Cell::from("Mid Price"),
+        Cell::from("Microprice"),
+    ])
+    .style(Style::default().fg(Color::Yellow).bg(Color::Blue));
+
+    // Table rows
+    let quotes: Vec<Row> = data
+        .quotes
+        .iter()
+        .map(|quote| {
+            Row::new(vec![
+                Cell::from(quote.symbol.to_string()),
+                Cell::from(format!("{:.4}", quote.bid_quantity)),
+                Cell::from(format!("{:.2}", quote.bid_price)),
+                Cell::from(format!("{:.2}", quote.ask_price)),
+                Cell::from(format!("{:.4}", quote.ask_quantity)),
+                Cell::from(format!("{:.4}", quote.spread)),
+                Cell::from(format!("{:.2}", quote.spread_bps)),
+                Cell::from(format!("{:.4}", quote.mid_price)),
+                Cell::from(format!("{:.4}", quote.microprice)),
+            ])
+        })
+        .collect();
+
+    const WIDTHS: [Constraint; 9] = [
+        Constraint::Length(12),
+        Constraint::Length(14),
+        Constraint::Length(12),
+        Constraint::Length(12),
+        Constraint::Length(14),
+        Constraint::Length(10),
+        Constraint::Length(14),
+        Constraint::Length(14),
+        Constraint::Length(14),
+    ];
+    // Table widget
+    let table = Table::new(quotes, WIDTHS)
+        .header(header)
+        .block(Block::default().borders(Borders::ALL).title("Best Bid/Ask"));
+
+    // Render table
+    f.render_widget(table, chunks[0]);
+
+    // Layout for statistics
+    let stats_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .margin(1)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+        .split(chunks[1]);
+
+    // Statistics for the most recently updated symbol
+    let current = &data.current;
+    let mut stats_lines = vec![
+        Line::from(vec![Span::raw(format!(
+            "Best Bid: {:.2} ({:.4})",
+            current.bid_price, current.bid_quantity
+        ))]),
+        Line::from(vec![Span::raw(format!(
+            "Best Ask: {:.2} ({:.4})",
+            current.ask_price, current.ask_quantity
+        ))]),
+    ];
+    stats_lines.extend(quote_lines(Some(current)));
+    let stats = Paragraph::new(stats_lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!("{} Statistics", current.symbol)),
+    );
+
+    f.render_widget(stats, stats_chunks[0]);
+
+    // Render performance statistics
+    render_performance_stats(f, stats_chunks[1], &data.performance);
+
+    // Render spread chart
+    render_spread_chart(f, chunks[2], data.spreads);
+}
+
+pub fn render_ticker_ui(f: &mut ratatui::Frame, data: &TickerRenderData) {
+    // Layout with two vertical chunks
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([Constraint::Percentage(75), Constraint::Percentage(25)].as_ref())
+        .split(f.size());
+
+    // Table header
+    let header = Row::new(vec![
+        Cell::from("#"),
+        Cell::from("Symbol"),
+        Cell::from("Last Price"),
+        Cell::from("24h Change"),
+        Cell::from("24h High"),
+        Cell::from("24h Low"),
+        Cell::from("Volume"),
+        Cell::from("Quote Volume"),
+    ])
+    .style(Style::default().fg(Color::Yellow).bg(Color::Blue));
+
+    // Table rows; only the rows that fit are built
+    let visible_rows = chunks[0].height.saturating_sub(3) as usize;
+    let tickers: Vec<Row> = data
+        .tickers
+        .iter()
+        .take(visible_rows)
+        .enumerate()
+        .map(|(i, ticker)| {
+            let color = if ticker.price_change_percent >= 0.0 {
+                Color::Green
+            } else {
+                Color::Red
+            };
+            Row::new(vec![
+                Cell::from((i + 1).to_string()),
+                Cell::from(ticker.symbol.clone()),
+                Cell::from(format!("{:.8}", ticker.last_price)),
+                Cell::from(format!("{:+.2}%", ticker.price_change_percent))
+                    .style(Style::default().fg(color)),
+                Cell::from(format!("{:.8}", ticker.high_price)),
+                Cell::from(format!("{:.8}", ticker.low_price)),
+                Cell::from(format!("{:.2}", ticker.volume)),
+                Cell::from(format!("{:.2}", ticker.quote_volume)),
+            ])
+        })
+        .collect();
+
+    const WIDTHS: [Constraint; 8] = [
+        Constraint::Length(5),
+        Constraint::Length(14),
+        Constraint::Length(18),
+        Constraint::Length(12),
+        Constraint::Length(18),
+        Constraint::Length(18),
+        Constraint::Length(20),
+        Constraint::Length(20),
+    ];
+    // Table widget
+    let table = Table::new(tickers, WIDTHS).header(header).block(
+        Block::default().borders(Borders::ALL).title(format!(
+            "Market Overview: {} ({} quote) - press 's' to change order",
+            data.sort_label,
+            data.quote_filter.unwrap_or("any")
+        )),
+    );
+
+    // Render table
+    f.render_widget(table, chunks[0]);
+
+    // Layout for statistics
+    let stats_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .margin(1)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+        .split(chunks[1]);
+
+    // Market breadth statistics
+    let stats = Paragraph::new(vec![
+        Line::from(vec![Span::raw(format!("Symbols: {}", data.tickers.len()))]),
+        Line::from(vec![
+            Span::raw("Gainers: "),
+            Span::styled(data.gainers.to_string(), Style::default().fg(Color::Green)),
+            Span::raw("  Losers: "),
+            Span::styled(data.losers.to_string(), Style::default().fg(Color::Red)),
+        ]),
+        Line::from(vec![Span::raw(format!(
+            "Total Quote Volume: {:.2}",
+            data.total_quote_volume
+        ))]),
+        Line::from(vec![Span::raw(format!(
+            "Last Update: {}",
+            data.last_event_time.map_or(String::from("-"), |time| time
+                .format("%H:%M:%S%.3f")
+                .to_string())
+        ))]),
+    ])
+    .block(Block::default().borders(Borders::ALL).title("Statistics"));
+
+    f.render_widget(stats, stats_chunks[0]);
+
+    // Render performance statistics
+    render_performance_stats(f, stats_chunks[1], &data.performance);
+}
+
+pub fn render_window_ticker_ui(f: &mut ratatui::Frame, data: &WindowTickerRenderData) {
+    // Layout with two vertical chunks
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([Constraint::Percentage(60), Constraint::Percentage(40)].as_ref())
+        .split(f.size());
+
+    // One column per window, shortest first
+    let mut header_cells = vec![Cell::from("Statistic")];
+    header_cells.extend(
+        data.windows
+            .iter()
+            .map(|ticker| Cell::from(ticker.window().to_string())),
+    );
+    let header = Row::new(header_cells).style(Style::default().fg(Color::Yellow).bg(Color::Blue));
+
+    // One row per statistic
+    let row = |label: &str, value: &dyn Fn(&WindowTicker) -> String| {
+        let mut cells = vec![Cell::from(label.to_string())];
+        cells.extend(data.windows.iter().map(|ticker| Cell::from(value(ticker))));
+        Row::new(cells)
+    };
+    let change_row = {
+        let mut cells = vec![Cell::from("Change %")];
+        cells.extend(data.windows.iter().map(|ticker| {
+            let color = if ticker.price_change_percent >= 0.0 {
+                Color::Green
+            } else {
+                Color::Red
+            };
+            Cell::from(format!("{:+.2}%", ticker.price_change_percent))
+                .style(Style::default().fg(color))
+        }));
+        Row::new(cells)
+    };
+    let rows = vec![
+        change_row,
+        row("Change", &|ticker| format!("{:+.8}", ticker.price_change)),
+        row("Open", &|ticker| format!("{:.8}", ticker.open_price)),
+        row("High", &|ticker| format!("{:.8}", ticker.high_price)),
+        row("Low", &|ticker| format!("{:.8}", ticker.low_price)),
+        row("Last", &|ticker| format!("{:.8}", ticker.last_price)),
+        row("Weighted Avg", &|ticker| {
+            format!("{:.8}", ticker.weighted_avg_price)
+        }),
+        row("Volume", &|ticker| format!("{:.2}", ticker.volume)),
+        row("Quote Volume", &|ticker| {
+            format!("{:.2}", ticker.quote_volume)
+        }),
+        row("Trades", &|ticker| ticker.trade_count.to_string()),
+        row("Window Open", &|ticker| {
+            ticker.open_time.format("%m-%d %H:%M:%S").to_string()
+        }),
+    ];
+
+    let mut widths = vec![Constraint::Length(14)];
+    widths.extend(data.windows.iter().map(|_| Constraint::Length(20)));
+    // Table widget
+    let table = Table::new(rows, widths).header(header).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!("{} Rolling Window Comparison", data.symbol)),
+    );
+
+    // Render table
+    f.render_widget(table, chunks[0]);
+
+    // Layout for momentum and performance statistics
+    let stats_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .margin(1)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+        .split(chunks[1]);
+
+    // Where the last price sits within each window's range, 0% at the low and 100% at the high
+    let momentum = Paragraph::new(
+        data.windows
+            .iter()
+            .map(|ticker| {
+                let range = ticker.high_price - ticker.low_price;
+                let position = if range > 0.0 {
+                    (ticker.last_price - ticker.low_price) / range * 100.0
+                } else {
+                    50.0
+                };
+                let trend = if ticker.last_price >= ticker.weighted_avg_price {
+                    Span::styled("above VWAP", Style::default().fg(Color::Green))
+                } else {
+                    Span::styled("below VWAP", Style::default().fg(Color::Red))
+                };
+                Line::from(vec![
+                    Span::raw(format!(
+                        "{:>4}: {:>5.1}% of range, ",
+                        ticker.window(),
+                        position
+                    )),
+                    trend,
+                ])
+            })
+            .collect::<Vec<_>>(),
+    )
+    .block(Block::default().borders(Borders::ALL).title("Momentum"));
+
+    f.render_widget(momentum, stats_chunks[0]);
+
+    // Render performance statistics
+    render_performance_stats(f, stats_chunks[1], &data.performance);
+}
+
+/// Renders the futures dashboard with mark prices, funding, liquidations and indexes
+pub fn render_futures_ui(f: &mut ratatui::Frame, data: &FuturesRenderData) {
+    // Layout with three vertical chunks
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints(
+            [
+                Constraint::Percentage(40),
+                Constraint::Percentage(35),
+                Constraint::Percentage(25),
+            ]
+            .as_ref(),
+        )
+        .split(f.size());
+    let top_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(35), Constraint::Percentage(65)].as_ref())
+        .split(chunks[0]);
+    let middle_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(60), Constraint::Percentage(40)].as_ref())
+        .split(chunks[1]);
+
+    // Mark price and funding of the contract updated last
+    let mark_lines = match data.current {
+        Some(mark) => {
+            let premium_bps = match mark.index_price {
+                Some(index) if index > 0.0 => (mark.mark_price - index) / index * 10_000.0,
+                _ => 0.0,
+            };
+            vec![
+                Line::from(vec![Span::raw(format!(
+                    "Mark Price: {:.8}",
+                    mark.mark_price
+                ))]),
+                Line::from(vec![Span::raw(format!(
+                    "Index Price: {}",
+                    optional_price(mark.index_price)
+                ))]),
+                Line::from(vec![Span::raw(format!(
+                    "Est. Settle Price: {:.8}",
+                    mark.estimated_settle_price
+                ))]),
+                Line::from(vec![Span::raw(format!("Premium: {:+.2} bps", premium_bps))]),
+                funding_line(mark),
+                Line::from(vec![Span::raw(format!(
+                    "Next Funding: {} (in {})",
+                    mark.next_funding_time.format("%H:%M:%S"),
+                    funding_countdown(mark, data.now)
+                ))]),
+            ]
+        }
+        None => vec![Line::from(vec![Span::raw("Waiting for mark price...")])],
+    };
+    let title = data.current.map_or("Mark Price".to_string(), |mark| {
+        format!("{} Mark Price", mark.symbol)
+    });
+    let mark_panel =
+        Paragraph::new(mark_lines).block(Block::default().borders(Borders::ALL).title(title));
+    f.render_widget(mark_panel, top_chunks[0]);
+
+    // Every contract, highest funding first
+    let header = Row::new(vec![
+        Cell::from("Symbol"),
+        Cell::from("Mark Price"),
+        Cell::from("Index Price"),
+        Cell::from("Funding"),
+        Cell::from("Next Funding"),
+    ])
+    .style(Style::default().fg(Color::Yellow).bg(Color::Blue));
+    let visible_rows = top_chunks[1].height.saturating_sub(3) as usize;
+    let rows: Vec<Row> = data
+        .mark_prices
+        .iter()
+        .take(visible_rows)
+        .map(|mark| {
+            let funding = match mark.funding_rate {
+                Some(rate) => format!("{:+.4}%", rate * 100.0),
+                None => "-".to_string(),
+            };
+            Row::new(vec![
+                Cell::from(mark.symbol.clone()),
+                Cell::from(format!("{:.8}", mark.mark_price)),
+                Cell::from(optional_price(mark.index_price)),
+                Cell::from(funding).style(Style::default().fg(funding_color(mark.funding_rate))),
+                Cell::from(funding_countdown(mark, data.now)),
+            ])
+        })
+        .collect();
+    const MARK_WIDTHS: [Constraint; 5] = [
+        Constraint::Length(16),
+        Constraint::Length(18),
+        Constraint::Length(18),
+        Constraint::Length(10),
+        Constraint::Length(12),
+    ];
+    let table = Table::new(rows, MARK_WIDTHS).header(header).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!("Funding ({} contracts)", data.mark_prices.len())),
+    );
+    f.render_widget(table, top_chunks[1]);
+
+    // Liquidation tape, newest first; sell orders close longs, buy orders close shorts
+    let header = Row::new(vec![
+        Cell::from("Time"),
+        Cell::from("Symbol"),
+        Cell::from("Side"),
+        Cell::from("Quantity"),
+        Cell::from("Avg Price"),
+        Cell::from("Notional"),
+    ])
+    .style(Style::default().fg(Color::Yellow).bg(Color::Blue));
+    let rows: Vec<Row> = data
+        .liquidations
+        .iter()
+        .map(|liquidation| {
+            let (side, color) = if liquidation.side == "SELL" {
+                ("Long", Color::Red)
+            } else {
+                ("Short", Color::Green)
+            };
+            Row::new(vec![
+                Cell::from(liquidation.trade_time.format("%H:%M:%S").to_string()),
+                Cell::from(liquidation.symbol.clone()),
+                Cell::from(side).style(Style::default().fg(color)),
+                Cell::from(format!("{:.4}", liquidation.filled_quantity)),
+                Cell::from(format!("{:.8}", liquidation.average_price)),
+                Cell::from(format!("{:.2}", liquidation.notional())),
+            ])
+        })
+        .collect();
+    const TAPE_WIDTHS: [Constraint; 6] = [
+        Constraint::Length(10),
+        Constraint::Length(14),
+        Constraint::Length(6),
+        Constraint::Length(12),
+        Constraint::Length(16),
+        Constraint::Length(14),
+    ];
+    let (longs, shorts) = data.liquidated_notional;
+    let tape = Table::new(rows, TAPE_WIDTHS).header(header).block(
+        Block::default().borders(Borders::ALL).title(format!(
+            "Liquidations (longs {:.2}, shorts {:.2})",
+            longs, shorts
+        )),
+    );
+    f.render_widget(tape, middle_chunks[0]);
+
+    // Composite index constituents when subscribed, otherwise the mark price chart
+    match data.index {
+        Some(index) => {
+            let header = Row::new(vec![
+                Cell::from("Asset"),
+                Cell::from("Weight"),
+                Cell::from("Price"),
+            ])
+            .style(Style::default().fg(Color::Yellow).bg(Color::Blue));
+            let rows: Vec<Row> = index
+                .components
+                .iter()
+                .map(|component| {
+                    Row::new(vec![
+                        Cell::from(format!(
+                            "{}/{}",
+                            component.base_asset, component.quote_asset
+                        )),
+                        Cell::from(format!("{:.2}%", component.weight_percent * 100.0)),
+                        Cell::from(format!("{:.8}", component.index_price)),
+                    ])
+                })
+                .collect();
+            const INDEX_WIDTHS: [Constraint; 3] = [
+                Constraint::Length(12),
+                Constraint::Length(8),
+                Constraint::Length(16),
+            ];
+            let table = Table::new(rows, INDEX_WIDTHS).header(header).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!("{} Index {:.8}", index.symbol, index.price)),
+            );
+            f.render_widget(table, middle_chunks[1]);
+        }
+        None => render_price_chart(f, middle_chunks[1], data.mark_history),
+    }
+
+    // Render performance statistics
+    render_performance_stats(f, chunks[2], &data.performance);
+}
+
+pub fn render_my_orders_ui(f: &mut ratatui::Frame, data: &UserDataRenderData) {
+    // Layout with three vertical chunks
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints(
+            [
+                Constraint::Percentage(40),
+                Constraint::Percentage(35),
+                Constraint::Percentage(25),
+            ]
+            .as_ref(),
+        )
+        .split(f.size());
+    let top_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(65), Constraint::Percentage(35)].as_ref())
+        .split(chunks[0]);
+    let middle_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(65), Constraint::Percentage(35)].as_ref())
+        .split(chunks[1]);
+
+    // Open orders, most recently updated first
+    let header = Row::new(vec![
+        Cell::from("Symbol"),
+        Cell::from("Side"),
+        Cell::from("Type"),
+        Cell::from("Price"),
+        Cell::from("Quantity"),
+        Cell::from("Filled"),
+        Cell::from("Status"),
+    ])
+    .style(Style::default().fg(Color::Yellow).bg(Color::Blue));
+    let rows: Vec<Row> = data
+        .open_orders
+        .iter()
+        .map(|order| {
+            Row::new(vec![
+                Cell::from(order.symbol.clone()),
+                Cell::from(order.side.clone()).style(Style::default().fg(side_color(&order.side))),
+                Cell::from(order.order_type.clone()),
+                Cell::from(format!("{:.8}", order.price)),
+                Cell::from(format!("{:.8}", order.quantity)),
+                Cell::from(format!("{:.8}", order.filled_quantity)),
+                Cell::from(order.status.clone()),
+            ])
+        })
+        .collect();
+    const ORDER_WIDTHS: [Constraint; 7] = [
+        Constraint::Length(12),
+        Constraint::Length(5),
+        Constraint::Length(12),
+        Constraint::Length(14),
+        Constraint::Length(14),
+        Constraint::Length(14),
+        Constraint::Length(16),
+    ];
+    let table = Table::new(rows, ORDER_WIDTHS).header(header).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!("My Orders ({} open)", data.open_orders.len())),
+    );
+    f.render_widget(table, top_chunks[0]);
+
+    // Balances of the assets the account holds
+    let header = Row::new(vec![
+        Cell::from("Asset"),
+        Cell::from("Free"),
+        Cell::from("Locked"),
+    ])
+    .style(Style::default().fg(Color::Yellow).bg(Color::Blue));
+    let rows: Vec<Row> = data
+        .balances
+        .iter()
+        .map(|balance| {
+            Row::new(vec![
+                Cell::from(balance.asset.clone()),
+                Cell::from(format!("{:.8}", balance.free)),
+                Cell::from(format!("{:.8}", balance.locked)),
+            ])
+        })
+        .collect();
+    const BALANCE_WIDTHS: [Constraint; 3] = [
+        Constraint::Length(8),
+        Constraint::Length(16),
+        Constraint::Length(16),
+    ];
+    let table = Table::new(rows, BALANCE_WIDTHS)
+        .header(header)
+        .block(Block::default().borders(Borders::ALL).title("Balances"));
+    f.render_widget(table, top_chunks[1]);
+
+    // Fills, newest first
+    let header = Row::new(vec![
+        Cell::from("Time"),
+        Cell::from("Symbol"),
+        Cell::from("Side"),
+        Cell::from("Price"),
+        Cell::from("Quantity"),
+        Cell::from("Commission"),
+        Cell::from("Role"),
+    ])
+    .style(Style::default().fg(Color::Yellow).bg(Color::Blue));
+    let rows: Vec<Row> = data
+        .fills
+        .iter()
+        .map(|fill| {
+            Row::new(vec![
+                Cell::from(fill.transaction_time.format("%H:%M:%S").to_string()),
+                Cell::from(fill.symbol.clone()),
+                Cell::from(fill.side.clone()).style(Style::default().fg(side_color(&fill.side))),
+                Cell::from(format!("{:.8}", fill.last_price)),
+                Cell::from(format!("{:.8}", fill.last_quantity)),
+                Cell::from(format!(
+                    "{:.8} {}",
+                    fill.commission,
+                    fill.commission_asset.as_deref().unwrap_or("")
+                )),
+                Cell::from(if fill.is_maker { "Maker" } else { "Taker" }),
+            ])
+        })
+        .collect();
+    const FILL_WIDTHS: [Constraint; 7] = [
+        Constraint::Length(10),
+        Constraint::Length(12),
+        Constraint::Length(5),
+        Constraint::Length(14),
+        Constraint::Length(14),
+        Constraint::Length(18),
+        Constraint::Length(6),
+    ];
+    let table = Table::new(rows, FILL_WIDTHS)
+        .header(header)
+        .block(Block::default().borders(Borders::ALL).title("My Fills"));
+    f.render_widget(table, middle_chunks[0]);
+
+    // Deposits, withdrawals and transfers, newest first
+    let lines: Vec<Line> = data
+        .balance_updates
+        .iter()
+        .map(|update| {
+            Line::from(vec![Span::styled(
+                format!(
+                    "{} {:+.8} {}",
+                    update.clear_time.format("%H:%M:%S"),
+                    update.delta,
+                    update.asset
+                ),
+                Style::default().fg(if update.delta >= 0.0 {
+                    Color::Green
+                } else {
+                    Color::Red
+                }),
+            )])
+        })
+        .collect();
+    let updates = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Balance Updates"),
+    );
+    f.render_widget(updates, middle_chunks[1]);
+
+    // Render performance statistics
+    render_performance_stats(f, chunks[2], &data.performance);
+}
+
+// Buys in green, sells in red
+fn side_color(side: &str) -> Color {
+    if side == "BUY" {
+        Color::Green
+    } else {
+        Color::Red
+    }
+}
+
+/// Formats a price that some markets leave out
+fn optional_price(price: Option<f64>) -> String {
+    price.map_or("-".to_string(), |price| format!("{:.8}", price))
+}
+
+/// Builds the funding rate line, with the rate annualized over three payments a day
+fn funding_line(mark: &MarkPrice) -> Line<'static> {
+    match mark.funding_rate {
+        Some(rate) => Line::from(vec![
+            Span::raw("Funding Rate: "),
+            Span::styled(
+                format!(
+                    "{:+.4}% ({:+.2}% APR)",
+                    rate * 100.0,
+                    rate * 3.0 * 365.0 * 100.0
+                ),
+                Style::default().fg(funding_color(Some(rate))),
+            ),
+        ]),
+        None => Line::from(vec![Span::raw("Funding Rate: none (delivery contract)")]),
+    }
+}
+
+// Positive funding is paid by longs, negative by shorts
+fn funding_color(rate: Option<f64>) -> Color {
+    match rate {
+        Some(rate) if rate > 0.0 => Color::Green,
+        Some(rate) if rate < 0.0 => Color::Red,
+        _ => Color::Gray,
+    }
+}
+
+// Time left until the next funding payment as HH:MM:SS
+fn funding_countdown(mark: &MarkPrice, now: DateTime<Utc>) -> String {
+    match time_to_funding(mark, now) {
+        Some(left) => format!(
+            "{:02}:{:02}:{:02}",
+            left.num_hours(),
+            left.num_minutes() % 60,
+            left.num_seconds() % 60
+        ),
+        None => "-".to_string(),
+    }
+}
+
+/// Builds the spread and mid price lines shown in the statistics panels
+fn quote_lines(quote: Option<&QuoteData>) -> Vec<Line<'static>> {
+    let Some(quote) = quote else {
+        return Vec::new();
+    };
+    vec![
+        Line::from(vec![Span::raw(format!(
+            "Spread: {:.4} ({:.2} bps)",
+            quote.spread, quote.spread_bps
+        ))]),
+        Line::from(vec![Span::raw(format!(
+            "Avg Spread: {:.4} ({:.2} bps)",
+            quote.average_spread, quote.average_spread_bps
+        ))]),
+        Line::from(vec![Span::raw(format!(
+            "Mid Price: {:.4}",
+            quote.mid_price
+        ))]),
+        Line::from(vec![Span::raw(format!(
+            "Microprice: {:.4}",
+            quote.microprice
+        ))]),
+    ]
+}
+
+/// Renders the trades table, splitting the area with the order book ladder when a snapshot exists
+fn render_with_ladder(
+    f: &mut ratatui::Frame,
+    area: Rect,
+    table: Table,
+    depth: Option<&DepthSnapshot>,
+) {
+    let Some(snapshot) = depth else {
+        f.render_widget(table, area);
+        return;
+    };
+
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(70), Constraint::Percentage(30)].as_ref())
+        .split(area);
+    f.render_widget(table, chunks[0]);
+    render_depth_ladder(f, chunks[1], snapshot);
+}
+
+/// Renders a bid/ask ladder with asks above bids
+fn render_depth_ladder(f: &mut ratatui::Frame, area: Rect, snapshot: &DepthSnapshot) {
+    // Table header
+    let header = Row::new(vec![
+        Cell::from("Bid Qty"),
+        Cell::from("Price"),
+        Cell::from("Ask Qty"),
+    ])
+    .style(Style::default().fg(Color::Yellow).bg(Color::Blue));
+
+    // Asks from highest to best, then bids from best to lowest
+    let asks = snapshot.asks.iter().rev().map(|level| {
+        Row::new(vec![
+            Cell::from(""),
+            Cell::from(format!("{:.2}", level.price)),
+            Cell::from(format!("{:.4}", level.quantity)),
+        ])
+        .style(Style::default().fg(Color::Red))
+    });
+    let bids = snapshot.bids.iter().map(|level| {
+        Row::new(vec![
+            Cell::from(format!("{:.4}", level.quantity)),
+            Cell::from(format!("{:.2}", level.price)),
+            Cell::from(""),
+        ])
+        .style(Style::default().fg(Color::Green))
+    });
+    let rows: Vec<Row> = asks.chain(bids).collect();
+
+    const WIDTHS: [Constraint; 3] = [
+        Constraint::Percentage(33),
+        Constraint::Percentage(34),
+        Constraint::Percentage(33),
+    ];
+    // Table widget
+    let ladder = Table::new(rows, WIDTHS).header(header).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!("{} Depth", snapshot.symbol)),
+    );
+
+    // Render ladder
+    f.render_widget(ladder, area);
+}
+
+/// Renders cumulative bid and ask quantity by price
+fn render_depth_chart(f: &mut ratatui::Frame, area: Rect, snapshot: &DepthSnapshot) {
+    // Accumulate quantity away from the top of the book
+    let cumulative = |levels: &[PriceLevel]| {
+        levels
+            .iter()
+            .scan(0.0, |total, level| {
+                *total += level.quantity;
+                Some((level.price, *total))
+            })
+            .collect::<Vec<_>>()
+    };
+    let bids = cumulative(&snapshot.bids);
+    let asks = cumulative(&snapshot.asks);
+
+    // Calculate bounds
+    let price_min = bids.last().map(|&(x, _)| x).unwrap_or(0.0);
+    let price_max = asks.last().map(|&(x, _)| x).unwrap_or(0.0);
+    let quantity_max = bids
+        .last()
+        .map(|&(_, y)| y)
+        .unwrap_or(0.0)
+        .max(asks.last().map(|&(_, y)| y).unwrap_or(0.0));
+
+    // Datasets for chart
+    let datasets = vec![
+        Dataset::default()
+            .name("Bids")
+            .marker(ratatui::symbols::Marker::Braille)
+            .style(Style::default().fg(Color::Green))
+            .data(&bids),
+        Dataset::default()
+            .name("Asks")
+            .marker(ratatui::symbols::Marker::Braille)
+            .style(Style::default().fg(Color::Red))
+            .data(&asks),
+    ];
+
+    // Chart widget
+    let depth_chart = Chart::new(datasets)
+        .block(Block::default().borders(Borders::ALL).title("Depth Chart"))
+        .x_axis(
+            Axis::default()
+                .title(Span::styled("Price", Style::default().fg(Color::Gray)))
+                .style(Style::default().fg(Color::Gray))
+                .bounds([price_min, price_max])
+                .labels(vec![
+                    Span::styled(
+                        format!("{:.2}", price_min),
+                        Style::default().add_modifier(ratatui::style::Modifier::BOLD),
+                    ),
+                    Span::styled(
+                        format!("{:.2}", price_max),
+                        Style::default().add_modifier(ratatui::style::Modifier::BOLD),
+                    ),
+                ]),
+        )
+        .y_axis(
+            Axis::default()
+                .title(Span::styled("Quantity", Style::default().fg(Color::Gray)))
+                .style(Style::default().fg(Color::Gray))
+                .bounds([0.0, quantity_max])
+                .labels(vec![
+                    Span::styled(
+                        "0",
+                        Style::default().add_modifier(ratatui::style::Modifier::BOLD),
+                    ),
+                    Span::styled(
+                        format!("{:.4}", quantity_max),
+                        Style::default().add_modifier(ratatui::style::Modifier::BOLD),
+                    ),
+                ]),
+        );
+
+    // Render depth chart
+    f.render_widget(depth_chart, area);
+}
+
+/// Renders the buyer maker distribution gauge
+fn render_buyer_maker_gauge(f: &mut ratatui::Frame, area: Rect, buyer_maker_count: (usize, usize)) {
+    // Calculate buyer maker percentages
+    let (buyer_maker_true, buyer_maker_false) = buyer_maker_count;
+    let total_buyer_maker = buyer_maker_true + buyer_maker_false;
+    let buyer_maker_true_percent = if total_buyer_maker > 0 {
+        (buyer_maker_true as f64 / total_buyer_maker as f64) * 100.0
+    } else {
+        0.0
+    };
+    let buyer_maker_false_percent = 100.0 - buyer_maker_true_percent;
+
+    // Gauge for buyer maker percentages
+    let buyer_maker_gauge = Gauge::default()
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Buyer Maker Distribution"),
+        )
+        .gauge_style(
+            Style::default()
+                .fg(Color::Magenta)
+                .bg(Color::Black)
+                .add_modifier(ratatui::style::Modifier::ITALIC),
+        )
+        .percent(buyer_maker_true_percent as u16)
+        .label(Span::styled(
+            format!(
+                "Buy: {:.1}%, Sell: {:.1}%",
+                buyer_maker_true_percent, buyer_maker_false_percent
+            ),
+            Style::default().add_modifier(ratatui::style::Modifier::BOLD),
+        ));
+
+    // Render the buyer maker gauge
+    f.render_widget(buyer_maker_gauge, area);
+}
+
+/// Renders the message throughput statistics
+fn render_performance_stats(f: &mut ratatui::Frame, area: Rect, data: &PerformanceData) {
+    // Performance statistics
+    let performance_stats = Paragraph::new(vec![
+        Line::from(vec![Span::raw(format!(
+            "Messages Processed: {}",
+            data.message_count
+        ))]),
+        Line::from(vec![Span::raw(format!(
+            "Avg Arrival Interval: {:.2} ms",
+            data.avg_arrival_interval
+        ))]),
+        Line::from(vec![Span::raw(format!(
+            "Avg Processing Time: {:.2} ms",
+            data.avg_processing_time
+        ))]),
+        Line::from(vec![Span::raw(format!(
+            "Decode Errors: {}",
+            data.decode_errors
+        ))]),
+        Line::from(vec![Span::raw(format!(
+            "Last Stream: {}",
+            data.last_source
+        ))]),
+        connection_line(data),
+        streams_line(data),
+        request_line(data),
+        delivery_line(data),
+        Line::from(vec![Span::raw(format!(
+            "Sequence Gaps: {} ({} trades backfilled, {} failed)",
+            data.gap_count, data.backfilled_count, data.backfill_failures
+        ))]),
+    ])
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Performance Stats"),
+    );
+
+    // Render performance statistics
+    f.render_widget(performance_stats, area);
+}
+
+/// Builds the connection state line, colored by state
+fn connection_line(data: &PerformanceData) -> Line<'static> {
+    let color = match data.connection_state {
+        ConnectionState::Connected => Color::Green,
+        ConnectionState::Connecting | ConnectionState::Reconnecting { .. } => Color::Yellow,
+        ConnectionState::Closed => Color::Red,
+    };
+    Line::from(vec![
+        Span::raw("Connection: "),
+        Span::styled(
+            data.connection_state.to_string(),
+            Style::default().fg(color),
+        ),
+        Span::raw(format!(" ({} reconnects)", data.reconnect_count)),
+    ])
+}
+
+// Streams named on the streams line before the rest are summarised as a count
+const MAX_LISTED_STREAMS: usize = 5;
+
+/// Builds the subscribed streams line, replaced by the stream prompt while typing
+fn streams_line(data: &PerformanceData) -> Line<'static> {
+    match data.prompt {
+        Some(prompt) => Line::from(vec![Span::styled(
+            prompt.to_string(),
+            Style::default().fg(Color::Yellow),
+        )]),
+        None => {
+            let confirmed = match data.confirmed_streams {
+                Some(confirmed) => format!("{} confirmed", confirmed.len()),
+                None => "unconfirmed".to_string(),
+            };
+            // A sharded subscription can carry hundreds of streams, show the first few
+            let mut streams = data.streams[..data.streams.len().min(MAX_LISTED_STREAMS)].join(", ");
+            if data.streams.len() > MAX_LISTED_STREAMS {
+                streams.push_str(&format!(
+                    " +{} more",
+                    data.streams.len() - MAX_LISTED_STREAMS
+                ));
+            }
+            Line::from(vec![Span::raw(format!(
+                "Streams: {} ({}; 'a' add, 'u' remove, 'l' list)",
+                streams, confirmed
+            ))])
+        }
+    }
+}
+
+/// Builds the line with the outcome of the latest subscription request
+fn request_line(data: &PerformanceData) -> Line<'static> {
+    let request = data.last_request.unwrap_or("none");
+    let color = if request.contains("failed") {
+        Color::Red
+    } else {
+        Color::White
+    };
+    Line::from(vec![
+        Span::raw("Last Request: "),
+        Span::styled(request.to_string(), Style::default().fg(color)),
+    ])
+}
+
+/// Builds the line showing how often each redundant connection delivered first
+fn delivery_line(data: &PerformanceData) -> Line<'static> {
+    let total: u64 = data.deliveries.iter().map(|stats| stats.first).sum();
+    if total == 0 {
+        return Line::from(vec![Span::raw("First Delivery: single connection")]);
+    }
+    let copies: Vec<String> = data
+        .deliveries
+        .iter()
+        .enumerate()
+        .map(|(index, stats)| {
+            format!(
+                "#{} {:.0}% (+{:.1} ms)",
+                index + 1,
+                stats.first as f64 * 100.0 / total as f64,
+                stats.average_lead().as_secs_f64() * 1000.0
+            )
+        })
+        .collect();
+    Line::from(vec![Span::raw(format!(
+        "First Delivery: {}",
+        copies.join(", ")
+    ))])
+}
+
+/// Renders the connection state while no market data has arrived yet
+pub fn render_connection_ui(f: &mut ratatui::Frame, data: &PerformanceData) {
+    let status = Paragraph::new(vec![
+        connection_line(data),
+        streams_line(data),
+        request_line(data),
+        Line::from(vec![Span::raw(
+            "Waiting for market data... press 'q' to return to the menu",
+        )]),
+    ])
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Binance WebSocket"),
+    );
+
+    f.render_widget(status, f.size());
+}
+
+/// Renders the price chart
+fn render_price_chart(f: &mut ratatui::Frame, area: Rect, prices: &[(f64, f64)]) {
+    // Calculate price bounds
+    let price_min = prices.iter().map(|&(_, y)| y).fold(f64::INFINITY, f64::min);
+    let price_max = prices
         .iter()
         .map(|&(_, y)| y)
         .fold(f64::NEG_INFINITY, f64::max);
@@ -240,7 +1965,7 @@ pub fn render_ui(f: &mut ratatui::Frame, data: &RenderData) {
         .name("Prices")
         .marker(ratatui::symbols::Marker::Block)
         .style(Style::default().fg(Color::Cyan))
-        .data(&data.prices)];
+        .data(prices)];
 
     // Chart widget
     let price_chart = Chart::new(price_dataset)
@@ -250,16 +1975,16 @@ pub fn render_ui(f: &mut ratatui::Frame, data: &RenderData) {
                 .title(Span::styled("Timestamp", Style::default().fg(Color::Gray)))
                 .style(Style::default().fg(Color::Gray))
                 .bounds([
-                    data.prices.first().map(|&(x, _)| x).unwrap_or(0.0),
-                    data.prices.last().map(|&(x, _)| x).unwrap_or(0.0),
+                    prices.first().map(|&(x, _)| x).unwrap_or(0.0),
+                    prices.last().map(|&(x, _)| x).unwrap_or(0.0),
                 ])
                 .labels(vec![
                     Span::styled(
-                        format!("{}", data.prices.first().map(|&(x, _)| x).unwrap_or(0.0)),
+                        format!("{}", prices.first().map(|&(x, _)| x).unwrap_or(0.0)),
                         Style::default().add_modifier(ratatui::style::Modifier::BOLD),
                     ),
                     Span::styled(
-                        format!("{}", data.prices.last().map(|&(x, _)| x).unwrap_or(0.0)),
+                        format!("{}", prices.last().map(|&(x, _)| x).unwrap_or(0.0)),
                         Style::default().add_modifier(ratatui::style::Modifier::BOLD),
                     ),
                 ]),
@@ -282,20 +2007,66 @@ pub fn render_ui(f: &mut ratatui::Frame, data: &RenderData) {
         );
 
     // Render price chart
-    f.render_widget(price_chart, chunks[2]);
+    f.render_widget(price_chart, area);
+}
+
+/// Renders the spread of each update in the rolling window
+fn render_spread_chart(f: &mut ratatui::Frame, area: Rect, spreads: &[(f64, f64)]) {
+    // Calculate bounds
+    let spread_max = spreads.iter().map(|&(_, y)| y).fold(0.0, f64::max);
+    let x_max = spreads.last().map(|&(x, _)| x).unwrap_or(0.0);
+
+    // Dataset for chart
+    let spread_dataset = vec![Dataset::default()
+        .name("Spread")
+        .marker(ratatui::symbols::Marker::Braille)
+        .style(Style::default().fg(Color::Cyan))
+        .data(spreads)];
+
+    // Chart widget
+    let spread_chart = Chart::new(spread_dataset)
+        .block(Block::default().borders(Borders::ALL).title("Spread Chart"))
+        .x_axis(
+            Axis::default()
+                .title(Span::styled("Update", Style::default().fg(Color::Gray)))
+                .style(Style::default().fg(Color::Gray))
+                .bounds([0.0, x_max]),
+        )
+        .y_axis(
+            Axis::default()
+                .title(Span::styled("Spread", Style::default().fg(Color::Gray)))
+                .style(Style::default().fg(Color::Gray))
+                .bounds([0.0, spread_max])
+                .labels(vec![
+                    Span::styled(
+                        "0",
+                        Style::default().add_modifier(ratatui::style::Modifier::BOLD),
+                    ),
+                    Span::styled(
+                        format!("{:.4}", spread_max),
+                        Style::default().add_modifier(ratatui::style::Modifier::BOLD),
+                    ),
+                ]),
+        );
+
+    // Render spread chart
+    f.render_widget(spread_chart, area);
+}
 
+/// Renders the performance chart
+fn render_performance_chart(f: &mut ratatui::Frame, area: Rect, data: &PerformanceData) {
     // Dataset for performance chart
     let performance_dataset = vec![
         Dataset::default()
             .name("Arrival Interval")
             .marker(ratatui::symbols::Marker::Braille)
             .style(Style::default().fg(Color::Yellow))
-            .data(&data.arrival_intervals),
+            .data(data.arrival_intervals),
         Dataset::default()
             .name("Processing Time")
             .marker(ratatui::symbols::Marker::Braille)
             .style(Style::default().fg(Color::Green))
-            .data(&data.processing_times),
+            .data(data.processing_times),
     ];
 
     // Performance chart widget
@@ -384,5 +2155,5 @@ pub fn render_ui(f: &mut ratatui::Frame, data: &RenderData) {
         );
 
     // Render performance chart
-    f.render_widget(performance_chart, chunks[3]);
+    f.render_widget(performance_chart, area);
 }
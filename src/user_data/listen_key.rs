@@ -0,0 +1,90 @@
+use reqwest::{Client, Error};
+use serde::Deserialize;
+
+// Header the API key is sent in
+const API_KEY_HEADER: &str = "X-MBX-APIKEY";
+
+#[derive(Deserialize, Debug)]
+struct ListenKeyResponse {
+    #[serde(rename = "listenKey")]
+    listen_key: String,
+}
+
+/// Creates a listenKey for the user data stream at `url`. It stays valid for 60
+/// minutes unless kept alive.
+pub async fn create_listen_key(url: &str, api_key: &str) -> Result<String, Error> {
+    let response = Client::new()
+        .post(url)
+        .header(API_KEY_HEADER, api_key)
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<ListenKeyResponse>()
+        .await?;
+    Ok(response.listen_key)
+}
+
+/// Extends the validity of a listenKey by another 60 minutes
+pub async fn keep_alive_listen_key(
+    url: &str,
+    api_key: &str,
+    listen_key: &str,
+) -> Result<(), Error> {
+    Client::new()
+        .put(format!("{}?listenKey={}", url, listen_key))
+        .header(API_KEY_HEADER, api_key)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+/// Closes a listenKey, ending its user data stream
+pub async fn close_listen_key(url: &str, api_key: &str, listen_key: &str) -> Result<(), Error> {
+    Client::new()
+        .delete(format!("{}?listenKey={}", url, listen_key))
+        .header(API_KEY_HEADER, api_key)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::spawn_recording_http_server;
+
+    #[tokio::test]
+    async fn test_listen_key_requests_carry_the_api_key() {
+        let (base_url, mut requests) = spawn_recording_http_server(vec![
+            (
+                "/api/v3/userDataStream",
+                r#"{"listenKey":"pqia91ma19a5s61cv6a81va65sdf19v8a65a1a5s61cv6a81va65sdf19v8a65a1"}"#
+                    .to_string(),
+            ),
+            (
+                "/api/v3/userDataStream?listenKey=pqia91ma19a5s61cv6a81va65sdf19v8a65a1a5s61cv6a81va65sdf19v8a65a1",
+                "{}".to_string(),
+            ),
+        ])
+        .await;
+        let url = format!("{}/api/v3/userDataStream", base_url);
+
+        let listen_key = create_listen_key(&url, "api-key").await.unwrap();
+        assert!(listen_key.starts_with("pqia91"));
+        keep_alive_listen_key(&url, "api-key", &listen_key)
+            .await
+            .unwrap();
+
+        let create = requests.recv().await.unwrap();
+        assert!(create.starts_with("POST /api/v3/userDataStream "));
+        assert!(create.to_lowercase().contains("x-mbx-apikey: api-key"));
+        let keep_alive = requests.recv().await.unwrap();
+        assert!(keep_alive.starts_with("PUT /api/v3/userDataStream?listenKey=pqia91"));
+
+        assert!(keep_alive_listen_key(&url, "api-key", "unknown")
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,165 @@
+use crate::websocket::deserialize::{datetime_from_millis, f64_from_str};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Event on the user data stream
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "e")]
+pub enum UserEvent {
+    #[serde(rename = "executionReport")]
+    ExecutionReport(ExecutionReport),
+    #[serde(rename = "outboundAccountPosition")]
+    AccountPosition(AccountPosition),
+    #[serde(rename = "balanceUpdate")]
+    BalanceUpdate(BalanceUpdate),
+    /// The listenKey is no longer valid and its stream has ended
+    #[serde(rename = "listenKeyExpired")]
+    ListenKeyExpired,
+}
+
+/// Update to one of the account's orders: placed, filled, canceled, rejected or expired
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExecutionReport {
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "i")]
+    pub order_id: u64,
+    #[serde(rename = "S")]
+    pub side: String,
+    #[serde(rename = "o")]
+    pub order_type: String,
+    #[serde(rename = "p", deserialize_with = "f64_from_str")]
+    pub price: f64,
+    #[serde(rename = "q", deserialize_with = "f64_from_str")]
+    pub quantity: f64,
+    /// What happened to the order, e.g. `NEW`, `TRADE` or `CANCELED`
+    #[serde(rename = "x")]
+    pub execution_type: String,
+    #[serde(rename = "X")]
+    pub status: String,
+    #[serde(rename = "l", deserialize_with = "f64_from_str")]
+    pub last_quantity: f64,
+    #[serde(rename = "L", deserialize_with = "f64_from_str")]
+    pub last_price: f64,
+    #[serde(rename = "z", deserialize_with = "f64_from_str")]
+    pub filled_quantity: f64,
+    #[serde(rename = "n", deserialize_with = "f64_from_str")]
+    pub commission: f64,
+    #[serde(rename = "N")]
+    pub commission_asset: Option<String>,
+    #[serde(rename = "T", deserialize_with = "datetime_from_millis")]
+    pub transaction_time: DateTime<Utc>,
+    #[serde(rename = "m")]
+    pub is_maker: bool,
+}
+
+impl ExecutionReport {
+    // Check whether the report is a fill
+    pub fn is_fill(&self) -> bool {
+        self.execution_type == "TRADE"
+    }
+
+    // Check whether the order can still trade
+    pub fn is_open(&self) -> bool {
+        matches!(
+            self.status.as_str(),
+            "NEW" | "PARTIALLY_FILLED" | "PENDING_NEW"
+        )
+    }
+}
+
+/// Balances of the assets changed by an account update
+#[derive(Debug, Clone, Deserialize)]
+pub struct AccountPosition {
+    #[serde(rename = "E", deserialize_with = "datetime_from_millis")]
+    pub event_time: DateTime<Utc>,
+    #[serde(rename = "B")]
+    pub balances: Vec<Balance>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Balance {
+    #[serde(rename = "a")]
+    pub asset: String,
+    #[serde(rename = "f", deserialize_with = "f64_from_str")]
+    pub free: f64,
+    #[serde(rename = "l", deserialize_with = "f64_from_str")]
+    pub locked: f64,
+}
+
+/// Deposit, withdrawal or transfer changing a balance
+#[derive(Debug, Clone, Deserialize)]
+pub struct BalanceUpdate {
+    #[serde(rename = "a")]
+    pub asset: String,
+    #[serde(rename = "d", deserialize_with = "f64_from_str")]
+    pub delta: f64,
+    #[serde(rename = "T", deserialize_with = "datetime_from_millis")]
+    pub clear_time: DateTime<Utc>,
+}
+
+/// Decodes a user data stream message. Replies to control messages and event types
+/// not shown on the dashboard, such as `listStatus`, decode to `None`.
+pub fn parse_user_event(text: &str) -> Result<Option<UserEvent>, serde_json::Error> {
+    let value: Value = serde_json::from_str(text)?;
+    match value.get("e").and_then(Value::as_str) {
+        Some(
+            "executionReport" | "outboundAccountPosition" | "balanceUpdate" | "listenKeyExpired",
+        ) => serde_json::from_value(value).map(Some),
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_user_events() {
+        let event = parse_user_event(
+            r#"{"e":"executionReport","E":1499405658658,"s":"ETHBTC","c":"mUvoqJxFIILMdfAW5iGSOW","S":"BUY","o":"LIMIT","f":"GTC","q":"1.00000000","p":"0.10264410","P":"0.00000000","F":"0.00000000","g":-1,"C":"","x":"TRADE","X":"PARTIALLY_FILLED","r":"NONE","i":4293153,"l":"0.40000000","z":"0.40000000","L":"0.10264400","n":"0.00040000","N":"ETH","T":1499405658657,"t":12,"I":8641984,"w":false,"m":true,"M":false,"O":1499405658657,"Z":"0.04105760","Y":"0.04105760","Q":"0.00000000","W":1499405658657,"V":"NONE"}"#,
+        )
+        .unwrap();
+        let Some(UserEvent::ExecutionReport(report)) = event else {
+            panic!("expected execution report, got {:?}", event);
+        };
+        assert_eq!(report.order_id, 4293153);
+        assert!(report.is_fill() && report.is_open());
+        assert_eq!(report.last_price, 0.102644);
+        assert_eq!(report.commission_asset.as_deref(), Some("ETH"));
+
+        let event = parse_user_event(
+            r#"{"e":"outboundAccountPosition","E":1564034571105,"u":1564034571073,"B":[{"a":"ETH","f":"10000.000000","l":"0.000000"}]}"#,
+        )
+        .unwrap();
+        let Some(UserEvent::AccountPosition(position)) = event else {
+            panic!("expected account position, got {:?}", event);
+        };
+        assert_eq!(position.balances[0].asset, "ETH");
+        assert_eq!(position.balances[0].free, 10000.0);
+
+        let event = parse_user_event(
+            r#"{"e":"balanceUpdate","E":1573200697110,"a":"BTC","d":"100.00000000","T":1573200697068}"#,
+        )
+        .unwrap();
+        assert!(
+            matches!(event, Some(UserEvent::BalanceUpdate(ref update)) if update.delta == 100.0)
+        );
+
+        let event =
+            parse_user_event(r#"{"e":"listenKeyExpired","E":1576653824250,"listenKey":"OfY"}"#);
+        assert!(matches!(event, Ok(Some(UserEvent::ListenKeyExpired))));
+
+        // Other events and control replies are skipped
+        assert!(
+            parse_user_event(r#"{"e":"listStatus","E":1564035303637,"s":"ETHBTC"}"#)
+                .unwrap()
+                .is_none()
+        );
+        assert!(parse_user_event(r#"{"result":null,"id":1}"#)
+            .unwrap()
+            .is_none());
+        assert!(parse_user_event("not json").is_err());
+    }
+}
@@ -0,0 +1,3 @@
+pub mod listen_key;
+pub mod message;
+pub mod stream;
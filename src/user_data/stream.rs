@@ -0,0 +1,232 @@
+use crate::endpoints::Endpoints;
+use crate::user_data::listen_key::{close_listen_key, create_listen_key, keep_alive_listen_key};
+use crate::user_data::message::{parse_user_event, UserEvent};
+use crate::websocket::client::backoff::Backoff;
+use crate::websocket::client::connection::{run_connection, ConnectionConfig, ConnectionEvent};
+use std::time::Duration;
+use tokio::sync::{mpsc, watch};
+use tokio::time::Instant;
+
+/// Settings for the account's user data stream
+#[derive(Debug, Clone)]
+pub struct UserStreamConfig {
+    pub endpoints: Endpoints,
+    pub api_key: String,
+    /// How often the listenKey is kept alive; it expires after 60 minutes without
+    pub keepalive_interval: Duration,
+    /// Settings for the stream connection, whose URL is set from the listenKey
+    pub connection: ConnectionConfig,
+    pub backoff: Backoff,
+}
+
+/// Creates a listenKey, keeps it alive and passes the account's order and balance events
+/// to `events` until `shutdown_rx` fires, then closes the listenKey. A new listenKey is
+/// created when the old one expires or can no longer be kept alive.
+pub async fn run_user_stream(
+    config: UserStreamConfig,
+    events: mpsc::Sender<UserEvent>,
+    mut shutdown_rx: watch::Receiver<bool>,
+) {
+    let url = config.endpoints.listen_key_url();
+    let mut backoff = config.backoff.clone();
+
+    loop {
+        let created = tokio::select! {
+            created = create_listen_key(&url, &config.api_key) => created,
+            _ = shutdown_rx.changed() => return,
+        };
+        let Ok(listen_key) = created else {
+            tokio::select! {
+                _ = tokio::time::sleep(backoff.next_delay()) => continue,
+                _ = shutdown_rx.changed() => return,
+            }
+        };
+        backoff.reset();
+
+        if !stream_listen_key(&config, &listen_key, &events, &mut shutdown_rx).await {
+            let _ = close_listen_key(&url, &config.api_key, &listen_key).await;
+            return;
+        }
+    }
+}
+
+// Stream the events of one listenKey while keeping it alive; returns whether a new
+// listenKey is needed
+async fn stream_listen_key(
+    config: &UserStreamConfig,
+    listen_key: &str,
+    events: &mpsc::Sender<UserEvent>,
+    shutdown_rx: &mut watch::Receiver<bool>,
+) -> bool {
+    let connection_config = ConnectionConfig {
+        url: config.endpoints.raw_url(listen_key),
+        ..config.connection.clone()
+    };
+    let (connection_tx, mut connection_rx) = mpsc::channel(1024);
+    // The user stream takes no subscriptions, but the connection expects a command channel
+    let (_commands_tx, commands_rx) = mpsc::channel(1);
+    let (stop_tx, stop_rx) = watch::channel(false);
+    let connection = tokio::spawn(run_connection(
+        connection_config,
+        connection_tx,
+        commands_rx,
+        stop_rx,
+        config.backoff.clone(),
+    ));
+
+    let url = config.endpoints.listen_key_url();
+    let mut keepalive = tokio::time::interval_at(
+        Instant::now() + config.keepalive_interval,
+        config.keepalive_interval,
+    );
+    let renew = loop {
+        tokio::select! {
+            Some(event) = connection_rx.recv() => {
+                let ConnectionEvent::Text(text) = event else {
+                    continue;
+                };
+                let event = match parse_user_event(&text) {
+                    Ok(Some(UserEvent::ListenKeyExpired)) => break true,
+                    Ok(Some(event)) => event,
+                    _ => continue,
+                };
+                // Nobody is watching the account anymore
+                if events.send(event).await.is_err() {
+                    break false;
+                }
+            }
+            _ = keepalive.tick() => {
+                // A listenKey the server no longer knows cannot be revived
+                if keep_alive_listen_key(&url, &config.api_key, listen_key).await.is_err() {
+                    break true;
+                }
+            }
+            _ = shutdown_rx.changed() => break false,
+        }
+    };
+
+    let _ = stop_tx.send(true);
+    drop(connection_rx);
+    let _ = connection.await;
+    renew
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchange::binance::Binance;
+    use crate::test_support::{spawn_recording_http_server, spawn_ws_server};
+    use std::sync::Arc;
+
+    fn config(ws_url: &str, rest_url: &str) -> UserStreamConfig {
+        UserStreamConfig {
+            endpoints: Endpoints::custom(ws_url, rest_url),
+            api_key: "api-key".to_string(),
+            keepalive_interval: Duration::from_millis(50),
+            connection: ConnectionConfig {
+                url: String::new(),
+                streams: Vec::new(),
+                base_id: 1,
+                rotate_after: Duration::from_secs(60),
+                rotation_overlap: Duration::from_secs(1),
+                rotation_retry: Duration::from_secs(30),
+                silence_timeout: Duration::from_secs(60),
+                request_timeout: Duration::from_secs(10),
+                combined: None,
+                exchange: Arc::new(Binance::default()),
+            },
+            backoff: Backoff::new(Duration::from_millis(10), Duration::from_millis(20)),
+        }
+    }
+
+    async fn spawn_rest_server() -> (String, mpsc::UnboundedReceiver<String>) {
+        spawn_recording_http_server(vec![
+            (
+                "/api/v3/userDataStream",
+                r#"{"listenKey":"listen-key"}"#.to_string(),
+            ),
+            (
+                "/api/v3/userDataStream?listenKey=listen-key",
+                "{}".to_string(),
+            ),
+        ])
+        .await
+    }
+
+    // Wait for a request starting with `prefix`, skipping the others
+    async fn next_request(requests: &mut mpsc::UnboundedReceiver<String>, prefix: &str) -> String {
+        loop {
+            let request = requests.recv().await.unwrap();
+            if request.starts_with(prefix) {
+                return request;
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_user_stream_keeps_its_listen_key_alive() {
+        let (rest_url, mut requests) = spawn_rest_server().await;
+        let ws_url = spawn_ws_server(vec![
+            r#"{"e":"executionReport","E":1499405658658,"s":"ETHBTC","c":"a","S":"BUY","o":"LIMIT","f":"GTC","q":"1.0","p":"0.1","x":"NEW","X":"NEW","r":"NONE","i":1,"l":"0.0","z":"0.0","L":"0.0","n":"0","N":null,"T":1499405658657,"t":-1,"m":false}"#.to_string(),
+            r#"{"e":"outboundAccountPosition","E":1564034571105,"u":1564034571073,"B":[{"a":"ETH","f":"1.0","l":"0.0"}]}"#.to_string(),
+            r#"{"e":"balanceUpdate","E":1573200697110,"a":"BTC","d":"1.0","T":1573200697068}"#.to_string(),
+        ])
+        .await;
+        let (events_tx, mut events_rx) = mpsc::channel(16);
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let stream = tokio::spawn(run_user_stream(
+            config(&ws_url, &rest_url),
+            events_tx,
+            shutdown_rx,
+        ));
+
+        let create = next_request(&mut requests, "POST").await;
+        assert!(create.to_lowercase().contains("x-mbx-apikey: api-key"));
+        assert!(matches!(
+            events_rx.recv().await,
+            Some(UserEvent::ExecutionReport(_))
+        ));
+        assert!(matches!(
+            events_rx.recv().await,
+            Some(UserEvent::AccountPosition(_))
+        ));
+        assert!(matches!(
+            events_rx.recv().await,
+            Some(UserEvent::BalanceUpdate(_))
+        ));
+        next_request(
+            &mut requests,
+            "PUT /api/v3/userDataStream?listenKey=listen-key",
+        )
+        .await;
+
+        // The listenKey is closed on the way out
+        shutdown_tx.send(true).unwrap();
+        stream.await.unwrap();
+        next_request(
+            &mut requests,
+            "DELETE /api/v3/userDataStream?listenKey=listen-key",
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_expired_listen_key_is_replaced() {
+        let (rest_url, mut requests) = spawn_rest_server().await;
+        let ws_url = spawn_ws_server(vec![
+            r#"{"e":"listenKeyExpired","E":1576653824250,"listenKey":"OfY"}"#.to_string(),
+        ])
+        .await;
+        let (events_tx, _events_rx) = mpsc::channel(16);
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let mut config = config(&ws_url, &rest_url);
+        config.keepalive_interval = Duration::from_secs(60);
+        let stream = tokio::spawn(run_user_stream(config, events_tx, shutdown_rx));
+
+        next_request(&mut requests, "POST").await;
+        next_request(&mut requests, "POST").await;
+
+        shutdown_tx.send(true).unwrap();
+        stream.await.unwrap();
+    }
+}
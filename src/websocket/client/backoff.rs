@@ -0,0 +1,85 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+/// Exponential backoff with jitter between reconnection attempts
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    base_delay: Duration,
+    max_delay: Duration,
+    attempt: u32,
+}
+
+impl Backoff {
+    // Create a Backoff doubling from `base_delay` up to `max_delay`
+    pub fn new(base_delay: Duration, max_delay: Duration) -> Self {
+        Self {
+            base_delay,
+            max_delay,
+            attempt: 0,
+        }
+    }
+
+    // Number of delays handed out since the last reset
+    pub fn attempt(&self) -> u32 {
+        self.attempt
+    }
+
+    // Start again from the base delay, e.g. after a successful connection
+    pub fn reset(&mut self) {
+        self.attempt = 0;
+    }
+
+    // Get the delay before the next attempt
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self.delay_for(self.attempt, random_fraction());
+        self.attempt = self.attempt.saturating_add(1);
+        delay
+    }
+
+    // Delay for an attempt: half of the capped exponential delay is fixed and
+    // the other half scaled by `jitter` in [0, 1), so clients spread out after an outage
+    fn delay_for(&self, attempt: u32, jitter: f64) -> Duration {
+        let exponential = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay);
+        exponential / 2 + exponential.mul_f64(jitter / 2.0)
+    }
+}
+
+// Random number in [0, 1), using the randomly keyed std hasher
+fn random_fraction() -> f64 {
+    let random = RandomState::new().build_hasher().finish();
+    (random >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delay_doubles_up_to_max() {
+        let backoff = Backoff::new(Duration::from_millis(100), Duration::from_secs(1));
+        assert_eq!(backoff.delay_for(0, 0.0), Duration::from_millis(50));
+        assert_eq!(backoff.delay_for(1, 0.0), Duration::from_millis(100));
+        assert_eq!(backoff.delay_for(2, 0.0), Duration::from_millis(200));
+        assert_eq!(backoff.delay_for(3, 0.5), Duration::from_millis(600));
+        assert_eq!(backoff.delay_for(20, 0.0), Duration::from_millis(500));
+        assert_eq!(backoff.delay_for(u32::MAX, 0.0), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_jitter_stays_within_bounds_and_reset() {
+        let mut backoff = Backoff::new(Duration::from_millis(100), Duration::from_secs(1));
+        for attempt in 0..10 {
+            let delay = backoff.next_delay();
+            let exponential = Duration::from_millis(100 << attempt).min(Duration::from_secs(1));
+            assert!(delay >= exponential / 2 && delay < exponential);
+        }
+        assert_eq!(backoff.attempt(), 10);
+        backoff.reset();
+        assert_eq!(backoff.attempt(), 0);
+        assert!(backoff.next_delay() < Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,1223 @@
+use crate::exchange::Exchange;
+use crate::websocket::client::backoff::Backoff;
+use crate::websocket::client::dedupe::Deduplicator;
+use crate::websocket::client::rate_limit::TokenBucket;
+use crate::websocket::client::redundant::DeliveryStats;
+use crate::websocket::client::requests::{PendingRequests, RequestError};
+use crate::websocket::client::writer::{spawn_writer, OutgoingSink};
+use crate::websocket::ping::{handle_incoming, start_ping, Liveness};
+use futures_util::{SinkExt, StreamExt};
+use serde_json::Value;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot, watch};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tokio_tungstenite::tungstenite::{self, protocol::Message};
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+
+/// State of the supervised connection, shown on the dashboard
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConnectionState {
+    Connecting,
+    Connected,
+    Reconnecting { attempt: u32, delay: Duration },
+    Closed,
+}
+
+impl fmt::Display for ConnectionState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConnectionState::Connecting => write!(f, "Connecting"),
+            ConnectionState::Connected => write!(f, "Connected"),
+            ConnectionState::Reconnecting { attempt, delay } => write!(
+                f,
+                "Reconnecting in {:.1}s (attempt {})",
+                delay.as_secs_f64(),
+                attempt
+            ),
+            ConnectionState::Closed => write!(f, "Closed"),
+        }
+    }
+}
+
+/// Everything the connection reports to its consumer
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConnectionEvent {
+    State(ConnectionState),
+    Text(String),
+    /// The streams subscribed after a command changed them
+    Streams(Vec<String>),
+    /// The server's answer to a request, or why none arrived
+    Reply {
+        command: ConnectionCommand,
+        result: Result<Value, RequestError>,
+    },
+    /// How often each redundant connection delivered events first
+    Deliveries(Vec<DeliveryStats>),
+}
+
+/// Requests sent to the server while the connection is running
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConnectionCommand {
+    Subscribe(Vec<String>),
+    Unsubscribe(Vec<String>),
+    ListSubscriptions,
+    /// Whether payloads are wrapped in `{"stream": ..., "data": ...}`
+    SetCombined(bool),
+    GetProperty(String),
+}
+
+impl fmt::Display for ConnectionCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConnectionCommand::Subscribe(streams) => write!(f, "SUBSCRIBE {}", streams.join(", ")),
+            ConnectionCommand::Unsubscribe(streams) => {
+                write!(f, "UNSUBSCRIBE {}", streams.join(", "))
+            }
+            ConnectionCommand::ListSubscriptions => write!(f, "LIST_SUBSCRIPTIONS"),
+            ConnectionCommand::SetCombined(combined) => {
+                write!(f, "SET_PROPERTY combined {}", combined)
+            }
+            ConnectionCommand::GetProperty(property) => write!(f, "GET_PROPERTY {}", property),
+        }
+    }
+}
+
+/// Where to connect, what to subscribe to and when to rotate the connection
+#[derive(Debug, Clone)]
+pub struct ConnectionConfig {
+    pub url: String,
+    pub streams: Vec<String>,
+    pub base_id: u64,
+    /// Age at which a replacement connection is opened, ahead of the server's 24 hour limit
+    pub rotate_after: Duration,
+    /// How long the old and new connections run side by side
+    pub rotation_overlap: Duration,
+    /// Delay before retrying a rotation whose replacement failed to open or dropped
+    pub rotation_retry: Duration,
+    /// Silence after which a connection is considered dead and replaced
+    pub silence_timeout: Duration,
+    /// How long a subscription request may wait for its reply
+    pub request_timeout: Duration,
+    /// Payload format requested with SET_PROPERTY on every new connection, if any
+    pub combined: Option<bool>,
+    /// Venue whose request and reply format the connection speaks
+    pub exchange: Arc<dyn Exchange>,
+}
+
+/// Most streams Binance allows on a single connection
+pub const MAX_STREAMS_PER_CONNECTION: usize = 1024;
+// Binance drops connections sending more than 5 messages a second; a burst of 2 refilled
+// at 3 per second never exceeds that within any one-second window
+const MESSAGE_BURST: u32 = 2;
+const MESSAGES_PER_SECOND: f64 = 3.0;
+// Uptime after which a connection counts as healthy even without any data
+const HEALTHY_AFTER: Duration = Duration::from_secs(30);
+// Recent events remembered to filter what an overlapping connection repeats
+const DEDUPE_CAPACITY: usize = 10_000;
+
+// What a session passes on to the supervisor
+enum SessionEvent {
+    Text(String),
+    Reply(ConnectionCommand, Result<Value, RequestError>),
+}
+
+// Why the supervised connection stopped delivering
+enum SessionEnd {
+    Shutdown,
+    Disconnected,
+}
+
+// A subscribed connection forwarding its text frames from a separate task
+struct Session {
+    stop_tx: watch::Sender<bool>,
+    commands_tx: mpsc::UnboundedSender<ConnectionCommand>,
+    handle: JoinHandle<()>,
+}
+
+impl Session {
+    // Pass a subscription change on to the connection
+    fn send(&self, command: ConnectionCommand) {
+        let _ = self.commands_tx.send(command);
+    }
+
+    // Unsubscribe, close the connection and wait for its task to finish
+    async fn stop(self) {
+        let _ = self.stop_tx.send(true);
+        let _ = self.handle.await;
+    }
+}
+
+// A replacement connection being opened in the background, and what it was opened with
+struct Opening {
+    task: JoinHandle<Option<Session>>,
+    streams: Vec<String>,
+    combined: Option<bool>,
+}
+
+impl Opening {
+    // Start connecting, giving up after the overlap the old connection can still cover
+    fn start(
+        config: &ConnectionConfig,
+        streams: &[String],
+        combined: Option<bool>,
+        texts: &mpsc::Sender<SessionEvent>,
+    ) -> Self {
+        let (config, texts) = (config.clone(), texts.clone());
+        let subscribed = streams.to_vec();
+        let task = tokio::spawn(async move {
+            let opened = open_session(&config, &subscribed, combined, &texts);
+            match tokio::time::timeout(config.rotation_overlap, opened).await {
+                Ok(Ok(session)) => Some(session),
+                _ => None,
+            }
+        });
+        Self {
+            task,
+            streams: streams.to_vec(),
+            combined,
+        }
+    }
+
+    // Pass on the changes made while the session was connecting
+    fn catch_up(&self, session: &Session, streams: &[String], combined: Option<bool>) {
+        let added: Vec<String> = streams
+            .iter()
+            .filter(|stream| !self.streams.contains(stream))
+            .cloned()
+            .collect();
+        let removed: Vec<String> = self
+            .streams
+            .iter()
+            .filter(|stream| !streams.contains(stream))
+            .cloned()
+            .collect();
+        if let Some(value) = combined.filter(|_| combined != self.combined) {
+            session.send(ConnectionCommand::SetCombined(value));
+        }
+        if !added.is_empty() {
+            session.send(ConnectionCommand::Subscribe(added));
+        }
+        if !removed.is_empty() {
+            session.send(ConnectionCommand::Unsubscribe(removed));
+        }
+    }
+
+    // Stop connecting, closing the session if it opened in the meantime
+    async fn cancel(self) {
+        self.task.abort();
+        if let Ok(Some(session)) = self.task.await {
+            session.stop().await;
+        }
+    }
+}
+
+// Wait for the replacement being opened, if any
+async fn opened(opening: &mut Option<Opening>) -> Option<Session> {
+    match opening {
+        Some(opening) => (&mut opening.task).await.ok().flatten(),
+        None => std::future::pending().await,
+    }
+}
+
+/// Keeps a connection to `config.url` open until `shutdown_rx` fires or the consumer goes away.
+/// Streams are added or removed through `commands`, and lost connections are re-established
+/// after a backoff delay with the current streams subscribed again.
+/// Before a connection reaches `rotate_after` a replacement is opened alongside it and the
+/// old connection is closed once they have overlapped; events already delivered are dropped.
+pub async fn run_connection(
+    config: ConnectionConfig,
+    events: mpsc::Sender<ConnectionEvent>,
+    mut commands: mpsc::Receiver<ConnectionCommand>,
+    mut shutdown_rx: watch::Receiver<bool>,
+    mut backoff: Backoff,
+) {
+    let (mut streams, refused) = fit_streams(&[], config.streams.clone());
+    if !refused.is_empty() {
+        let _ = events.send(stream_limit_reply(refused)).await;
+    }
+    // Set by SET_PROPERTY and applied again on every new connection
+    let mut combined = config.combined;
+    let (texts_tx, mut texts_rx) = mpsc::channel::<SessionEvent>(1024);
+    let mut dedupe = Deduplicator::new(DEDUPE_CAPACITY);
+    let _ = events
+        .send(ConnectionEvent::State(ConnectionState::Connecting))
+        .await;
+
+    'connect: loop {
+        let mut active = loop {
+            let opened = tokio::select! {
+                opened = open_session(&config, &streams, combined, &texts_tx) => opened,
+                _ = shutdown_rx.changed() => break 'connect,
+            };
+            if let Ok(session) = opened {
+                break session;
+            }
+            if !wait_to_reconnect(&mut backoff, &events, &mut shutdown_rx).await {
+                break 'connect;
+            }
+        };
+
+        let state = ConnectionEvent::State(ConnectionState::Connected);
+        if events.send(state).await.is_err() {
+            active.stop().await;
+            break;
+        }
+
+        // The connection being replaced and when to close it
+        let mut retiring: Option<Session> = None;
+        // The replacement is opened without holding up frames, commands or shutdown
+        let mut opening: Option<Opening> = None;
+        let mut overlap_end: Option<Instant> = None;
+        // Connections only deliver the same events around a rotation, so duplicates are
+        // looked for from one overlap before it until one overlap after the old one closes
+        let mut dedupe_until: Option<Instant> = None;
+        let rotation = tokio::time::sleep(config.rotate_after);
+        tokio::pin!(rotation);
+        // The backoff restarts only once the connection delivers data, has its
+        // subscription accepted or stays up for a while, so a server that accepts the
+        // handshake and then drops or refuses us is retried ever more slowly
+        let mut healthy = false;
+        let healthy_after = tokio::time::sleep(HEALTHY_AFTER);
+        tokio::pin!(healthy_after);
+
+        let end = loop {
+            tokio::select! {
+                Some(event) = texts_rx.recv() => {
+                    let now = Instant::now();
+                    if dedupe_until.is_some_and(|until| now >= until) {
+                        dedupe_until = None;
+                        dedupe.clear();
+                    }
+                    let deduping = retiring.is_some()
+                        || dedupe_until.is_some()
+                        || now + config.rotation_overlap >= rotation.deadline();
+                    let event = match event {
+                        SessionEvent::Text(text) if deduping && dedupe.is_duplicate(&text) => continue,
+                        SessionEvent::Text(text) => {
+                            if !healthy {
+                                healthy = true;
+                                backoff.reset();
+                            }
+                            ConnectionEvent::Text(text)
+                        }
+                        SessionEvent::Reply(command, result) => {
+                            if !healthy && matches!(command, ConnectionCommand::Subscribe(_)) && result.is_ok() {
+                                healthy = true;
+                                backoff.reset();
+                            }
+                            if reject_streams(&mut streams, &command, &result)
+                                && events.send(ConnectionEvent::Streams(streams.clone())).await.is_err()
+                            {
+                                break SessionEnd::Shutdown;
+                            }
+                            ConnectionEvent::Reply { command, result }
+                        }
+                    };
+                    // The consumer is gone, so nobody needs the connection anymore
+                    if events.send(event).await.is_err() {
+                        break SessionEnd::Shutdown;
+                    }
+                }
+                _ = &mut healthy_after, if !healthy => {
+                    healthy = true;
+                    backoff.reset();
+                }
+                _ = &mut active.handle => {
+                    // Fall back to the old connection if its replacement drops mid-rotation;
+                    // it is close to the server's limit, so rotate again shortly
+                    match retiring.take() {
+                        Some(old) => {
+                            active = old;
+                            overlap_end = None;
+                            dedupe_until = Some(Instant::now() + config.rotation_overlap);
+                            rotation.as_mut().reset(Instant::now() + config.rotation_retry);
+                        }
+                        None => break SessionEnd::Disconnected,
+                    }
+                }
+                _ = &mut rotation, if opening.is_none() => {
+                    opening = Some(Opening::start(&config, &streams, combined, &texts_tx));
+                }
+                replacement = opened(&mut opening), if opening.is_some() => {
+                    let Some(started) = opening.take() else {
+                        continue;
+                    };
+                    match replacement {
+                        Some(replacement) => {
+                            started.catch_up(&replacement, &streams, combined);
+                            retiring = Some(std::mem::replace(&mut active, replacement));
+                            overlap_end = Some(Instant::now() + config.rotation_overlap);
+                            rotation.as_mut().reset(Instant::now() + config.rotate_after);
+                        }
+                        // The old connection still has time left, so try again shortly
+                        None => rotation.as_mut().reset(Instant::now() + config.rotation_retry),
+                    }
+                }
+                _ = tokio::time::sleep_until(overlap_end.unwrap_or_else(Instant::now)), if overlap_end.is_some() => {
+                    overlap_end = None;
+                    if let Some(old) = retiring.take() {
+                        old.stop().await;
+                    }
+                    // Frames the old connection sent before closing may still be queued
+                    dedupe_until = Some(Instant::now() + config.rotation_overlap);
+                }
+                Some(command) = commands.recv() => {
+                    // Streams beyond the connection's limit are refused rather than sent
+                    let command = match command {
+                        ConnectionCommand::Subscribe(requested) => {
+                            let (accepted, refused) = fit_streams(&streams, requested);
+                            if !refused.is_empty() && events.send(stream_limit_reply(refused)).await.is_err() {
+                                break SessionEnd::Shutdown;
+                            }
+                            ConnectionCommand::Subscribe(accepted)
+                        }
+                        command => command,
+                    };
+                    let Some(change) = apply_command(&mut streams, &mut combined, command) else {
+                        continue;
+                    };
+                    let is_query = matches!(
+                        change,
+                        ConnectionCommand::ListSubscriptions | ConnectionCommand::GetProperty(_)
+                    );
+                    // Both connections must follow changes while they overlap; queries need one answer
+                    if let Some(old) = retiring.as_ref().filter(|_| !is_query) {
+                        old.send(change.clone());
+                    }
+                    let changes_streams = matches!(
+                        change,
+                        ConnectionCommand::Subscribe(_) | ConnectionCommand::Unsubscribe(_)
+                    );
+                    active.send(change);
+                    if changes_streams
+                        && events.send(ConnectionEvent::Streams(streams.clone())).await.is_err()
+                    {
+                        break SessionEnd::Shutdown;
+                    }
+                }
+                _ = shutdown_rx.changed() => break SessionEnd::Shutdown,
+            }
+        };
+
+        if let Some(started) = opening.take() {
+            started.cancel().await;
+        }
+        if let Some(old) = retiring.take() {
+            old.stop().await;
+        }
+        match end {
+            SessionEnd::Shutdown => {
+                active.stop().await;
+                break;
+            }
+            SessionEnd::Disconnected => {
+                if !wait_to_reconnect(&mut backoff, &events, &mut shutdown_rx).await {
+                    break;
+                }
+            }
+        }
+    }
+
+    let _ = events
+        .send(ConnectionEvent::State(ConnectionState::Closed))
+        .await;
+}
+
+// Split requested streams into those that fit within the per-connection limit and the rest
+fn fit_streams(active: &[String], requested: Vec<String>) -> (Vec<String>, Vec<String>) {
+    let mut room = MAX_STREAMS_PER_CONNECTION.saturating_sub(active.len());
+    let mut accepted: Vec<String> = Vec::new();
+    let mut refused = Vec::new();
+    for stream in requested {
+        // Streams already subscribed or requested take no extra room
+        if active.contains(&stream) || accepted.contains(&stream) {
+            accepted.push(stream);
+        } else if room > 0 {
+            room -= 1;
+            accepted.push(stream);
+        } else {
+            refused.push(stream);
+        }
+    }
+    (accepted, refused)
+}
+
+// Report streams that were refused for exceeding the per-connection limit
+fn stream_limit_reply(refused: Vec<String>) -> ConnectionEvent {
+    ConnectionEvent::Reply {
+        command: ConnectionCommand::Subscribe(refused),
+        result: Err(RequestError::StreamLimit {
+            limit: MAX_STREAMS_PER_CONNECTION,
+        }),
+    }
+}
+
+// Update the stream set and properties, returning the part of the command to send
+fn apply_command(
+    streams: &mut Vec<String>,
+    combined: &mut Option<bool>,
+    command: ConnectionCommand,
+) -> Option<ConnectionCommand> {
+    match command {
+        ConnectionCommand::Subscribe(requested) => {
+            let mut added = Vec::new();
+            for stream in requested {
+                if !streams.contains(&stream) {
+                    streams.push(stream.clone());
+                    added.push(stream);
+                }
+            }
+            (!added.is_empty()).then_some(ConnectionCommand::Subscribe(added))
+        }
+        ConnectionCommand::Unsubscribe(requested) => {
+            let mut removed = Vec::new();
+            for stream in requested {
+                if let Some(position) = streams.iter().position(|active| *active == stream) {
+                    removed.push(streams.remove(position));
+                }
+            }
+            (!removed.is_empty()).then_some(ConnectionCommand::Unsubscribe(removed))
+        }
+        ConnectionCommand::SetCombined(value) => {
+            *combined = Some(value);
+            Some(ConnectionCommand::SetCombined(value))
+        }
+        query => Some(query),
+    }
+}
+
+// Drop streams the server refused to subscribe to, returning whether any were dropped
+fn reject_streams(
+    streams: &mut Vec<String>,
+    command: &ConnectionCommand,
+    result: &Result<Value, RequestError>,
+) -> bool {
+    let (ConnectionCommand::Subscribe(refused), Err(RequestError::Rejected { .. })) =
+        (command, result)
+    else {
+        return false;
+    };
+    let count = streams.len();
+    streams.retain(|stream| !refused.contains(stream));
+    streams.len() != count
+}
+
+// Report the next attempt and wait for it; false when shutting down instead
+async fn wait_to_reconnect(
+    backoff: &mut Backoff,
+    events: &mpsc::Sender<ConnectionEvent>,
+    shutdown_rx: &mut watch::Receiver<bool>,
+) -> bool {
+    let delay = backoff.next_delay();
+    let state = ConnectionState::Reconnecting {
+        attempt: backoff.attempt(),
+        delay,
+    };
+    if events.send(ConnectionEvent::State(state)).await.is_err() {
+        return false;
+    }
+    tokio::select! {
+        _ = tokio::time::sleep(delay) => true,
+        _ = shutdown_rx.changed() => false,
+    }
+}
+
+// Connect and start a session subscribed to `streams`, forwarding what it receives to `texts`
+async fn open_session(
+    config: &ConnectionConfig,
+    streams: &[String],
+    combined: Option<bool>,
+    texts: &mpsc::Sender<SessionEvent>,
+) -> Result<Session, tungstenite::Error> {
+    let (ws_stream, _) = connect_async(config.url.as_str()).await?;
+    let (stop_tx, stop_rx) = watch::channel(false);
+    let (commands_tx, commands_rx) = mpsc::unbounded_channel();
+    let handle = tokio::spawn(run_session(
+        ws_stream,
+        config.clone(),
+        streams.to_vec(),
+        combined,
+        texts.clone(),
+        commands_rx,
+        stop_rx,
+    ));
+    Ok(Session {
+        stop_tx,
+        commands_tx,
+        handle,
+    })
+}
+
+/// Subscribes on a fresh connection and forwards its text frames until it ends.
+/// Replies are matched to the requests that caused them, server pings are answered,
+/// and a connection silent for `silence_timeout` is dropped.
+async fn run_session(
+    ws_stream: WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>,
+    config: ConnectionConfig,
+    mut streams: Vec<String>,
+    combined: Option<bool>,
+    texts: mpsc::Sender<SessionEvent>,
+    mut commands: mpsc::UnboundedReceiver<ConnectionCommand>,
+    mut stop_rx: watch::Receiver<bool>,
+) {
+    let (write, mut read) = ws_stream.split();
+    let limiter = TokenBucket::new(
+        MESSAGE_BURST,
+        MESSAGES_PER_SECOND,
+        std::time::Instant::now(),
+    );
+    let (mut outgoing, writer) = spawn_writer(write, limiter);
+    let mut requests = PendingRequests::new(config.base_id);
+    let exchange = config.exchange.as_ref();
+
+    // Choose the payload format first so even the first frames have it
+    if let Some(combined) = combined {
+        let set_combined = ConnectionCommand::SetCombined(combined);
+        if send_command(&mut outgoing, &mut requests, exchange, set_combined)
+            .await
+            .is_err()
+        {
+            return;
+        }
+    }
+
+    // Subscribe to streams; a pooled connection may start without any
+    if !streams.is_empty() {
+        let subscribe = ConnectionCommand::Subscribe(streams.clone());
+        if send_command(&mut outgoing, &mut requests, exchange, subscribe)
+            .await
+            .is_err()
+        {
+            return;
+        }
+    }
+
+    // Start ping often enough that a healthy but quiet connection answers within the window;
+    // it sends the close frame once the session ends
+    let (stop_ping_tx, stop_ping_rx) = oneshot::channel();
+    let mut ping_sink = outgoing.clone();
+    let ping_period = config.silence_timeout / 3;
+    let ping = tokio::spawn(async move {
+        start_ping(&mut ping_sink, ping_period, stop_ping_rx).await;
+    });
+
+    let mut liveness = Liveness::new(std::time::Instant::now());
+    let mut watchdog =
+        tokio::time::interval(config.silence_timeout.min(config.request_timeout) / 4);
+    loop {
+        tokio::select! {
+            message = read.next() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(message)) => match handle_incoming(&mut outgoing, message, &mut liveness).await {
+                    Ok(Some(text)) => {
+                        let event = match exchange.parse_reply(&text) {
+                            // Replies to ids we did not send, or already timed out, are dropped
+                            Some((id, result)) => match match id {
+                                Some(id) => requests.resolve(id, result),
+                                None => requests.resolve_oldest(result),
+                            } {
+                                Some((command, result)) => {
+                                    // Refresh the confirmed list whenever the server accepts a change
+                                    let changed = matches!(
+                                        command,
+                                        ConnectionCommand::Subscribe(_) | ConnectionCommand::Unsubscribe(_)
+                                    );
+                                    if changed && result.is_ok() {
+                                        let list = ConnectionCommand::ListSubscriptions;
+                                        if send_command(&mut outgoing, &mut requests, exchange, list).await.is_err() {
+                                            break;
+                                        }
+                                    }
+                                    SessionEvent::Reply(command, result)
+                                }
+                                None => continue,
+                            },
+                            None => SessionEvent::Text(text),
+                        };
+                        if texts.send(event).await.is_err() {
+                            break;
+                        }
+                    }
+                    Ok(None) => {}
+                    Err(_) => break,
+                },
+            },
+            _ = watchdog.tick() => {
+                let now = std::time::Instant::now();
+                for (command, error) in requests.expire(now, config.request_timeout) {
+                    let _ = texts.send(SessionEvent::Reply(command, Err(error))).await;
+                }
+                // Nothing arrived for too long, so the connection is treated as lost
+                if liveness.is_stale(now, config.silence_timeout) {
+                    break;
+                }
+            }
+            Some(command) = commands.recv() => {
+                match &command {
+                    ConnectionCommand::Subscribe(added) => streams.extend(added.iter().cloned()),
+                    ConnectionCommand::Unsubscribe(removed) => {
+                        streams.retain(|stream| !removed.contains(stream))
+                    }
+                    _ => {}
+                }
+                let sent = send_command(&mut outgoing, &mut requests, exchange, command.clone()).await;
+                match sent.ok() {
+                    Some(true) => {}
+                    Some(false) => {
+                        let unsupported = RequestError::Unsupported(exchange.name());
+                        let _ = texts.send(SessionEvent::Reply(command, Err(unsupported))).await;
+                    }
+                    None => break,
+                }
+            }
+            _ = stop_rx.changed() => {
+                // Unsubscribe from streams; the connection closes before any reply could arrive
+                let unsubscribe = ConnectionCommand::Unsubscribe(streams.clone());
+                if let Some(message) = exchange.request_message(&unsubscribe, requests.next_id()) {
+                    let _ = outgoing.send(Message::Text(message)).await;
+                }
+                break;
+            }
+        }
+    }
+
+    for (command, error) in requests.fail_all() {
+        let _ = texts.send(SessionEvent::Reply(command, Err(error))).await;
+    }
+    let _ = stop_ping_tx.send(());
+    let _ = ping.await;
+    drop(outgoing);
+    let _ = writer.await;
+}
+
+// Send a request under a fresh id, recording it until the reply arrives.
+// Returns false without sending anything when the venue has no such request.
+async fn send_command(
+    outgoing: &mut OutgoingSink,
+    requests: &mut PendingRequests,
+    exchange: &dyn Exchange,
+    command: ConnectionCommand,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let id = requests.next_id();
+    let Some(message) = exchange.request_message(&command, id) else {
+        return Ok(false);
+    };
+    requests.track(id, command, std::time::Instant::now());
+    outgoing.send(Message::Text(message)).await?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchange::binance::Binance;
+    use crate::test_support::{spawn_ws_sessions, spawn_ws_sessions_closing};
+
+    fn config(url: String, rotate_after: Duration) -> ConnectionConfig {
+        ConnectionConfig {
+            url,
+            streams: vec!["btcusdt@aggTrade".to_string()],
+            base_id: 1,
+            rotate_after,
+            rotation_overlap: Duration::from_millis(100),
+            rotation_retry: Duration::from_millis(50),
+            silence_timeout: Duration::from_secs(60),
+            request_timeout: Duration::from_secs(10),
+            combined: None,
+            exchange: Arc::new(Binance::default()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reconnects_and_resubscribes() {
+        let (url, mut received) = spawn_ws_sessions(
+            vec![
+                vec![r#"{"session":1}"#.to_string()],
+                vec![r#"{"session":2}"#.to_string()],
+            ],
+            true,
+        )
+        .await;
+        let (events_tx, mut events_rx) = mpsc::channel(16);
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let backoff = Backoff::new(Duration::from_millis(10), Duration::from_millis(20));
+        let (_commands_tx, commands_rx) = mpsc::channel(16);
+        let connection = tokio::spawn(run_connection(
+            config(url, Duration::from_secs(60)),
+            events_tx,
+            commands_rx,
+            shutdown_rx,
+            backoff,
+        ));
+
+        // Both sessions deliver their frame, with a reconnect in between
+        let mut texts = Vec::new();
+        let mut states = Vec::new();
+        while texts.len() < 2 {
+            match events_rx.recv().await.unwrap() {
+                ConnectionEvent::Text(text) => texts.push(text),
+                ConnectionEvent::State(state) => states.push(state),
+                _ => {}
+            }
+        }
+        assert_eq!(texts, [r#"{"session":1}"#, r#"{"session":2}"#]);
+        assert_eq!(states[0], ConnectionState::Connecting);
+        assert_eq!(states[1], ConnectionState::Connected);
+        assert!(matches!(
+            states[2],
+            ConnectionState::Reconnecting { attempt: 1, .. }
+        ));
+        assert_eq!(states[3], ConnectionState::Connected);
+
+        // The subscription is sent again on the new connection
+        for _ in 0..2 {
+            let message: serde_json::Value =
+                serde_json::from_str(&received.recv().await.unwrap()).unwrap();
+            assert_eq!(message["method"], "SUBSCRIBE");
+            assert_eq!(message["params"][0], "btcusdt@aggTrade");
+        }
+
+        shutdown_tx.send(true).unwrap();
+        connection.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_backoff_grows_when_connections_drop_before_any_data() {
+        // Every connection is closed as soon as the client subscribes, without a reply
+        let (url, _received) = spawn_ws_sessions(vec![Vec::new(); 4], true).await;
+        let (events_tx, mut events_rx) = mpsc::channel(16);
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let backoff = Backoff::new(Duration::from_millis(10), Duration::from_secs(1));
+        let (_commands_tx, commands_rx) = mpsc::channel(16);
+        let connection = tokio::spawn(run_connection(
+            config(url, Duration::from_secs(60)),
+            events_tx,
+            commands_rx,
+            shutdown_rx,
+            backoff,
+        ));
+
+        let mut reconnects = Vec::new();
+        while reconnects.len() < 3 {
+            if let ConnectionEvent::State(ConnectionState::Reconnecting { attempt, delay }) =
+                events_rx.recv().await.unwrap()
+            {
+                reconnects.push((attempt, delay));
+            }
+        }
+        let attempts: Vec<u32> = reconnects.iter().map(|(attempt, _)| *attempt).collect();
+        assert_eq!(attempts, [1, 2, 3]);
+        // Jitter keeps each delay within [d/2, d) of a doubling d, so they strictly grow
+        assert!(reconnects.windows(2).all(|pair| pair[0].1 < pair[1].1));
+
+        shutdown_tx.send(true).unwrap();
+        connection.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_rotation_overlaps_connections_without_duplicates() {
+        let agg_trade = |id: u64| {
+            format!(
+                r#"{{"e":"aggTrade","E":{},"s":"BTCUSDT","a":{},"p":"1.0","q":"1.0","f":{},"l":{},"T":{},"m":true,"M":true}}"#,
+                id, id, id, id, id
+            )
+        };
+        // The replacement connection repeats the last trades of the old one
+        let (url, mut received) = spawn_ws_sessions(
+            vec![
+                (1..=3).map(agg_trade).collect(),
+                (2..=4).map(agg_trade).collect(),
+            ],
+            false,
+        )
+        .await;
+        let (events_tx, mut events_rx) = mpsc::channel(16);
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let backoff = Backoff::new(Duration::from_millis(10), Duration::from_millis(20));
+        let (_commands_tx, commands_rx) = mpsc::channel(16);
+        let connection = tokio::spawn(run_connection(
+            config(url, Duration::from_millis(100)),
+            events_tx,
+            commands_rx,
+            shutdown_rx,
+            backoff,
+        ));
+
+        let mut texts = Vec::new();
+        let mut states = Vec::new();
+        while texts.len() < 4 {
+            match events_rx.recv().await.unwrap() {
+                ConnectionEvent::Text(text) => texts.push(text),
+                ConnectionEvent::State(state) => states.push(state),
+                _ => {}
+            }
+        }
+        let expected: Vec<_> = (1..=4).map(agg_trade).collect();
+        assert_eq!(texts, expected);
+        assert_eq!(
+            states,
+            [ConnectionState::Connecting, ConnectionState::Connected]
+        );
+
+        // The new connection subscribes before the old one unsubscribes
+        let mut methods = Vec::new();
+        for _ in 0..3 {
+            let message: serde_json::Value =
+                serde_json::from_str(&received.recv().await.unwrap()).unwrap();
+            methods.push(message["method"].as_str().unwrap().to_string());
+        }
+        assert_eq!(methods, ["SUBSCRIBE", "SUBSCRIBE", "UNSUBSCRIBE"]);
+
+        shutdown_tx.send(true).unwrap();
+        connection.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_repeated_payloads_pass_outside_rotations() {
+        // A ticker may legitimately repeat itself byte for byte
+        let ticker = r#"{"e":"24hrMiniTicker","E":1,"s":"BTCUSDT","c":"1","o":"1","h":"1","l":"1","v":"0","q":"0"}"#;
+        let (url, _received) =
+            spawn_ws_sessions(vec![vec![ticker.to_string(), ticker.to_string()]], false).await;
+        let (events_tx, mut events_rx) = mpsc::channel(16);
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let backoff = Backoff::new(Duration::from_millis(10), Duration::from_millis(20));
+        let (_commands_tx, commands_rx) = mpsc::channel(16);
+        let connection = tokio::spawn(run_connection(
+            config(url, Duration::from_secs(60)),
+            events_tx,
+            commands_rx,
+            shutdown_rx,
+            backoff,
+        ));
+
+        let mut texts = Vec::new();
+        while texts.len() < 2 {
+            if let ConnectionEvent::Text(text) = events_rx.recv().await.unwrap() {
+                texts.push(text);
+            }
+        }
+        assert_eq!(texts, [ticker, ticker]);
+
+        shutdown_tx.send(true).unwrap();
+        connection.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_rotation_is_retried_when_the_replacement_drops() {
+        let frame = |session: u64| format!(r#"{{"session":{}}}"#, session);
+        // The replacement closes at once, leaving the old connection close to its limit
+        let (url, _received) = spawn_ws_sessions_closing(vec![
+            (vec![frame(1)], false),
+            (Vec::new(), true),
+            (vec![frame(3)], false),
+        ])
+        .await;
+        let (events_tx, mut events_rx) = mpsc::channel(16);
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let backoff = Backoff::new(Duration::from_millis(10), Duration::from_millis(20));
+        let (_commands_tx, commands_rx) = mpsc::channel(16);
+        let rotate_after = Duration::from_millis(500);
+        let start = Instant::now();
+        let connection = tokio::spawn(run_connection(
+            ConnectionConfig {
+                rotation_overlap: Duration::from_secs(5),
+                ..config(url, rotate_after)
+            },
+            events_tx,
+            commands_rx,
+            shutdown_rx,
+            backoff,
+        ));
+
+        let mut texts = Vec::new();
+        let mut states = Vec::new();
+        while texts.len() < 2 {
+            match events_rx.recv().await.unwrap() {
+                ConnectionEvent::Text(text) => texts.push(text),
+                ConnectionEvent::State(state) => states.push(state),
+                _ => {}
+            }
+        }
+        // A third connection takes over after the retry delay, well before the old one
+        // would next rotate, without the old one ever being dropped
+        assert_eq!(texts, [frame(1), frame(3)]);
+        assert!(start.elapsed() < rotate_after * 2 - Duration::from_millis(200));
+        assert_eq!(
+            states,
+            [ConnectionState::Connecting, ConnectionState::Connected]
+        );
+
+        shutdown_tx.send(true).unwrap();
+        connection.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_frames_and_shutdown_are_handled_while_a_replacement_connects() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}/", listener.local_addr().unwrap());
+        let (stalled_tx, mut stalled_rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            // The first connection keeps sending frames until it is closed
+            let (socket, _) = listener.accept().await.unwrap();
+            tokio::spawn(async move {
+                let mut ws_stream = tokio_tungstenite::accept_async(socket).await.unwrap();
+                let _ = ws_stream.next().await;
+                for id in 1.. {
+                    let frame = format!(r#"{{"frame":{}}}"#, id);
+                    if ws_stream.send(Message::Text(frame)).await.is_err() {
+                        break;
+                    }
+                    tokio::time::sleep(Duration::from_millis(5)).await;
+                }
+            });
+            // Later connections are accepted but never get a handshake answer
+            let mut stalled = Vec::new();
+            while let Ok((socket, _)) = listener.accept().await {
+                stalled.push(socket);
+                let _ = stalled_tx.send(());
+            }
+        });
+        // Without room for more than one event, frames only arrive while the loop runs
+        let (events_tx, mut events_rx) = mpsc::channel(1);
+        let (commands_tx, commands_rx) = mpsc::channel(16);
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let backoff = Backoff::new(Duration::from_millis(10), Duration::from_millis(20));
+        let connection = tokio::spawn(run_connection(
+            ConnectionConfig {
+                rotation_overlap: Duration::from_millis(500),
+                ..config(url, Duration::from_millis(100))
+            },
+            events_tx,
+            commands_rx,
+            shutdown_rx,
+            backoff,
+        ));
+
+        tokio::time::timeout(Duration::from_secs(2), async {
+            // Frames and commands keep flowing while the replacement hangs
+            let mut texts = 0;
+            let mut rotating = false;
+            while texts < 20 {
+                rotating |= stalled_rx.try_recv().is_ok();
+                if let ConnectionEvent::Text(_) = events_rx.recv().await.unwrap() {
+                    texts += usize::from(rotating);
+                }
+            }
+            let subscribe = ConnectionCommand::Subscribe(vec!["ethusdt@aggTrade".to_string()]);
+            commands_tx.send(subscribe).await.unwrap();
+            while !matches!(events_rx.recv().await.unwrap(), ConnectionEvent::Streams(_)) {}
+            // The hanging attempt times out and the rotation is retried
+            while stalled_rx.try_recv().is_err() {
+                events_rx.recv().await.unwrap();
+            }
+        })
+        .await
+        .expect("the connection stopped handling events during rotation");
+
+        // Shutdown is not held up by the replacement still connecting
+        shutdown_tx.send(true).unwrap();
+        tokio::spawn(async move { while events_rx.recv().await.is_some() {} });
+        tokio::time::timeout(Duration::from_millis(200), connection)
+            .await
+            .expect("shutdown waited for the replacement to connect")
+            .unwrap();
+    }
+
+    async fn next_request(
+        received: &mut mpsc::UnboundedReceiver<String>,
+    ) -> (serde_json::Value, serde_json::Value) {
+        let message: serde_json::Value =
+            serde_json::from_str(&received.recv().await.unwrap()).unwrap();
+        (message["method"].clone(), message["params"].clone())
+    }
+
+    async fn next_streams(events: &mut mpsc::Receiver<ConnectionEvent>) -> Vec<String> {
+        loop {
+            if let ConnectionEvent::Streams(streams) = events.recv().await.unwrap() {
+                return streams;
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_streams_change_while_connected() {
+        let (url, mut received) = spawn_ws_sessions(vec![Vec::new()], false).await;
+        let (events_tx, mut events_rx) = mpsc::channel(16);
+        let (commands_tx, commands_rx) = mpsc::channel(16);
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let backoff = Backoff::new(Duration::from_millis(10), Duration::from_millis(20));
+        let connection = tokio::spawn(run_connection(
+            config(url, Duration::from_secs(60)),
+            events_tx,
+            commands_rx,
+            shutdown_rx,
+            backoff,
+        ));
+        assert_eq!(next_request(&mut received).await.0, "SUBSCRIBE");
+
+        // Only streams that are not subscribed yet are requested
+        commands_tx
+            .send(ConnectionCommand::Subscribe(vec![
+                "btcusdt@aggTrade".to_string(),
+                "ethusdt@trade".to_string(),
+            ]))
+            .await
+            .unwrap();
+        assert_eq!(
+            next_request(&mut received).await,
+            ("SUBSCRIBE".into(), serde_json::json!(["ethusdt@trade"]))
+        );
+        assert_eq!(
+            next_streams(&mut events_rx).await,
+            ["btcusdt@aggTrade", "ethusdt@trade"]
+        );
+
+        commands_tx
+            .send(ConnectionCommand::Unsubscribe(vec![
+                "btcusdt@aggTrade".to_string()
+            ]))
+            .await
+            .unwrap();
+        assert_eq!(
+            next_request(&mut received).await,
+            (
+                "UNSUBSCRIBE".into(),
+                serde_json::json!(["btcusdt@aggTrade"])
+            )
+        );
+        assert_eq!(next_streams(&mut events_rx).await, ["ethusdt@trade"]);
+
+        // The remaining streams are unsubscribed on shutdown
+        shutdown_tx.send(true).unwrap();
+        assert_eq!(
+            next_request(&mut received).await,
+            ("UNSUBSCRIBE".into(), serde_json::json!(["ethusdt@trade"]))
+        );
+        connection.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_replies_are_matched_to_requests() {
+        let (url, _received) = spawn_ws_sessions(
+            vec![vec![
+                r#"{"result":null,"id":7}"#.to_string(),
+                r#"{"error":{"code":2,"msg":"Invalid request"},"id":1}"#.to_string(),
+            ]],
+            false,
+        )
+        .await;
+        let (events_tx, mut events_rx) = mpsc::channel(16);
+        let (_commands_tx, commands_rx) = mpsc::channel(16);
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let backoff = Backoff::new(Duration::from_millis(10), Duration::from_millis(20));
+        let connection = tokio::spawn(run_connection(
+            config(url, Duration::from_secs(60)),
+            events_tx,
+            commands_rx,
+            shutdown_rx,
+            backoff,
+        ));
+
+        // The reply to an unknown id is dropped, the error reply fails the subscription
+        let mut streams = None;
+        let reply = loop {
+            match events_rx.recv().await.unwrap() {
+                ConnectionEvent::Text(text) => panic!("reply forwarded as data: {}", text),
+                ConnectionEvent::Streams(active) => streams = Some(active),
+                ConnectionEvent::Reply { command, result } => break (command, result),
+                _ => {}
+            }
+        };
+        assert_eq!(
+            reply,
+            (
+                ConnectionCommand::Subscribe(vec!["btcusdt@aggTrade".to_string()]),
+                Err(RequestError::Rejected {
+                    code: 2,
+                    msg: "Invalid request".to_string()
+                })
+            )
+        );
+        // The refused stream is no longer part of the subscription
+        assert_eq!(streams, Some(Vec::new()));
+
+        shutdown_tx.send(true).unwrap();
+        connection.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_accepted_changes_refresh_the_subscription_list() {
+        let (url, mut received) = spawn_ws_sessions(
+            vec![vec![
+                r#"{"result":null,"id":1}"#.to_string(),
+                r#"{"result":["btcusdt@aggTrade"],"id":2}"#.to_string(),
+            ]],
+            false,
+        )
+        .await;
+        let (events_tx, mut events_rx) = mpsc::channel(16);
+        let (_commands_tx, commands_rx) = mpsc::channel(16);
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let backoff = Backoff::new(Duration::from_millis(10), Duration::from_millis(20));
+        let connection = tokio::spawn(run_connection(
+            config(url, Duration::from_secs(60)),
+            events_tx,
+            commands_rx,
+            shutdown_rx,
+            backoff,
+        ));
+
+        let mut replies = Vec::new();
+        while replies.len() < 2 {
+            if let ConnectionEvent::Reply { command, result } = events_rx.recv().await.unwrap() {
+                replies.push((command, result.unwrap()));
+            }
+        }
+        assert_eq!(
+            replies,
+            [
+                (
+                    ConnectionCommand::Subscribe(vec!["btcusdt@aggTrade".to_string()]),
+                    Value::Null
+                ),
+                (
+                    ConnectionCommand::ListSubscriptions,
+                    serde_json::json!(["btcusdt@aggTrade"])
+                ),
+            ]
+        );
+        assert_eq!(next_request(&mut received).await.0, "SUBSCRIBE");
+        assert_eq!(next_request(&mut received).await.0, "LIST_SUBSCRIPTIONS");
+
+        shutdown_tx.send(true).unwrap();
+        connection.await.unwrap();
+    }
+
+    #[test]
+    fn test_streams_are_capped_per_connection() {
+        let active: Vec<String> = (0..MAX_STREAMS_PER_CONNECTION - 2)
+            .map(|i| format!("symbol{}@trade", i))
+            .collect();
+        let requested = vec![
+            "symbol0@trade".to_string(),
+            "btcusdt@trade".to_string(),
+            "btcusdt@trade".to_string(),
+            "ethusdt@trade".to_string(),
+            "bnbusdt@trade".to_string(),
+        ];
+        let (accepted, refused) = fit_streams(&active, requested);
+        assert_eq!(
+            accepted,
+            [
+                "symbol0@trade",
+                "btcusdt@trade",
+                "btcusdt@trade",
+                "ethusdt@trade"
+            ]
+        );
+        assert_eq!(refused, ["bnbusdt@trade"]);
+    }
+}
@@ -0,0 +1,175 @@
+use serde_json::Value;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, Instant};
+
+/// Drops events already delivered by another connection to the same streams.
+/// Events are identified by stream and trade or update id; other payloads by their full text.
+pub struct Deduplicator {
+    // Connection that delivered each event first, when, and how many copies arrived since
+    seen: HashMap<u64, (usize, Instant, u32)>,
+    order: VecDeque<u64>,
+    capacity: usize,
+}
+
+/// How a copy of an event relates to the copies delivered before it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Delivery {
+    First,
+    /// The first repeat, with the connection that beat it and by how much
+    Second {
+        first: usize,
+        lead: Duration,
+    },
+    Later,
+}
+
+impl Deduplicator {
+    // Create a Deduplicator remembering the last `capacity` events
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            seen: HashMap::with_capacity(capacity),
+            order: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    // Check whether the event was seen before, remembering it if not
+    pub fn is_duplicate(&mut self, text: &str) -> bool {
+        self.deliver(text, 0, Instant::now()) != Delivery::First
+    }
+
+    // Forget every event seen so far
+    pub fn clear(&mut self) {
+        self.seen.clear();
+        self.order.clear();
+    }
+
+    // Record a copy of the event delivered by connection `source` at `now`
+    pub fn deliver(&mut self, text: &str, source: usize, now: Instant) -> Delivery {
+        let key = event_key(text);
+        if let Some((first, delivered_at, repeats)) = self.seen.get_mut(&key) {
+            *repeats += 1;
+            return match repeats {
+                1 => Delivery::Second {
+                    first: *first,
+                    lead: now.saturating_duration_since(*delivered_at),
+                },
+                _ => Delivery::Later,
+            };
+        }
+        if self.order.len() == self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+        self.seen.insert(key, (source, now, 0));
+        self.order.push_back(key);
+        Delivery::First
+    }
+}
+
+// Hash of the frame's identity, so large payloads are not kept around
+fn event_key(text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    event_identity(text).hash(&mut hasher);
+    hasher.finish()
+}
+
+// Identify a frame by its stream, event type, symbol and sequence id
+fn event_identity(text: &str) -> String {
+    let Ok(json) = serde_json::from_str::<Value>(text) else {
+        return text.to_string();
+    };
+    let stream = json.get("stream").and_then(Value::as_str).unwrap_or("");
+    let data = json.get("data").unwrap_or(&json);
+    let event_type = data.get("e").and_then(Value::as_str);
+
+    // Trades carry their id in `t`, aggregate trades in `a`, book updates in `u`
+    let id_field = match event_type {
+        Some("aggTrade") => "a",
+        Some("trade") => "t",
+        Some("depthUpdate") => "u",
+        None if data.get("lastUpdateId").is_some() => "lastUpdateId",
+        None => "u",
+        Some(_) => return text.to_string(),
+    };
+    let Some(id) = data.get(id_field).and_then(Value::as_u64) else {
+        return text.to_string();
+    };
+    let symbol = data.get("s").and_then(Value::as_str).unwrap_or("");
+    format!(
+        "{}|{}|{}|{}={}",
+        stream,
+        event_type.unwrap_or(""),
+        symbol,
+        id_field,
+        id
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duplicates_are_matched_by_id() {
+        let mut dedupe = Deduplicator::new(10);
+        let agg_trade = |id: u64, price: &str| {
+            format!(
+                r#"{{"stream":"btcusdt@aggTrade","data":{{"e":"aggTrade","E":1,"s":"BTCUSDT","a":{},"p":"{}"}}}}"#,
+                id, price
+            )
+        };
+        assert!(!dedupe.is_duplicate(&agg_trade(1, "1.0")));
+        assert!(!dedupe.is_duplicate(&agg_trade(2, "1.0")));
+        // Same id from the other connection, even if serialized differently
+        assert!(dedupe.is_duplicate(&agg_trade(1, "1.00")));
+
+        // Trades are keyed by `t`, not by the seller order id in `a`
+        assert!(!dedupe.is_duplicate(r#"{"e":"trade","s":"BTCUSDT","t":7,"a":5}"#));
+        assert!(!dedupe.is_duplicate(r#"{"e":"trade","s":"BTCUSDT","t":8,"a":5}"#));
+        assert!(dedupe.is_duplicate(r#"{"e":"trade","s":"BTCUSDT","t":8,"a":5}"#));
+
+        // Payloads without an id fall back to their text
+        let kline = r#"{"e":"kline","E":1,"s":"BTCUSDT","k":{}}"#;
+        assert!(!dedupe.is_duplicate(kline));
+        assert!(dedupe.is_duplicate(kline));
+    }
+
+    #[test]
+    fn test_first_delivery_and_lead_are_reported() {
+        let mut dedupe = Deduplicator::new(10);
+        let start = Instant::now();
+        let trade = r#"{"e":"trade","s":"BTCUSDT","t":1}"#;
+        assert_eq!(dedupe.deliver(trade, 1, start), Delivery::First);
+        assert_eq!(
+            dedupe.deliver(trade, 0, start + Duration::from_millis(3)),
+            Delivery::Second {
+                first: 1,
+                lead: Duration::from_millis(3)
+            }
+        );
+        assert_eq!(
+            dedupe.deliver(trade, 2, start + Duration::from_millis(5)),
+            Delivery::Later
+        );
+    }
+
+    #[test]
+    fn test_oldest_events_are_forgotten() {
+        let mut dedupe = Deduplicator::new(2);
+        let depth = |id: u64| {
+            format!(
+                r#"{{"e":"depthUpdate","s":"BTCUSDT","U":{},"u":{}}}"#,
+                id, id
+            )
+        };
+        assert!(!dedupe.is_duplicate(&depth(1)));
+        assert!(!dedupe.is_duplicate(&depth(2)));
+        assert!(!dedupe.is_duplicate(&depth(3)));
+        assert!(!dedupe.is_duplicate(&depth(1)));
+        assert!(dedupe.is_duplicate(&depth(3)));
+    }
+}
@@ -1,6 +1,10 @@
+pub mod backoff;
+pub mod connection;
+pub mod dedupe;
+pub mod pool;
+pub mod rate_limit;
+pub mod redundant;
+pub mod requests;
 pub mod run;
-pub mod subscribe;
-pub mod unsubscribe;
-
-pub const BINANCE_WS_URL: &str = "wss://stream.binance.com:9443/ws/";
-pub const BINANCE_WS_COMBINED_URL: &str = "wss://stream.binance.com:9443/stream?streams=";
+pub mod writer;
+pub mod ws_api;
@@ -0,0 +1,604 @@
+use crate::websocket::client::backoff::Backoff;
+use crate::websocket::client::connection::{
+    run_connection, ConnectionCommand, ConnectionConfig, ConnectionEvent, ConnectionState,
+    MAX_STREAMS_PER_CONNECTION,
+};
+use crate::websocket::client::requests::RequestError;
+use serde_json::Value;
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
+
+// Streams a pooled connection should carry, well below the hard per-connection limit
+const STREAMS_PER_CONNECTION: usize = 200;
+// Expected messages per second a pooled connection should carry
+const RATE_PER_CONNECTION: f64 = 500.0;
+
+/// Settings for a pool of connections sharing one subscription set
+#[derive(Debug, Clone)]
+pub struct PoolConfig {
+    /// Settings shared by every connection; its `streams` are ignored
+    pub connection: ConnectionConfig,
+    pub streams: Vec<String>,
+    /// Connections opened up front and kept open
+    pub connections: usize,
+    /// Most streams placed on a single connection
+    pub max_streams: usize,
+    pub backoff: Backoff,
+}
+
+/// Rough number of messages per second a stream delivers, used to balance connections
+pub fn expected_rate(stream: &str) -> f64 {
+    let kind = stream.split_once('@').map_or(stream, |(_, kind)| kind);
+    match kind {
+        "bookTicker" => 10.0,
+        "aggTrade" | "trade" => 5.0,
+        kind if kind.starts_with("depth") && kind.ends_with("100ms") => 10.0,
+        // Partial and diff depth every second, klines every two, tickers every second
+        _ => 1.0,
+    }
+}
+
+/// Number of connections needed to spread `streams` by both count and expected rate
+pub fn connections_needed(streams: &[String]) -> usize {
+    let total_rate: f64 = streams.iter().map(|stream| expected_rate(stream)).sum();
+    let by_count = streams.len().div_ceil(STREAMS_PER_CONNECTION);
+    let by_rate = (total_rate / RATE_PER_CONNECTION).ceil() as usize;
+    by_count.max(by_rate).max(1)
+}
+
+// One connection of the pool with the streams placed on it
+struct Shard {
+    id: usize,
+    streams: Vec<String>,
+    load: f64,
+    state: ConnectionState,
+    confirmed: Option<Vec<String>>,
+    commands: mpsc::Sender<ConnectionCommand>,
+    shutdown: watch::Sender<bool>,
+    handle: JoinHandle<()>,
+}
+
+impl Shard {
+    // Add streams to the shard's books; the caller sends the command
+    fn assign(&mut self, streams: &[String]) {
+        self.load += streams
+            .iter()
+            .map(|stream| expected_rate(stream))
+            .sum::<f64>();
+        self.streams.extend(streams.iter().cloned());
+    }
+
+    // Remove streams from the shard's books, returning those it carried
+    fn release(&mut self, streams: &[String]) -> Vec<String> {
+        let released: Vec<String> = self
+            .streams
+            .iter()
+            .filter(|stream| streams.contains(stream))
+            .cloned()
+            .collect();
+        self.streams.retain(|stream| !released.contains(stream));
+        self.load -= released
+            .iter()
+            .map(|stream| expected_rate(stream))
+            .sum::<f64>();
+        released
+    }
+
+    // Stop the connection and wait for it to unsubscribe and close
+    async fn stop(self) {
+        let _ = self.shutdown.send(true);
+        let _ = self.handle.await;
+    }
+}
+
+// Choose the least loaded shard with room for another stream
+fn least_loaded(loads: &[(usize, f64)], max_streams: usize) -> Option<usize> {
+    loads
+        .iter()
+        .enumerate()
+        .filter(|(_, (count, _))| *count < max_streams)
+        .min_by(|(_, (_, a)), (_, (_, b))| a.total_cmp(b))
+        .map(|(index, _)| index)
+}
+
+/// Spreads `streams` over `shards` connections, busiest streams first, each going to the
+/// least loaded connection with room. Streams that fit nowhere are returned separately.
+fn distribute(
+    streams: &[String],
+    shards: usize,
+    max_streams: usize,
+) -> (Vec<Vec<String>>, Vec<String>) {
+    let mut ordered: Vec<&String> = streams.iter().collect();
+    ordered.sort_by(|a, b| expected_rate(b).total_cmp(&expected_rate(a)));
+
+    let mut placed = vec![Vec::new(); shards];
+    let mut loads = vec![(0, 0.0); shards];
+    let mut unplaced = Vec::new();
+    for stream in ordered {
+        match least_loaded(&loads, max_streams) {
+            Some(index) => {
+                placed[index].push(stream.clone());
+                loads[index].0 += 1;
+                loads[index].1 += expected_rate(stream);
+            }
+            None => unplaced.push(stream.clone()),
+        }
+    }
+    (placed, unplaced)
+}
+
+/// Runs several connections as one: streams are spread across them by count and expected
+/// message rate, their events are merged into `events`, new streams go to the least loaded
+/// connection, and the streams of a connection that drops move to the ones still connected.
+pub async fn run_pool(
+    config: PoolConfig,
+    events: mpsc::Sender<ConnectionEvent>,
+    mut commands: mpsc::Receiver<ConnectionCommand>,
+    mut shutdown_rx: watch::Receiver<bool>,
+) {
+    let max_streams = config.max_streams.min(MAX_STREAMS_PER_CONNECTION);
+    let (merged_tx, mut merged_rx) = mpsc::channel(1024);
+    let mut next_id = 0;
+    let mut spawn = |streams: Vec<String>| {
+        let shard = spawn_shard(next_id, &config, streams, &merged_tx);
+        next_id += 1;
+        shard
+    };
+
+    // Start with enough connections for the initial streams
+    let shard_count = config
+        .connections
+        .max(config.streams.len().div_ceil(max_streams))
+        .max(1);
+    let (placed, _) = distribute(&config.streams, shard_count, max_streams);
+    let mut shards: Vec<Shard> = placed.into_iter().map(&mut spawn).collect();
+    let mut pool_state = ConnectionState::Connecting;
+    let _ = events
+        .send(ConnectionEvent::State(pool_state.clone()))
+        .await;
+
+    loop {
+        tokio::select! {
+            Some((id, event)) = merged_rx.recv() => {
+                let event = match event {
+                    ConnectionEvent::State(state) => {
+                        let Some(index) = shards.iter().position(|shard| shard.id == id) else {
+                            continue;
+                        };
+                        shards[index].state = state.clone();
+                        if matches!(state, ConnectionState::Reconnecting { .. }) {
+                            move_streams_off(&mut shards, index, max_streams, &mut spawn).await;
+                        }
+                        let state = combined_state(&shards);
+                        if state == pool_state {
+                            continue;
+                        }
+                        pool_state = state.clone();
+                        ConnectionEvent::State(state)
+                    }
+                    // A connection's own list may predate streams placed on it since, so the
+                    // pool's books are only changed by refusals
+                    ConnectionEvent::Streams(_) => ConnectionEvent::Streams(pool_streams(&shards)),
+                    // A connection dropped streams the server refused or that did not fit
+                    ConnectionEvent::Reply {
+                        command: ConnectionCommand::Subscribe(refused),
+                        result: Err(error @ (RequestError::Rejected { .. } | RequestError::StreamLimit { .. })),
+                    } => {
+                        if let Some(shard) = shards.iter_mut().find(|shard| shard.id == id) {
+                            shard.release(&refused);
+                        }
+                        if events.send(ConnectionEvent::Streams(pool_streams(&shards))).await.is_err() {
+                            break;
+                        }
+                        ConnectionEvent::Reply {
+                            command: ConnectionCommand::Subscribe(refused),
+                            result: Err(error),
+                        }
+                    }
+                    // Each connection lists its own streams, the consumer wants all of them
+                    ConnectionEvent::Reply { command: ConnectionCommand::ListSubscriptions, result: Ok(list) } => {
+                        if let Some(shard) = shards.iter_mut().find(|shard| shard.id == id) {
+                            shard.confirmed = serde_json::from_value(list).ok();
+                        }
+                        let confirmed: Vec<String> = shards
+                            .iter()
+                            .filter_map(|shard| shard.confirmed.as_ref())
+                            .flatten()
+                            .cloned()
+                            .collect();
+                        ConnectionEvent::Reply {
+                            command: ConnectionCommand::ListSubscriptions,
+                            result: Ok(Value::from(confirmed)),
+                        }
+                    }
+                    event => event,
+                };
+                if events.send(event).await.is_err() {
+                    break;
+                }
+            }
+            Some(command) = commands.recv() => {
+                match command {
+                    ConnectionCommand::Subscribe(requested) => {
+                        let active = pool_streams(&shards);
+                        let mut added: Vec<String> = Vec::new();
+                        for stream in requested {
+                            if !active.contains(&stream) && !added.contains(&stream) {
+                                added.push(stream);
+                            }
+                        }
+                        place_streams(&mut shards, added, max_streams, &mut spawn).await;
+                    }
+                    ConnectionCommand::Unsubscribe(requested) => {
+                        for shard in &mut shards {
+                            let released = shard.release(&requested);
+                            if !released.is_empty() {
+                                let _ = shard.commands.send(ConnectionCommand::Unsubscribe(released)).await;
+                            }
+                        }
+                    }
+                    // Properties apply to every connection, a single one answers queries
+                    ConnectionCommand::SetCombined(combined) => {
+                        for shard in &shards {
+                            let _ = shard.commands.send(ConnectionCommand::SetCombined(combined)).await;
+                        }
+                        continue;
+                    }
+                    ConnectionCommand::ListSubscriptions => {
+                        for shard in &shards {
+                            let _ = shard.commands.send(ConnectionCommand::ListSubscriptions).await;
+                        }
+                        continue;
+                    }
+                    query => {
+                        if let Some(shard) = shards.first() {
+                            let _ = shard.commands.send(query).await;
+                        }
+                        continue;
+                    }
+                }
+                if events.send(ConnectionEvent::Streams(pool_streams(&shards))).await.is_err() {
+                    break;
+                }
+            }
+            _ = shutdown_rx.changed() => break,
+        }
+    }
+
+    for shard in shards {
+        shard.stop().await;
+    }
+    let _ = events
+        .send(ConnectionEvent::State(ConnectionState::Closed))
+        .await;
+}
+
+// Start a connection for `streams`, forwarding its events tagged with the shard id
+fn spawn_shard(
+    id: usize,
+    config: &PoolConfig,
+    streams: Vec<String>,
+    merged: &mpsc::Sender<(usize, ConnectionEvent)>,
+) -> Shard {
+    let (events_tx, mut events_rx) = mpsc::channel(1024);
+    let (commands_tx, commands_rx) = mpsc::channel(16);
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    let connection = ConnectionConfig {
+        streams: streams.clone(),
+        ..config.connection.clone()
+    };
+    let handle = tokio::spawn(run_connection(
+        connection,
+        events_tx,
+        commands_rx,
+        shutdown_rx,
+        config.backoff.clone(),
+    ));
+
+    let merged = merged.clone();
+    tokio::spawn(async move {
+        while let Some(event) = events_rx.recv().await {
+            if merged.send((id, event)).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut shard = Shard {
+        id,
+        streams: Vec::new(),
+        load: 0.0,
+        state: ConnectionState::Connecting,
+        confirmed: None,
+        commands: commands_tx,
+        shutdown: shutdown_tx,
+        handle,
+    };
+    shard.assign(&streams);
+    shard
+}
+
+// Put new streams on the least loaded connections, opening another when all are full
+async fn place_streams(
+    shards: &mut Vec<Shard>,
+    streams: Vec<String>,
+    max_streams: usize,
+    spawn: &mut impl FnMut(Vec<String>) -> Shard,
+) {
+    let mut batches: Vec<Vec<String>> = vec![Vec::new(); shards.len()];
+    let mut overflow = Vec::new();
+    for stream in streams {
+        // Connections that are reconnecting only get streams when nothing else has room
+        let loads: Vec<(usize, f64)> = shards
+            .iter()
+            .zip(&batches)
+            .map(|(shard, batch)| {
+                let load = shard.load + batch.iter().map(|s| expected_rate(s)).sum::<f64>();
+                let penalty = match shard.state {
+                    ConnectionState::Reconnecting { .. } => f64::MAX / 2.0,
+                    _ => 0.0,
+                };
+                (shard.streams.len() + batch.len(), load + penalty)
+            })
+            .collect();
+        match least_loaded(&loads, max_streams) {
+            Some(index) => batches[index].push(stream),
+            None => overflow.push(stream),
+        }
+    }
+
+    for (shard, batch) in shards.iter_mut().zip(batches) {
+        if !batch.is_empty() {
+            shard.assign(&batch);
+            let _ = shard
+                .commands
+                .send(ConnectionCommand::Subscribe(batch))
+                .await;
+        }
+    }
+    for chunk in overflow.chunks(max_streams) {
+        shards.push(spawn(chunk.to_vec()));
+    }
+}
+
+// Move the streams of a dropped connection to the others, replacing it with an empty one.
+// While no connection is up there is nowhere better to go, so the streams stay put.
+async fn move_streams_off(
+    shards: &mut Vec<Shard>,
+    index: usize,
+    max_streams: usize,
+    spawn: &mut impl FnMut(Vec<String>) -> Shard,
+) {
+    let any_connected = shards
+        .iter()
+        .any(|shard| shard.state == ConnectionState::Connected);
+    if !any_connected {
+        return;
+    }
+    let dropped = shards.remove(index);
+    let streams = dropped.streams.clone();
+    tokio::spawn(dropped.stop());
+
+    shards.push(spawn(Vec::new()));
+    place_streams(shards, streams, max_streams, spawn).await;
+}
+
+// All streams carried by the pool
+fn pool_streams(shards: &[Shard]) -> Vec<String> {
+    shards
+        .iter()
+        .flat_map(|shard| shard.streams.iter().cloned())
+        .collect()
+}
+
+// The pool is connected once every connection is, and otherwise shows the least healthy one
+fn combined_state(shards: &[Shard]) -> ConnectionState {
+    let states = || shards.iter().map(|shard| &shard.state);
+    if let Some(reconnecting) =
+        states().find(|state| matches!(state, ConnectionState::Reconnecting { .. }))
+    {
+        return reconnecting.clone();
+    }
+    if states().any(|state| *state == ConnectionState::Connecting) {
+        return ConnectionState::Connecting;
+    }
+    if states().all(|state| *state == ConnectionState::Closed) {
+        return ConnectionState::Closed;
+    }
+    ConnectionState::Connected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchange::binance::Binance;
+    use crate::test_support::spawn_ws_sessions;
+    use std::collections::HashSet;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    fn streams(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_streams_are_spread_by_rate_and_count() {
+        let (placed, unplaced) = distribute(
+            &streams(&[
+                "btcusdt@kline_1m",
+                "btcusdt@aggTrade",
+                "btcusdt@depth@100ms",
+                "ethusdt@aggTrade",
+            ]),
+            2,
+            10,
+        );
+        // The busy depth stream gets a connection of its own apart from a quiet kline
+        assert_eq!(
+            placed,
+            [
+                streams(&["btcusdt@depth@100ms", "btcusdt@kline_1m"]),
+                streams(&["btcusdt@aggTrade", "ethusdt@aggTrade"]),
+            ]
+        );
+        assert!(unplaced.is_empty());
+
+        // Connections never take more than their share of streams
+        let (placed, unplaced) = distribute(&streams(&["a@trade", "b@trade", "c@trade"]), 1, 2);
+        assert_eq!(placed[0].len(), 2);
+        assert_eq!(unplaced, ["c@trade"]);
+
+        let many: Vec<String> = (0..450).map(|i| format!("s{}@kline_1m", i)).collect();
+        assert_eq!(connections_needed(&many), 3);
+        let busy: Vec<String> = (0..150).map(|i| format!("s{}@bookTicker", i)).collect();
+        assert_eq!(connections_needed(&busy), 3);
+    }
+
+    #[tokio::test]
+    async fn test_pool_merges_connections_and_places_new_streams() {
+        let (url, mut received) = spawn_ws_sessions(
+            vec![
+                vec![r#"{"from":"first"}"#.to_string()],
+                vec![r#"{"from":"second"}"#.to_string()],
+            ],
+            false,
+        )
+        .await;
+        let config = PoolConfig {
+            connection: ConnectionConfig {
+                url,
+                streams: Vec::new(),
+                base_id: 1,
+                rotate_after: Duration::from_secs(60),
+                rotation_overlap: Duration::from_secs(1),
+                rotation_retry: Duration::from_secs(30),
+                silence_timeout: Duration::from_secs(60),
+                request_timeout: Duration::from_secs(10),
+                combined: None,
+                exchange: Arc::new(Binance::default()),
+            },
+            streams: streams(&[
+                "btcusdt@depth@100ms",
+                "btcusdt@aggTrade",
+                "ethusdt@aggTrade",
+            ]),
+            connections: 2,
+            max_streams: 10,
+            backoff: Backoff::new(Duration::from_millis(10), Duration::from_millis(20)),
+        };
+        let (events_tx, mut events_rx) = mpsc::channel(16);
+        let (commands_tx, commands_rx) = mpsc::channel(16);
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let pool = tokio::spawn(run_pool(config, events_tx, commands_rx, shutdown_rx));
+
+        // Each connection subscribes to its share, and both feed the one event stream
+        let mut subscriptions = HashSet::new();
+        for _ in 0..2 {
+            let message: Value = serde_json::from_str(&received.recv().await.unwrap()).unwrap();
+            assert_eq!(message["method"], "SUBSCRIBE");
+            subscriptions.insert(message["params"].to_string());
+        }
+        assert_eq!(
+            subscriptions,
+            HashSet::from([
+                r#"["btcusdt@depth@100ms"]"#.to_string(),
+                r#"["btcusdt@aggTrade","ethusdt@aggTrade"]"#.to_string(),
+            ])
+        );
+        let mut texts = HashSet::new();
+        while texts.len() < 2 {
+            if let ConnectionEvent::Text(text) = events_rx.recv().await.unwrap() {
+                texts.insert(text);
+            }
+        }
+
+        // A new stream goes to the connection with the lighter load
+        commands_tx
+            .send(ConnectionCommand::Subscribe(streams(&["bnbusdt@kline_1m"])))
+            .await
+            .unwrap();
+        let message: Value = serde_json::from_str(&received.recv().await.unwrap()).unwrap();
+        assert_eq!(message["params"], serde_json::json!(["bnbusdt@kline_1m"]));
+        let active = loop {
+            if let ConnectionEvent::Streams(active) = events_rx.recv().await.unwrap() {
+                break active;
+            }
+        };
+        assert_eq!(active.len(), 4);
+
+        shutdown_tx.send(true).unwrap();
+        pool.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_pool_releases_only_refused_streams() {
+        // The server refuses the first subscription
+        let (url, mut received) = spawn_ws_sessions(
+            vec![vec![
+                r#"{"error":{"code":2,"msg":"Invalid request"},"id":1}"#.to_string(),
+            ]],
+            false,
+        )
+        .await;
+        let config = PoolConfig {
+            connection: ConnectionConfig {
+                url,
+                streams: Vec::new(),
+                base_id: 1,
+                rotate_after: Duration::from_secs(60),
+                rotation_overlap: Duration::from_secs(1),
+                rotation_retry: Duration::from_secs(30),
+                silence_timeout: Duration::from_secs(60),
+                request_timeout: Duration::from_secs(10),
+                combined: None,
+                exchange: Arc::new(Binance::default()),
+            },
+            streams: streams(&["bad@trade"]),
+            connections: 1,
+            max_streams: 10,
+            backoff: Backoff::new(Duration::from_millis(10), Duration::from_millis(20)),
+        };
+        let (events_tx, mut events_rx) = mpsc::channel(64);
+        let (commands_tx, commands_rx) = mpsc::channel(16);
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let pool = tokio::spawn(run_pool(config, events_tx, commands_rx, shutdown_rx));
+
+        let refused = loop {
+            if let ConnectionEvent::Streams(active) = events_rx.recv().await.unwrap() {
+                if active.is_empty() {
+                    break active;
+                }
+            }
+        };
+        assert!(refused.is_empty());
+        received.recv().await.unwrap();
+
+        // Streams placed back to back stay placed while older connection lists arrive
+        for stream in ["a@trade", "b@trade"] {
+            let subscribe = ConnectionCommand::Subscribe(streams(&[stream]));
+            commands_tx.send(subscribe).await.unwrap();
+        }
+        for _ in 0..2 {
+            received.recv().await.unwrap();
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        commands_tx
+            .send(ConnectionCommand::Subscribe(streams(&["c@trade"])))
+            .await
+            .unwrap();
+        received.recv().await.unwrap();
+        let mut active = Vec::new();
+        while let Ok(Some(event)) =
+            tokio::time::timeout(Duration::from_millis(100), events_rx.recv()).await
+        {
+            if let ConnectionEvent::Streams(streams) = event {
+                active = streams;
+            }
+        }
+        active.sort();
+        assert_eq!(active, ["a@trade", "b@trade", "c@trade"]);
+
+        shutdown_tx.send(true).unwrap();
+        pool.await.unwrap();
+    }
+}
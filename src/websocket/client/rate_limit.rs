@@ -0,0 +1,61 @@
+use std::time::{Duration, Instant};
+
+/// Token bucket spacing out messages sent to the server.
+/// Each message takes a token; when none is left the sender waits for the next refill.
+pub struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    refill_per_second: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    // Create a full bucket holding `capacity` tokens, refilled at `refill_per_second`
+    pub fn new(capacity: u32, refill_per_second: f64, now: Instant) -> Self {
+        Self {
+            capacity: capacity as f64,
+            tokens: capacity as f64,
+            refill_per_second,
+            last_refill: now,
+        }
+    }
+
+    // Take a token, returning how long to wait before sending.
+    // Tokens taken while the bucket is empty are owed, so later senders wait longer.
+    pub fn acquire(&mut self, now: Instant) -> Duration {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_second).min(self.capacity);
+        self.last_refill = now;
+
+        self.tokens -= 1.0;
+        if self.tokens >= 0.0 {
+            return Duration::ZERO;
+        }
+        Duration::from_secs_f64(-self.tokens / self.refill_per_second)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_spaces_out_bursts() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(2, 4.0, start);
+
+        // The burst goes out at once, then one message every 250ms
+        assert_eq!(bucket.acquire(start), Duration::ZERO);
+        assert_eq!(bucket.acquire(start), Duration::ZERO);
+        assert_eq!(bucket.acquire(start), Duration::from_millis(250));
+        assert_eq!(bucket.acquire(start), Duration::from_millis(500));
+
+        // After a quiet spell the bucket is full again, but never above its capacity
+        let later = start + Duration::from_secs(5);
+        assert_eq!(bucket.acquire(later), Duration::ZERO);
+        assert_eq!(bucket.acquire(later), Duration::ZERO);
+        assert_eq!(bucket.acquire(later), Duration::from_millis(250));
+    }
+}
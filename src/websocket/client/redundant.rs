@@ -0,0 +1,268 @@
+use crate::websocket::client::backoff::Backoff;
+use crate::websocket::client::connection::{
+    run_connection, ConnectionCommand, ConnectionConfig, ConnectionEvent, ConnectionState,
+};
+use crate::websocket::client::dedupe::{Deduplicator, Delivery};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
+
+// Events remembered to drop the copies the other connections deliver
+const DEDUPE_CAPACITY: usize = 100_000;
+// How often the delivery statistics are reported
+const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Settings for several connections carrying the same streams
+#[derive(Debug, Clone)]
+pub struct RedundantConfig {
+    pub connection: ConnectionConfig,
+    /// Connections opened to the same streams
+    pub copies: usize,
+    pub backoff: Backoff,
+}
+
+/// How often a connection delivered an event before the others
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DeliveryStats {
+    /// Events this connection delivered first
+    pub first: u64,
+    /// Of those, events another connection delivered as well
+    pub matched: u64,
+    /// Total time by which the matched events beat the next copy
+    pub lead: Duration,
+}
+
+impl DeliveryStats {
+    // Average time by which this connection beat the next copy
+    pub fn average_lead(&self) -> Duration {
+        match self.matched {
+            0 => Duration::ZERO,
+            matched => self.lead / matched as u32,
+        }
+    }
+}
+
+// One of the redundant connections
+struct Replica {
+    state: ConnectionState,
+    commands: mpsc::Sender<ConnectionCommand>,
+    shutdown: watch::Sender<bool>,
+    handle: JoinHandle<()>,
+}
+
+/// Runs `copies` connections to the same streams as one hot-standby group, so a
+/// reconnect on one of them loses nothing. Each trade, aggregate trade and depth
+/// update is passed on once, from whichever connection delivered it first, and
+/// `Deliveries` events report how often each connection was first and by how much.
+pub async fn run_redundant(
+    config: RedundantConfig,
+    events: mpsc::Sender<ConnectionEvent>,
+    mut commands: mpsc::Receiver<ConnectionCommand>,
+    mut shutdown_rx: watch::Receiver<bool>,
+) {
+    let (merged_tx, mut merged_rx) = mpsc::channel(1024);
+    let mut copies: Vec<Replica> = (0..config.copies.max(1))
+        .map(|id| spawn_replica(id, &config, &merged_tx))
+        .collect();
+    drop(merged_tx);
+
+    let mut dedupe = Deduplicator::new(DEDUPE_CAPACITY);
+    let mut stats = vec![DeliveryStats::default(); copies.len()];
+    let mut reported = stats.clone();
+    let mut report = tokio::time::interval(REPORT_INTERVAL);
+    let mut group_state = ConnectionState::Connecting;
+    let _ = events
+        .send(ConnectionEvent::State(group_state.clone()))
+        .await;
+
+    loop {
+        tokio::select! {
+            Some((id, event)) = merged_rx.recv() => {
+                let event = match event {
+                    ConnectionEvent::Text(text) => {
+                        match dedupe.deliver(&text, id, Instant::now()) {
+                            Delivery::First => stats[id].first += 1,
+                            Delivery::Second { first, lead } => {
+                                stats[first].matched += 1;
+                                stats[first].lead += lead;
+                                continue;
+                            }
+                            Delivery::Later => continue,
+                        }
+                        ConnectionEvent::Text(text)
+                    }
+                    ConnectionEvent::State(state) => {
+                        copies[id].state = state;
+                        let state = group_state_of(&copies);
+                        if state == group_state {
+                            continue;
+                        }
+                        group_state = state.clone();
+                        ConnectionEvent::State(state)
+                    }
+                    // Every connection answers every request, pass on one answer
+                    event => {
+                        if id != primary(&copies) {
+                            continue;
+                        }
+                        event
+                    }
+                };
+                if events.send(event).await.is_err() {
+                    break;
+                }
+            }
+            Some(command) = commands.recv() => {
+                for copy in &copies {
+                    let _ = copy.commands.send(command.clone()).await;
+                }
+            }
+            _ = report.tick() => {
+                if stats != reported {
+                    reported = stats.clone();
+                    if events.send(ConnectionEvent::Deliveries(stats.clone())).await.is_err() {
+                        break;
+                    }
+                }
+            }
+            _ = shutdown_rx.changed() => break,
+        }
+    }
+
+    for copy in &copies {
+        let _ = copy.shutdown.send(true);
+    }
+    for copy in copies.drain(..) {
+        let _ = copy.handle.await;
+    }
+    let _ = events
+        .send(ConnectionEvent::State(ConnectionState::Closed))
+        .await;
+}
+
+// Start a connection, forwarding its events tagged with its index
+fn spawn_replica(
+    id: usize,
+    config: &RedundantConfig,
+    merged: &mpsc::Sender<(usize, ConnectionEvent)>,
+) -> Replica {
+    let (events_tx, mut events_rx) = mpsc::channel(1024);
+    let (commands_tx, commands_rx) = mpsc::channel(16);
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    let handle = tokio::spawn(run_connection(
+        config.connection.clone(),
+        events_tx,
+        commands_rx,
+        shutdown_rx,
+        config.backoff.clone(),
+    ));
+
+    let merged = merged.clone();
+    tokio::spawn(async move {
+        while let Some(event) = events_rx.recv().await {
+            if merged.send((id, event)).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    Replica {
+        state: ConnectionState::Connecting,
+        commands: commands_tx,
+        shutdown: shutdown_tx,
+        handle,
+    }
+}
+
+// The connection whose replies and stream lists are passed on: the first one connected
+fn primary(copies: &[Replica]) -> usize {
+    copies
+        .iter()
+        .position(|copy| copy.state == ConnectionState::Connected)
+        .unwrap_or(0)
+}
+
+// The group is connected while any connection is, so a standby hides a reconnect
+fn group_state_of(copies: &[Replica]) -> ConnectionState {
+    let states = || copies.iter().map(|copy| &copy.state);
+    if states().any(|state| *state == ConnectionState::Connected) {
+        return ConnectionState::Connected;
+    }
+    if let Some(reconnecting) =
+        states().find(|state| matches!(state, ConnectionState::Reconnecting { .. }))
+    {
+        return reconnecting.clone();
+    }
+    if states().all(|state| *state == ConnectionState::Closed) {
+        return ConnectionState::Closed;
+    }
+    ConnectionState::Connecting
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchange::binance::Binance;
+    use crate::test_support::spawn_ws_sessions;
+    use std::collections::HashSet;
+    use std::sync::Arc;
+
+    fn agg_trade(id: u64) -> String {
+        format!(
+            r#"{{"stream":"btcusdt@aggTrade","data":{{"e":"aggTrade","s":"BTCUSDT","a":{}}}}}"#,
+            id
+        )
+    }
+
+    #[tokio::test]
+    async fn test_copies_are_merged_keeping_the_first_delivery() {
+        let (url, _received) = spawn_ws_sessions(
+            vec![
+                vec![agg_trade(1), agg_trade(2)],
+                vec![agg_trade(2), agg_trade(3)],
+            ],
+            false,
+        )
+        .await;
+        let config = RedundantConfig {
+            connection: ConnectionConfig {
+                url,
+                streams: vec!["btcusdt@aggTrade".to_string()],
+                base_id: 1,
+                rotate_after: Duration::from_secs(60),
+                rotation_overlap: Duration::from_secs(1),
+                rotation_retry: Duration::from_secs(30),
+                silence_timeout: Duration::from_secs(60),
+                request_timeout: Duration::from_secs(10),
+                combined: None,
+                exchange: Arc::new(Binance::default()),
+            },
+            copies: 2,
+            backoff: Backoff::new(Duration::from_millis(10), Duration::from_millis(20)),
+        };
+        let (events_tx, mut events_rx) = mpsc::channel(16);
+        let (_commands_tx, commands_rx) = mpsc::channel(16);
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let group = tokio::spawn(run_redundant(config, events_tx, commands_rx, shutdown_rx));
+
+        // Every trade arrives once, whichever connection had it
+        let mut texts = Vec::new();
+        let deliveries = loop {
+            match events_rx.recv().await.unwrap() {
+                ConnectionEvent::Text(text) => texts.push(text),
+                ConnectionEvent::Deliveries(deliveries) if texts.len() == 3 => break deliveries,
+                _ => {}
+            }
+        };
+        assert_eq!(
+            texts.into_iter().collect::<HashSet<_>>(),
+            HashSet::from([agg_trade(1), agg_trade(2), agg_trade(3)])
+        );
+        assert_eq!(deliveries.len(), 2);
+        assert_eq!(deliveries.iter().map(|stats| stats.first).sum::<u64>(), 3);
+        assert_eq!(deliveries.iter().map(|stats| stats.matched).sum::<u64>(), 1);
+
+        shutdown_tx.send(true).unwrap();
+        group.await.unwrap();
+    }
+}
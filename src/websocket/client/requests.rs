@@ -0,0 +1,228 @@
+use crate::websocket::client::connection::ConnectionCommand;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Why a request sent on the connection did not succeed
+#[derive(Debug, Clone, PartialEq)]
+pub enum RequestError {
+    /// The server answered with an error reply
+    Rejected { code: i64, msg: String },
+    /// No reply arrived within the request timeout
+    TimedOut,
+    /// The connection ended before the reply arrived
+    Disconnected,
+    /// The streams did not fit within the per-connection limit and were not requested
+    StreamLimit { limit: usize },
+    /// The reply did not have the expected shape
+    InvalidReply(String),
+    /// The venue, named here, has no such request
+    Unsupported(&'static str),
+}
+
+impl fmt::Display for RequestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RequestError::Rejected { code, msg } => write!(f, "{} (code {})", msg, code),
+            RequestError::TimedOut => write!(f, "timed out"),
+            RequestError::Disconnected => write!(f, "connection lost"),
+            RequestError::StreamLimit { limit } => {
+                write!(f, "over the limit of {} streams per connection", limit)
+            }
+            RequestError::InvalidReply(reason) => write!(f, "unexpected reply: {}", reason),
+            RequestError::Unsupported(venue) => write!(f, "not supported by {}", venue),
+        }
+    }
+}
+
+impl std::error::Error for RequestError {}
+
+#[derive(Deserialize)]
+struct ErrorReply {
+    code: i64,
+    msg: String,
+}
+
+#[derive(Deserialize)]
+struct Reply {
+    id: u64,
+    #[serde(default)]
+    result: Option<Value>,
+    #[serde(default)]
+    error: Option<ErrorReply>,
+}
+
+/// Decodes a reply to a control request, e.g. `{"result":null,"id":1}`.
+/// Returns `None` for market data and anything else without a numeric `id`.
+pub fn parse_reply(text: &str) -> Option<(u64, Result<Value, RequestError>)> {
+    // Market data never carries a top-level `id`, so skip the full decode for it
+    if !text.contains("\"id\"") {
+        return None;
+    }
+    let reply: Reply = serde_json::from_str(text).ok()?;
+    let result = match reply.error {
+        Some(error) => Err(RequestError::Rejected {
+            code: error.code,
+            msg: error.msg,
+        }),
+        None => Ok(reply.result.unwrap_or(Value::Null)),
+    };
+    Some((reply.id, result))
+}
+
+// A request waiting for its reply
+struct PendingRequest {
+    command: ConnectionCommand,
+    sent_at: Instant,
+}
+
+/// Hands out request ids and matches replies to the requests that are still waiting
+pub struct PendingRequests {
+    next_id: u64,
+    pending: HashMap<u64, PendingRequest>,
+}
+
+impl PendingRequests {
+    // Create an empty table handing out ids from `first_id`
+    pub fn new(first_id: u64) -> Self {
+        Self {
+            next_id: first_id,
+            pending: HashMap::new(),
+        }
+    }
+
+    // Allocate an id for a request
+    pub fn next_id(&mut self) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    // Wait for the reply to a request sent under an id from `next_id`
+    pub fn track(&mut self, id: u64, command: ConnectionCommand, now: Instant) {
+        self.pending.insert(
+            id,
+            PendingRequest {
+                command,
+                sent_at: now,
+            },
+        );
+    }
+
+    // Match a reply to its request; replies to unknown ids are ignored
+    pub fn resolve(
+        &mut self,
+        id: u64,
+        result: Result<Value, RequestError>,
+    ) -> Option<(ConnectionCommand, Result<Value, RequestError>)> {
+        let request = self.pending.remove(&id)?;
+        Some((request.command, result))
+    }
+
+    // Match a reply without an id to the oldest request still waiting, for venues
+    // that answer in order
+    pub fn resolve_oldest(
+        &mut self,
+        result: Result<Value, RequestError>,
+    ) -> Option<(ConnectionCommand, Result<Value, RequestError>)> {
+        let id = *self.pending.keys().min()?;
+        self.resolve(id, result)
+    }
+
+    // Fail every request that has waited longer than `timeout`
+    pub fn expire(
+        &mut self,
+        now: Instant,
+        timeout: Duration,
+    ) -> Vec<(ConnectionCommand, RequestError)> {
+        let expired: Vec<u64> = self
+            .pending
+            .iter()
+            .filter(|(_, request)| now.duration_since(request.sent_at) >= timeout)
+            .map(|(&id, _)| id)
+            .collect();
+        expired
+            .into_iter()
+            .filter_map(|id| self.pending.remove(&id))
+            .map(|request| (request.command, RequestError::TimedOut))
+            .collect()
+    }
+
+    // Fail every request still waiting, once the connection is gone
+    pub fn fail_all(&mut self) -> Vec<(ConnectionCommand, RequestError)> {
+        self.pending
+            .drain()
+            .map(|(_, request)| (request.command, RequestError::Disconnected))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_reply() {
+        assert_eq!(
+            parse_reply(r#"{"result":null,"id":3}"#),
+            Some((3, Ok(Value::Null)))
+        );
+        assert_eq!(
+            parse_reply(r#"{"error":{"code":2,"msg":"Invalid request"},"id":4}"#),
+            Some((
+                4,
+                Err(RequestError::Rejected {
+                    code: 2,
+                    msg: "Invalid request".to_string()
+                })
+            ))
+        );
+        assert_eq!(parse_reply(r#"{"e":"aggTrade","a":1}"#), None);
+    }
+
+    #[test]
+    fn test_replies_are_matched_to_requests() {
+        let start = Instant::now();
+        let subscribe = ConnectionCommand::Subscribe(vec!["btcusdt@trade".to_string()]);
+        let unsubscribe = ConnectionCommand::Unsubscribe(vec!["btcusdt@trade".to_string()]);
+        let mut requests = PendingRequests::new(1);
+
+        let first = requests.next_id();
+        requests.track(first, subscribe.clone(), start);
+        let second = requests.next_id();
+        requests.track(second, unsubscribe.clone(), start + Duration::from_secs(5));
+        assert_eq!((first, second), (1, 2));
+        assert_eq!(requests.next_id(), 3);
+
+        // Replies may arrive in any order, and only once
+        assert_eq!(requests.resolve(99, Ok(Value::Null)), None);
+        assert_eq!(
+            requests.resolve(first, Ok(Value::Null)),
+            Some((subscribe, Ok(Value::Null)))
+        );
+        assert_eq!(requests.resolve(first, Ok(Value::Null)), None);
+
+        let timeout = Duration::from_secs(10);
+        assert!(requests.expire(start + timeout, timeout).is_empty());
+        assert_eq!(
+            requests.expire(start + Duration::from_secs(15), timeout),
+            [(unsubscribe, RequestError::TimedOut)]
+        );
+        assert!(requests.fail_all().is_empty());
+
+        // Replies without an id go to the oldest request
+        let list = ConnectionCommand::ListSubscriptions;
+        let third = requests.next_id();
+        requests.track(third, list.clone(), start);
+        let fourth = requests.next_id();
+        let property = ConnectionCommand::GetProperty("combined".to_string());
+        requests.track(fourth, property, start);
+        assert_eq!(
+            requests.resolve_oldest(Ok(Value::Null)),
+            Some((list, Ok(Value::Null)))
+        );
+        assert_eq!(requests.pending.len(), 1);
+    }
+}
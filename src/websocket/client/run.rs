@@ -1,50 +1,191 @@
-use crate::storage::aggtrade_storage::AggTradeStorage;
-use crate::websocket::client::subscribe::subscribe_to_streams;
-use crate::websocket::client::unsubscribe::unsubscribe_from_streams;
-use crate::websocket::handler::aggtrade_handler::handle_aggtrade_messages;
-use crate::websocket::ping::start_ping;
+use crate::endpoints::{Endpoints, Market, Venue};
+use crate::exchange;
+use crate::storage::market_storage::MarketStorage;
+use crate::user_data::stream::{run_user_stream, UserStreamConfig};
+use crate::websocket::client::backoff::Backoff;
+use crate::websocket::client::connection::{
+    run_connection, ConnectionCommand, ConnectionConfig, ConnectionEvent,
+    MAX_STREAMS_PER_CONNECTION,
+};
+use crate::websocket::client::pool::{connections_needed, run_pool, PoolConfig};
+use crate::websocket::client::redundant::{run_redundant, RedundantConfig};
+use crate::websocket::handler::market_handler::handle_market_messages;
 use crate::websocket::shutdown::handle_shutdown;
-use futures_util::StreamExt;
+use std::future::Future;
 use std::sync::{Arc, RwLock};
-use tokio::sync::{mpsc, oneshot};
-use tokio_tungstenite::connect_async;
+use std::time::Duration;
+use tokio::sync::{mpsc, watch};
+
+// Delay before the first reconnection attempt, doubled after every failure
+const RECONNECT_BASE_DELAY: Duration = Duration::from_millis(500);
+// Upper bound for the delay between reconnection attempts
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(30);
+// Binance closes connections after 24 hours, so replace them well before that
+const ROTATE_AFTER: Duration = Duration::from_secs(23 * 60 * 60);
+// Time the replacement connection runs alongside the old one
+const ROTATION_OVERLAP: Duration = Duration::from_secs(10);
+// Delay before retrying a rotation whose replacement connection failed or dropped
+const ROTATION_RETRY_DELAY: Duration = Duration::from_secs(30);
+// Binance pings every 20 seconds, so a minute without any frame means the connection is dead
+const SILENCE_TIMEOUT: Duration = Duration::from_secs(60);
+// Time the server has to answer a subscription request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+// A listenKey expires after 60 minutes without a keepalive
+const LISTEN_KEY_KEEPALIVE: Duration = Duration::from_secs(30 * 60);
 
 pub async fn run(
+    endpoints: &Endpoints,
     url: &str,
-    streams: &Vec<String>,
+    streams: &[String],
     base_id: u64,
-    storage: &Arc<RwLock<AggTradeStorage>>,
+    storage: &Arc<RwLock<MarketStorage>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Keep the connection open, reconnecting and resubscribing when it drops
+    let config = connection_config(endpoints, url, streams, base_id);
+    run_dashboard(endpoints, streams, storage, |events, commands, shutdown| {
+        run_connection(config, events, commands, shutdown, backoff())
+    })
+    .await
+}
+
+/// Runs the dashboard for a subscription too large for one connection, spreading the
+/// streams across a pool of connections whose events are merged into one stream
+pub async fn run_sharded(
+    endpoints: &Endpoints,
+    streams: &[String],
+    storage: &Arc<RwLock<MarketStorage>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let config = PoolConfig {
+        connection: ConnectionConfig {
+            combined: Some(true),
+            ..connection_config(endpoints, &endpoints.raw_url(""), &[], 1)
+        },
+        streams: streams.to_vec(),
+        connections: connections_needed(streams),
+        max_streams: MAX_STREAMS_PER_CONNECTION,
+        backoff: backoff(),
+    };
+    run_dashboard(endpoints, streams, storage, |events, commands, shutdown| {
+        run_pool(config, events, commands, shutdown)
+    })
+    .await
+}
+
+/// Runs the dashboard from several connections to the same streams, so that a
+/// reconnect on one of them loses no trades or depth updates
+pub async fn run_with_standby(
+    endpoints: &Endpoints,
+    url: &str,
+    streams: &[String],
+    copies: usize,
+    storage: &Arc<RwLock<MarketStorage>>,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    let config = RedundantConfig {
+        connection: connection_config(endpoints, url, streams, 1),
+        copies,
+        backoff: backoff(),
+    };
+    run_dashboard(endpoints, streams, storage, |events, commands, shutdown| {
+        run_redundant(config, events, commands, shutdown)
+    })
+    .await
+}
+
+// Settings shared by every connection the dashboard opens
+fn connection_config(
+    endpoints: &Endpoints,
+    url: &str,
+    streams: &[String],
+    base_id: u64,
+) -> ConnectionConfig {
+    ConnectionConfig {
+        url: url.to_string(),
+        streams: streams.to_vec(),
+        base_id,
+        rotate_after: ROTATE_AFTER,
+        rotation_overlap: ROTATION_OVERLAP,
+        rotation_retry: ROTATION_RETRY_DELAY,
+        silence_timeout: SILENCE_TIMEOUT,
+        request_timeout: REQUEST_TIMEOUT,
+        combined: None,
+        exchange: exchange::for_endpoints(endpoints),
+    }
+}
+
+fn backoff() -> Backoff {
+    Backoff::new(RECONNECT_BASE_DELAY, RECONNECT_MAX_DELAY)
+}
+
+// The account's user data stream, when an API key is set; only spot events are decoded
+fn user_stream_config(endpoints: &Endpoints) -> Option<UserStreamConfig> {
+    if endpoints.venue != Venue::Binance || endpoints.market != Market::Spot {
+        return None;
+    }
+    Some(UserStreamConfig {
+        endpoints: endpoints.clone(),
+        api_key: endpoints.api_key.clone()?,
+        keepalive_interval: LISTEN_KEY_KEEPALIVE,
+        connection: connection_config(endpoints, "", &[], 1),
+        backoff: backoff(),
+    })
+}
+
+// Drive the dashboard from the events of the task `start` spawns until the user leaves
+async fn run_dashboard<F, T>(
+    endpoints: &Endpoints,
+    streams: &[String],
+    storage: &Arc<RwLock<MarketStorage>>,
+    start: F,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    F: FnOnce(
+        mpsc::Sender<ConnectionEvent>,
+        mpsc::Receiver<ConnectionCommand>,
+        watch::Receiver<bool>,
+    ) -> T,
+    T: Future<Output = ()> + Send + 'static,
+{
     let (shutdown_tx, mut shutdown_rx) = mpsc::channel(1);
-    let (ws_shutdown_tx, ws_shutdown_rx) = oneshot::channel();
+    let (connection_shutdown_tx, connection_shutdown_rx) = watch::channel(false);
+    let (events_tx, mut events_rx) = mpsc::channel(1024);
+    let (commands_tx, commands_rx) = mpsc::channel(16);
 
     // Spawn a task to handle shutdown
     tokio::spawn(async move {
         handle_shutdown(&shutdown_tx).await;
     });
 
-    // Connect to WebSocket
-    let (ws_stream, _) = connect_async(url).await?;
-    println!("WebSocket connected");
-
-    let (mut write, mut read) = ws_stream.split();
-
-    // Subscribe to streams
-    subscribe_to_streams(&mut write, &streams, base_id).await?;
-    let storage_clone = Arc::clone(storage);
-    // Handle incoming messages
-    let handle = tokio::spawn(async move {
-        handle_aggtrade_messages(&mut read, &storage_clone, &mut shutdown_rx).await;
-        let _ = ws_shutdown_tx.send(());
+    let (user_events_tx, mut user_events_rx) = mpsc::channel(1024);
+    let user_stream = user_stream_config(endpoints).map(|config| {
+        tokio::spawn(run_user_stream(
+            config,
+            user_events_tx,
+            connection_shutdown_rx.clone(),
+        ))
     });
+    let connection = tokio::spawn(start(events_tx, commands_rx, connection_shutdown_rx));
 
-    // Start ping
-    start_ping(&mut write, ws_shutdown_rx).await;
-    // Await the handle to ensure proper handling
-    handle.await?;
+    // Handle incoming messages; storage is kept across reconnects
+    handle_market_messages(
+        endpoints,
+        &mut events_rx,
+        &mut user_events_rx,
+        &commands_tx,
+        streams,
+        storage,
+        &mut shutdown_rx,
+    )
+    .await;
 
-    // Unsubscribe from streams
-    unsubscribe_from_streams(&mut write, &streams, base_id + 1000).await?;
+    // Close the connection once the dashboard is gone
+    let _ = connection_shutdown_tx.send(true);
+    drop(events_rx);
+    drop(user_events_rx);
+    connection.await?;
+    // Let the user stream close its listenKey
+    if let Some(user_stream) = user_stream {
+        user_stream.await?;
+    }
 
     Ok(())
 }
@@ -0,0 +1,91 @@
+use crate::websocket::client::rate_limit::TokenBucket;
+use futures_util::{Sink, SinkExt};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Instant;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::protocol::Message;
+
+/// Queues messages for the task that owns the write half of a connection,
+/// so several tasks can send without sharing the socket
+#[derive(Clone)]
+pub struct OutgoingSink {
+    tx: mpsc::UnboundedSender<Message>,
+}
+
+impl Sink<Message> for OutgoingSink {
+    type Error = mpsc::error::SendError<Message>;
+
+    fn poll_ready(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: Message) -> Result<(), Self::Error> {
+        self.tx.send(item)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Spawns a task writing queued messages to `write` in order, each one waiting for a token from
+/// `limiter` except the final close frame.
+/// The task stops after sending a close frame, on a write error, or once every sink is dropped.
+pub fn spawn_writer<W>(mut write: W, mut limiter: TokenBucket) -> (OutgoingSink, JoinHandle<()>)
+where
+    W: Sink<Message> + Unpin + Send + 'static,
+{
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let handle = tokio::spawn(async move {
+        while let Some(message) = rx.recv().await {
+            let closing = matches!(message, Message::Close(_));
+            if !closing {
+                let wait = limiter.acquire(Instant::now());
+                if !wait.is_zero() {
+                    tokio::time::sleep(wait).await;
+                }
+            }
+            if write.send(message).await.is_err() || closing {
+                break;
+            }
+        }
+    });
+    (OutgoingSink { tx }, handle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::MockSink;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_messages_are_rate_limited() {
+        let (mock_sink, mut rx) = MockSink::new();
+        let start = Instant::now();
+        let (mut outgoing, writer) = spawn_writer(mock_sink, TokenBucket::new(2, 20.0, start));
+
+        for id in 0..4 {
+            outgoing.send(Message::Text(id.to_string())).await.unwrap();
+        }
+        outgoing.send(Message::Close(None)).await.unwrap();
+
+        // Two messages fit the burst, the other two wait 50ms each, the close frame does not wait
+        let mut received = Vec::new();
+        while let Some(message) = rx.recv().await {
+            received.push((message, start.elapsed()));
+        }
+        writer.await.unwrap();
+        assert_eq!(received.len(), 5);
+        assert!(received[1].1 < Duration::from_millis(50));
+        assert!(received[3].1 >= Duration::from_millis(100));
+        assert_eq!(received[4].0, Message::Close(None));
+        assert!(received[4].1 - received[3].1 < Duration::from_millis(50));
+    }
+}
@@ -0,0 +1,354 @@
+use crate::storage::aggtrade_storage::AggTrade;
+use crate::storage::depth_storage::DepthSnapshot;
+use crate::storage::kline_storage::Kline;
+use crate::websocket::client::requests::{parse_reply, RequestError};
+use crate::websocket::ping::{handle_incoming, Liveness};
+use chrono::{DateTime, TimeZone, Utc};
+use futures_util::{SinkExt, StreamExt};
+use serde::de::{DeserializeOwned, IgnoredAny};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, oneshot};
+use tokio_tungstenite::tungstenite::protocol::Message;
+use tokio_tungstenite::{connect_async, tungstenite};
+
+// A request waiting on the connection task, with where to deliver its result
+struct Request {
+    method: String,
+    params: Value,
+    reply: oneshot::Sender<Result<Value, RequestError>>,
+}
+
+/// Client for the request/response WebSocket API (`/ws-api/v3`). Each request gets an
+/// id and resolves with the result of the response carrying the same id, so market data
+/// can be queried over one open connection instead of REST. Requests fail with
+/// `Disconnected` once the connection is gone; connect a new client to carry on.
+#[derive(Clone)]
+pub struct WsApiClient {
+    requests: mpsc::Sender<Request>,
+}
+
+#[derive(Deserialize)]
+struct ServerTime {
+    #[serde(rename = "serverTime")]
+    server_time: i64,
+}
+
+#[derive(Deserialize)]
+struct TickerPrice {
+    price: String,
+}
+
+// Candle as sent by the `klines` method: open time, OHLCV, close time, quote volume,
+// trade count and taker buy volumes
+#[derive(Deserialize)]
+struct KlineRow(
+    i64,
+    String,
+    String,
+    String,
+    String,
+    String,
+    i64,
+    String,
+    u64,
+    String,
+    String,
+    IgnoredAny,
+);
+
+impl WsApiClient {
+    // Connect to the WebSocket API at `url`; requests unanswered after `request_timeout` fail
+    pub async fn connect(url: &str, request_timeout: Duration) -> Result<Self, tungstenite::Error> {
+        let (ws_stream, _) = connect_async(url).await?;
+        let (requests_tx, requests_rx) = mpsc::channel(64);
+        tokio::spawn(run_client(ws_stream, requests_rx, request_timeout));
+        Ok(Self {
+            requests: requests_tx,
+        })
+    }
+
+    // Send a request and wait for its result
+    pub async fn request(&self, method: &str, params: Value) -> Result<Value, RequestError> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        let request = Request {
+            method: method.to_string(),
+            params,
+            reply: reply_tx,
+        };
+        self.requests
+            .send(request)
+            .await
+            .map_err(|_| RequestError::Disconnected)?;
+        reply_rx.await.unwrap_or(Err(RequestError::Disconnected))
+    }
+
+    // Send a request and decode its result
+    async fn query<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: Value,
+    ) -> Result<T, RequestError> {
+        let result = self.request(method, params).await?;
+        serde_json::from_value(result).map_err(|e| RequestError::InvalidReply(e.to_string()))
+    }
+
+    // Get the server's clock
+    pub async fn server_time(&self) -> Result<DateTime<Utc>, RequestError> {
+        let time: ServerTime = self.query("time", Value::Null).await?;
+        Utc.timestamp_millis_opt(time.server_time)
+            .single()
+            .ok_or_else(|| RequestError::InvalidReply("server time out of range".to_string()))
+    }
+
+    // Get the latest price of a symbol
+    pub async fn ticker_price(&self, symbol: &str) -> Result<f64, RequestError> {
+        let ticker: TickerPrice = self
+            .query("ticker.price", json!({ "symbol": symbol.to_uppercase() }))
+            .await?;
+        parse_number(&ticker.price)
+    }
+
+    // Get the best `limit` levels of a symbol's order book
+    pub async fn depth(&self, symbol: &str, limit: u16) -> Result<DepthSnapshot, RequestError> {
+        let mut snapshot: DepthSnapshot = self
+            .query(
+                "depth",
+                json!({ "symbol": symbol.to_uppercase(), "limit": limit }),
+            )
+            .await?;
+        snapshot.symbol = symbol.to_uppercase();
+        Ok(snapshot)
+    }
+
+    // Get up to `limit` aggregate trades from `from_id`, or the most recent ones without it
+    pub async fn agg_trades(
+        &self,
+        symbol: &str,
+        from_id: Option<u64>,
+        limit: u16,
+    ) -> Result<Vec<AggTrade>, RequestError> {
+        let mut params = json!({ "symbol": symbol.to_uppercase(), "limit": limit });
+        if let Some(from_id) = from_id {
+            params["fromId"] = json!(from_id);
+        }
+        let mut trades: Vec<AggTrade> = self.query("trades.aggregate", params).await?;
+        for trade in &mut trades {
+            trade.symbol = symbol.to_uppercase();
+        }
+        Ok(trades)
+    }
+
+    // Get the latest `limit` candles of a symbol, oldest first
+    pub async fn klines(
+        &self,
+        symbol: &str,
+        interval: &str,
+        limit: u16,
+    ) -> Result<Vec<Kline>, RequestError> {
+        let rows: Vec<KlineRow> = self
+            .query(
+                "klines",
+                json!({ "symbol": symbol.to_uppercase(), "interval": interval, "limit": limit }),
+            )
+            .await?;
+        let now = Utc::now();
+        rows.into_iter()
+            .map(|row| {
+                let time = |millis: i64| {
+                    Utc.timestamp_millis_opt(millis).single().ok_or_else(|| {
+                        RequestError::InvalidReply("candle time out of range".to_string())
+                    })
+                };
+                let close_time = time(row.6)?;
+                Ok(Kline {
+                    symbol: symbol.to_uppercase(),
+                    interval: interval.to_string(),
+                    open_time: time(row.0)?,
+                    close_time,
+                    open: parse_number(&row.1)?,
+                    high: parse_number(&row.2)?,
+                    low: parse_number(&row.3)?,
+                    close: parse_number(&row.4)?,
+                    volume: parse_number(&row.5)?,
+                    quote_volume: parse_number(&row.7)?,
+                    trade_count: row.8,
+                    taker_buy_base_volume: parse_number(&row.9)?,
+                    taker_buy_quote_volume: parse_number(&row.10)?,
+                    is_closed: close_time < now,
+                })
+            })
+            .collect()
+    }
+}
+
+// Parse a decimal sent as a string
+fn parse_number(text: &str) -> Result<f64, RequestError> {
+    text.parse()
+        .map_err(|_| RequestError::InvalidReply(format!("'{}' is not a number", text)))
+}
+
+/// Sends queued requests with increasing ids and resolves each with the response that
+/// carries its id, failing those unanswered after `request_timeout`. Stops when the
+/// connection closes or every client is dropped.
+async fn run_client(
+    ws_stream: tokio_tungstenite::WebSocketStream<
+        tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>,
+    >,
+    mut requests: mpsc::Receiver<Request>,
+    request_timeout: Duration,
+) {
+    let (mut write, mut read) = ws_stream.split();
+    let mut liveness = Liveness::new(Instant::now());
+    let mut pending: HashMap<u64, (oneshot::Sender<Result<Value, RequestError>>, Instant)> =
+        HashMap::new();
+    let mut next_id = 1;
+    let mut sweep = tokio::time::interval(request_timeout / 4);
+
+    loop {
+        tokio::select! {
+            request = requests.recv() => {
+                let Some(request) = request else {
+                    let _ = write.send(Message::Close(None)).await;
+                    break;
+                };
+                let id = next_id;
+                next_id += 1;
+                let mut message = json!({ "id": id, "method": request.method });
+                if !request.params.is_null() {
+                    message["params"] = request.params;
+                }
+                if write.send(Message::Text(message.to_string())).await.is_err() {
+                    let _ = request.reply.send(Err(RequestError::Disconnected));
+                    break;
+                }
+                pending.insert(id, (request.reply, Instant::now()));
+            }
+            message = read.next() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(message)) => match handle_incoming(&mut write, message, &mut liveness).await {
+                    Ok(Some(text)) => {
+                        // Responses to ids that already timed out are dropped
+                        if let Some((id, result)) = parse_reply(&text) {
+                            if let Some((reply, _)) = pending.remove(&id) {
+                                let _ = reply.send(result);
+                            }
+                        }
+                    }
+                    Ok(None) => {}
+                    Err(_) => break,
+                },
+            },
+            _ = sweep.tick() => {
+                let now = Instant::now();
+                let expired: Vec<u64> = pending
+                    .iter()
+                    .filter(|(_, (_, sent_at))| now.duration_since(*sent_at) >= request_timeout)
+                    .map(|(id, _)| *id)
+                    .collect();
+                for id in expired {
+                    if let Some((reply, _)) = pending.remove(&id) {
+                        let _ = reply.send(Err(RequestError::TimedOut));
+                    }
+                }
+            }
+        }
+    }
+
+    // Requests still waiting are dropped, which the clients see as a disconnect
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::spawn_ws_responder;
+
+    // Answer like the WebSocket API, except for `slow` which is never answered
+    fn respond(request: &Value) -> Option<Value> {
+        let id = request["id"].clone();
+        let params = &request["params"];
+        let result = match request["method"].as_str()? {
+            "time" => json!({ "serverTime": 1656400526260u64 }),
+            "ticker.price" if params["symbol"] == "BNBBTC" => {
+                json!({ "symbol": "BNBBTC", "price": "0.01361900" })
+            }
+            "ticker.price" => {
+                return Some(json!({
+                    "id": id,
+                    "status": 400,
+                    "error": { "code": -1121, "msg": "Invalid symbol." }
+                }))
+            }
+            "depth" => json!({
+                "lastUpdateId": 2731179239u64,
+                "bids": [["0.01379900", "3.43200000"]],
+                "asks": [["0.01380000", "5.91700000"]]
+            }),
+            "trades.aggregate" => json!([{
+                "a": params["fromId"].as_u64().unwrap_or(50000000),
+                "p": "0.00274100", "q": "57.19000000", "f": 59120167, "l": 59120170,
+                "T": 1565877971222u64, "m": true, "M": true
+            }]),
+            "klines" => json!([[
+                1655971200000u64,
+                "0.01086000",
+                "0.01086600",
+                "0.01083600",
+                "0.01083800",
+                "2290.53800000",
+                1656028799999u64,
+                "24.85074442",
+                2283,
+                "1171.64000000",
+                "12.71225884",
+                "0"
+            ]]),
+            _ => return None,
+        };
+        Some(json!({ "id": id, "status": 200, "result": result }))
+    }
+
+    #[tokio::test]
+    async fn test_requests_resolve_with_matching_responses() {
+        let url = spawn_ws_responder(respond).await;
+        let client = WsApiClient::connect(&url, Duration::from_millis(200))
+            .await
+            .unwrap();
+
+        // Concurrent requests each get their own response
+        let (time, price, depth) = tokio::join!(
+            client.server_time(),
+            client.ticker_price("bnbbtc"),
+            client.depth("bnbbtc", 5)
+        );
+        assert_eq!(time.unwrap().timestamp_millis(), 1656400526260);
+        assert_eq!(price.unwrap(), 0.013619);
+        let depth = depth.unwrap();
+        assert_eq!(depth.symbol, "BNBBTC");
+        assert_eq!(depth.last_update_id, 2731179239);
+
+        let trades = client.agg_trades("bnbbtc", Some(42), 1).await.unwrap();
+        assert_eq!(
+            (trades[0].symbol.as_str(), trades[0].trade_id),
+            ("BNBBTC", 42)
+        );
+        let klines = client.klines("bnbbtc", "1d", 1).await.unwrap();
+        assert_eq!(klines[0].close, 0.010838);
+        assert_eq!(klines[0].trade_count, 2283);
+        assert!(klines[0].is_closed);
+
+        assert_eq!(
+            client.ticker_price("nope").await,
+            Err(RequestError::Rejected {
+                code: -1121,
+                msg: "Invalid symbol.".to_string()
+            })
+        );
+        assert_eq!(
+            client.request("slow", Value::Null).await,
+            Err(RequestError::TimedOut)
+        );
+    }
+}
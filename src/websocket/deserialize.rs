@@ -0,0 +1,130 @@
+use chrono::{DateTime, TimeZone, Utc};
+use serde::de::{self, Deserializer};
+use serde::Deserialize;
+
+/// Binance sends decimals as strings to preserve precision; accept numbers too
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StringOrNumber {
+    String(String),
+    Number(f64),
+}
+
+impl StringOrNumber {
+    fn into_f64<E: de::Error>(self) -> Result<f64, E> {
+        match self {
+            StringOrNumber::String(s) => s
+                .parse()
+                .map_err(|_| E::custom(format!("invalid decimal `{}`", s))),
+            StringOrNumber::Number(n) => Ok(n),
+        }
+    }
+}
+
+// Deserialize an f64 from a decimal string such as "0.0010"
+pub fn f64_from_str<'de, D>(deserializer: D) -> Result<f64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    StringOrNumber::deserialize(deserializer)?.into_f64()
+}
+
+// Deserialize an optional f64 from a decimal string, where an empty string means no value
+pub fn optional_f64_from_str<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match StringOrNumber::deserialize(deserializer)? {
+        StringOrNumber::String(s) if s.is_empty() => Ok(None),
+        value => value.into_f64().map(Some),
+    }
+}
+
+// Deserialize a UTC timestamp from milliseconds since the epoch
+pub fn datetime_from_millis<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let millis = i64::deserialize(deserializer)?;
+    Utc.timestamp_millis_opt(millis)
+        .single()
+        .ok_or_else(|| de::Error::custom(format!("invalid timestamp `{}`", millis)))
+}
+
+/// A price level sent as a `["price", "quantity"]` pair
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PriceLevel {
+    pub price: f64,
+    pub quantity: f64,
+}
+
+impl<'de> Deserialize<'de> for PriceLevel {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (price, quantity) = <(StringOrNumber, StringOrNumber)>::deserialize(deserializer)?;
+        Ok(PriceLevel {
+            price: price.into_f64()?,
+            quantity: quantity.into_f64()?,
+        })
+    }
+}
+
+// Deserialize a UTC timestamp from an RFC 3339 string such as "2024-05-14T09:12:03.482817Z"
+pub fn datetime_from_rfc3339<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let text = String::deserialize(deserializer)?;
+    DateTime::parse_from_rfc3339(&text)
+        .map(|time| time.with_timezone(&Utc))
+        .map_err(|_| de::Error::custom(format!("invalid timestamp `{}`", text)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct Sample {
+        #[serde(deserialize_with = "f64_from_str")]
+        price: f64,
+        #[serde(deserialize_with = "datetime_from_millis")]
+        time: DateTime<Utc>,
+    }
+
+    #[test]
+    fn test_f64_from_str() {
+        let sample: Sample =
+            serde_json::from_str(r#"{"price":"0.0010","time":1672515782136}"#).unwrap();
+        assert_eq!(sample.price, 0.001);
+        assert_eq!(sample.time.timestamp_millis(), 1672515782136);
+
+        let numeric: Sample = serde_json::from_str(r#"{"price":1.5,"time":0}"#).unwrap();
+        assert_eq!(numeric.price, 1.5);
+
+        let invalid = serde_json::from_str::<Sample>(r#"{"price":"abc","time":0}"#);
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    fn test_price_level() {
+        let levels: Vec<PriceLevel> =
+            serde_json::from_str(r#"[["0.0024","10"],["0.0025","0"]]"#).unwrap();
+        assert_eq!(
+            levels,
+            vec![
+                PriceLevel {
+                    price: 0.0024,
+                    quantity: 10.0
+                },
+                PriceLevel {
+                    price: 0.0025,
+                    quantity: 0.0
+                }
+            ]
+        );
+        assert!(serde_json::from_str::<PriceLevel>(r#"["0.0024"]"#).is_err());
+    }
+}
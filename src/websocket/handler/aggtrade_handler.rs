@@ -1,155 +1,156 @@
-use crate::storage::aggtrade_storage::AggTradeStorage;
+use crate::rest::agg_trades::fetch_agg_trades;
+use crate::storage::aggtrade_storage::{AggTrade, AggTradeStorage, TradeGap};
+use crate::storage::book_ticker_storage::QuoteSeries;
+use crate::storage::depth_storage::DepthSnapshot;
+use crate::storage::market_storage::MarketStorage;
 use crate::ui::render::{render_ui, RenderData};
-use crate::websocket::handler::input::handle_input;
-use crate::websocket::message::parse_agg_trade;
-use crossterm::{
-    execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
-};
-use futures_util::StreamExt;
-use ratatui::backend::CrosstermBackend;
-use ratatui::Terminal;
-use serde_json::Value;
-use std::io::stdout;
+use crate::websocket::handler::book_ticker_handler::quote_data;
+use crate::websocket::handler::performance::PerformanceMetrics;
+use reqwest::Error;
 use std::sync::{Arc, RwLock};
-use std::time::{Duration, Instant};
-use tokio::sync::mpsc;
-use tokio_tungstenite::tungstenite::protocol::Message;
 
-pub async fn handle_aggtrade_messages<S>(
-    read: &mut S,
-    storage: &Arc<RwLock<AggTradeStorage>>,
-    shutdown_rx: &mut mpsc::Receiver<()>,
-) where
-    S: StreamExt<Item = Result<Message, tokio_tungstenite::tungstenite::Error>> + Unpin,
-{
-    // Initialize terminal
-    let mut stdout = stdout();
-    execute!(stdout, EnterAlternateScreen).unwrap();
-    enable_raw_mode().unwrap();
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend).unwrap();
-    terminal.clear().unwrap();
+// Most aggregate trades the REST API returns per request
+const BACKFILL_PAGE: u16 = 1000;
 
-    // Channel for user input shutdown signal
-    let (input_tx, mut input_rx) = mpsc::channel(1);
-    tokio::spawn(async move {
-        handle_input(&input_tx).await;
-    });
+/// Stores an incoming aggTrade
+pub fn handle_agg_trade(agg_trade: AggTrade, storage: &Arc<RwLock<MarketStorage>>) {
+    let mut storage = storage.write().unwrap();
+    storage.agg_trades.add_trade(agg_trade);
+}
 
-    let mut message_count: u64 = 0;
-    let mut total_processing_time = Duration::new(0, 0);
-    let mut total_arrival_time = Duration::new(0, 0);
-    let mut last_message_time = Instant::now();
-    let mut arrival_intervals = Vec::new();
-    let mut processing_times = Vec::new();
+/// Fetches the trades missing from every gap found in the aggTrade stream
+pub fn request_backfills(storage: &Arc<RwLock<MarketStorage>>, api_url: &str) {
+    let gaps = storage.write().unwrap().agg_trades.take_gaps();
+    for gap in gaps {
+        let storage = Arc::clone(storage);
+        let api_url = api_url.to_string();
+        tokio::spawn(async move {
+            let trades = fetch_gap(&api_url, &gap).await;
+            let mut storage = storage.write().unwrap();
+            match trades {
+                Ok(trades) => storage.agg_trades.insert_backfill(trades),
+                Err(_) => storage.agg_trades.backfill_failed(),
+            }
+        });
+    }
+}
 
-    'main_loop: loop {
-        tokio::select! {
-            // Handle incoming messages
-            Some(message) = read.next() => {
-                let arrival_time = last_message_time.elapsed();
-                total_arrival_time += arrival_time;
-                last_message_time = Instant::now();
+// Fetch the trades of a gap page by page
+async fn fetch_gap(api_url: &str, gap: &TradeGap) -> Result<Vec<AggTrade>, Error> {
+    let mut trades: Vec<AggTrade> = Vec::new();
+    let mut from_id = gap.from_id;
+    while from_id <= gap.to_id {
+        let page = fetch_agg_trades(api_url, &gap.symbol, from_id, BACKFILL_PAGE).await?;
+        let Some(last) = page.last() else {
+            break;
+        };
+        from_id = last.trade_id + 1;
+        trades.extend(page.into_iter().filter(|trade| trade.trade_id <= gap.to_id));
+    }
+    Ok(trades)
+}
 
-                match message {
-                    Ok(Message::Text(text)) => {
-                        if let Ok(json) = serde_json::from_str::<Value>(&text) {
-                            if let Some(agg_trade) = parse_agg_trade(&json) {
-                                let start_processing = Instant::now();
+/// Draws the aggTrade dashboard from the current storage contents
+pub fn draw_agg_trades(
+    f: &mut ratatui::Frame,
+    storage: &AggTradeStorage,
+    depth: Option<&DepthSnapshot>,
+    quote: Option<&QuoteSeries>,
+    performance: &PerformanceMetrics,
+) {
+    // Calculate statistics
+    let (buyer_maker_true, buyer_maker_false) = storage.calculate_buyer_maker_count();
+    let last_price = storage
+        .get_trades()
+        .back()
+        .map(|trade| trade.price)
+        .unwrap_or(0.0);
+    let trades = storage
+        .get_trades()
+        .iter()
+        .rev()
+        .take(20)
+        .cloned()
+        .collect::<Vec<_>>();
+    let prices = storage
+        .get_trades()
+        .iter()
+        .map(|trade| (trade.timestamp.timestamp_millis() as f64, trade.price))
+        .collect::<Vec<_>>();
 
-                                // Update storage
-                                {
-                                    let mut storage = storage.write().unwrap();
-                                    storage.add_trade(agg_trade.clone());
-                                }
+    // Create RenderData
+    let render_data = RenderData {
+        trades: &trades,
+        avg_price: storage.calculate_average_price().unwrap_or(0.0),
+        median_price: storage.calculate_median_price().unwrap_or(0.0),
+        std_dev: storage.calculate_standard_deviation().unwrap_or(0.0),
+        total_volume: storage.total_volume(),
+        total_contracts: storage.total_contracts(),
+        volume_weighted_avg_price: storage.calculate_vwap().unwrap_or(0.0),
+        max_price: storage.calculate_max_price().unwrap_or(0.0),
+        min_price: storage.calculate_min_price().unwrap_or(0.0),
+        ema: storage.calculate_ema(10).unwrap_or(0.0),
+        sma: storage.calculate_sma(10).unwrap_or(0.0),
+        rsi: storage.calculate_rsi(14).unwrap_or(0.0),
+        last_price,
+        prices: &prices,
+        buyer_maker_count: (buyer_maker_true, buyer_maker_false),
+        depth,
+        quote: quote.map(quote_data),
+        performance: performance.render_data(),
+    };
 
-                                // Calculate statistics
-                                let (avg_price, median_price, std_dev, total_volume, volume_weighted_avg_price, max_price, min_price, ema, sma, rsi, buyer_maker_true, buyer_maker_false, last_price, trades, prices) = {
-                                    let storage = storage.read().unwrap();
-                                    (
-                                        storage.calculate_average_price().unwrap_or(0.0),
-                                        storage.calculate_median_price().unwrap_or(0.0),
-                                        storage.calculate_standard_deviation().unwrap_or(0.0),
-                                        storage.total_volume(),
-                                        storage.calculate_vwap().unwrap_or(0.0),
-                                        storage.calculate_max_price().unwrap_or(0.0),
-                                        storage.calculate_min_price().unwrap_or(0.0),
-                                        storage.calculate_ema(10).unwrap_or(0.0),
-                                        storage.calculate_sma(10).unwrap_or(0.0),
-                                        storage.calculate_rsi(14).unwrap_or(0.0),
-                                        storage.calculate_buyer_maker_count().0,
-                                        storage.calculate_buyer_maker_count().1,
-                                        agg_trade.price,
-                                        storage.get_trades().iter().rev().take(20).cloned().collect::<Vec<_>>(),
-                                        storage.get_trades().iter().map(|trade| (trade.timestamp.timestamp_millis() as f64, trade.price)).collect::<Vec<_>>()
-                                    )
-                                };
+    render_ui(f, &render_data);
+}
 
-                                // Calculate performance metrics
-                                let processing_time = start_processing.elapsed();
-                                total_processing_time += processing_time;
-                                message_count += 1;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::spawn_http_server;
+    use crate::websocket::handler::dispatcher::dispatch_frame;
+    use crate::websocket::message::parse_frame;
+    use std::time::Duration;
 
-                                let avg_arrival_interval = total_arrival_time.as_secs_f64() * 1000.0 / message_count as f64;
-                                let avg_processing_time = total_processing_time.as_secs_f64() * 1000.0 / message_count as f64;
+    fn agg_trade_frame(id: u64) -> String {
+        format!(
+            r#"{{"stream":"btcusdt@aggTrade","data":{{"e":"aggTrade","E":1,"s":"BTCUSDT","a":{},"p":"100.0","q":"1.0","f":{},"l":{},"T":{},"m":false}}}}"#,
+            id, id, id, id
+        )
+    }
 
-                                // Update performance metrics history
-                                arrival_intervals.push((message_count as f64, avg_arrival_interval));
-                                processing_times.push((message_count as f64, avg_processing_time));
+    #[tokio::test]
+    async fn test_gap_in_agg_trades_is_backfilled() {
+        let rest_url = spawn_http_server(vec![(
+            "/api/v3/aggTrades?symbol=BTCUSDT&fromId=2&limit=1000",
+            r#"[{"a":2,"p":"100.0","q":"1.0","f":2,"l":2,"T":2,"m":false},{"a":3,"p":"100.0","q":"1.0","f":3,"l":3,"T":3,"m":true},{"a":4,"p":"100.0","q":"1.0","f":4,"l":4,"T":4,"m":false}]"#.to_string(),
+        )])
+        .await;
 
-                                // Create RenderData
-                                let render_data = RenderData {
-                                    trades: &trades,
-                                    avg_price,
-                                    median_price,
-                                    std_dev,
-                                    total_volume,
-                                    volume_weighted_avg_price,
-                                    max_price,
-                                    min_price,
-                                    ema,
-                                    sma,
-                                    rsi,
-                                    last_price,
-                                    prices: &prices,
-                                    buyer_maker_count: (buyer_maker_true, buyer_maker_false),
-                                    message_count,
-                                    avg_arrival_interval,
-                                    avg_processing_time,
-                                    arrival_intervals: &arrival_intervals,
-                                    processing_times: &processing_times,
-                                };
+        let storage = Arc::new(RwLock::new(MarketStorage::new(100)));
+        for id in [1, 4] {
+            let frame = parse_frame(&agg_trade_frame(id)).unwrap().unwrap();
+            dispatch_frame(frame, &storage);
+            request_backfills(&storage, &format!("{}/api/v3", rest_url));
+        }
 
-                                // Draw UI
-                                terminal.draw(|f| {
-                                    render_ui(f, &render_data);
-                                }).unwrap();
-                            }
-                        }
-                    }
-                    Ok(Message::Close(_)) => {
-                        println!("WebSocket connection closed.");
-                        break 'main_loop;
-                    }
-                    _ => {}
-                }
-            },
-            // Handle shutdown signal
-            _ = shutdown_rx.recv() => {
-                println!("Received shutdown signal.");
-                break 'main_loop;
-            },
-            // Handle input shutdown signal
-            _ = input_rx.recv() => {
-                println!("Received input shutdown signal.");
-                break 'main_loop;
-            },
+        // Wait for the backfill task to insert the missing trades
+        for _ in 0..100 {
+            if storage.read().unwrap().agg_trades.backfilled_count > 0 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
         }
-    }
 
-    // Restore terminal
-    disable_raw_mode().unwrap();
-    execute!(terminal.backend_mut(), LeaveAlternateScreen).unwrap();
-    terminal.show_cursor().unwrap();
+        let storage = storage.read().unwrap();
+        let ids: Vec<u64> = storage
+            .agg_trades
+            .get_trades()
+            .iter()
+            .map(|trade| trade.trade_id)
+            .collect();
+        assert_eq!(ids, [1, 2, 3, 4]);
+        assert_eq!(storage.agg_trades.gap_count, 1);
+        // Trade 4 arrived on the stream, only the missing ones are backfilled
+        assert_eq!(storage.agg_trades.backfilled_count, 2);
+    }
 }
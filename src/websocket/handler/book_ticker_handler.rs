@@ -0,0 +1,62 @@
+use crate::storage::book_ticker_storage::{BookTickerStorage, QuoteSeries};
+use crate::storage::market_storage::MarketStorage;
+use crate::ui::render::{render_book_ticker_ui, BookTickerRenderData, QuoteData};
+use crate::websocket::handler::performance::PerformanceMetrics;
+use crate::websocket::message::BookTicker;
+use std::sync::{Arc, RwLock};
+
+/// Stores an incoming best bid/ask update
+pub fn handle_book_ticker(book_ticker: BookTicker, storage: &Arc<RwLock<MarketStorage>>) {
+    let mut storage = storage.write().unwrap();
+    storage.book_tickers.add_book_ticker(book_ticker);
+}
+
+/// Builds the quote statistics shown in the statistics panels
+pub fn quote_data(quote: &QuoteSeries) -> QuoteData<'_> {
+    let latest = quote.latest();
+    QuoteData {
+        symbol: &latest.symbol,
+        bid_price: latest.bid_price,
+        bid_quantity: latest.bid_quantity,
+        ask_price: latest.ask_price,
+        ask_quantity: latest.ask_quantity,
+        spread: quote.calculate_spread(),
+        spread_bps: quote.calculate_spread_bps(),
+        average_spread: quote.calculate_average_spread(),
+        average_spread_bps: quote.calculate_average_spread_bps(),
+        mid_price: quote.calculate_mid_price(),
+        microprice: quote.calculate_microprice(),
+    }
+}
+
+/// Draws the bookTicker dashboard, highlighting the most recently updated symbol
+pub fn draw_book_tickers(
+    f: &mut ratatui::Frame,
+    storage: &BookTickerStorage,
+    performance: &PerformanceMetrics,
+) {
+    let Some(current) = storage.last_updated_quote() else {
+        return;
+    };
+    let quotes = storage
+        .get_quotes()
+        .into_iter()
+        .map(quote_data)
+        .collect::<Vec<_>>();
+    let spreads = current
+        .get_spreads()
+        .iter()
+        .enumerate()
+        .map(|(i, spread)| (i as f64, *spread))
+        .collect::<Vec<_>>();
+
+    // Create BookTickerRenderData
+    let render_data = BookTickerRenderData {
+        quotes: &quotes,
+        current: quote_data(current),
+        spreads: &spreads,
+        performance: performance.render_data(),
+    };
+
+    render_book_ticker_ui(f, &render_data);
+}
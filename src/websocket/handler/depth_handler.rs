@@ -0,0 +1,179 @@
+use crate::rest::depth::fetch_depth_snapshot;
+use crate::storage::depth_storage::{DepthSnapshot, DepthStorage};
+use crate::storage::market_storage::MarketStorage;
+use crate::storage::order_book::OrderBookStorage;
+use crate::ui::render::{
+    render_depth_ui, render_order_book_ui, DepthRenderData, OrderBookRenderData,
+};
+use crate::websocket::handler::performance::PerformanceMetrics;
+use crate::websocket::message::DepthUpdate;
+use std::sync::{Arc, RwLock};
+
+// Number of levels requested from the REST snapshot
+const SNAPSHOT_LIMIT: u16 = 1000;
+// Number of levels shown on the order book ladder
+const LADDER_LEVELS: usize = 20;
+
+/// Stores the latest partial depth snapshot for a symbol
+pub fn handle_partial_depth(
+    symbol: String,
+    mut snapshot: DepthSnapshot,
+    storage: &Arc<RwLock<MarketStorage>>,
+) {
+    snapshot.symbol = symbol;
+    let mut storage = storage.write().unwrap();
+    storage.depth.update(snapshot);
+}
+
+/// Applies a diff depth event to the local order book for its symbol
+pub fn handle_depth_update(update: DepthUpdate, storage: &Arc<RwLock<MarketStorage>>) {
+    let mut storage = storage.write().unwrap();
+    storage.order_books.apply_update(update);
+}
+
+/// Fetches REST snapshots for every order book waiting for one
+pub fn request_snapshots(storage: &Arc<RwLock<MarketStorage>>, api_url: &str) {
+    let symbols = storage
+        .write()
+        .unwrap()
+        .order_books
+        .take_snapshot_requests();
+    for symbol in symbols {
+        let storage = Arc::clone(storage);
+        let api_url = api_url.to_string();
+        tokio::spawn(async move {
+            let snapshot = fetch_depth_snapshot(&api_url, &symbol, SNAPSHOT_LIMIT).await;
+            let mut storage = storage.write().unwrap();
+            match snapshot {
+                // A stale snapshot is re-requested on the next event
+                Ok(snapshot) => {
+                    let _ = storage.order_books.apply_snapshot(&symbol, &snapshot);
+                }
+                Err(_) => storage.order_books.snapshot_failed(&symbol),
+            }
+        });
+    }
+}
+
+/// Draws the partial depth dashboard for the most recently updated symbol
+pub fn draw_depth(
+    f: &mut ratatui::Frame,
+    storage: &DepthStorage,
+    performance: &PerformanceMetrics,
+) {
+    let Some(snapshot) = storage.last_updated_snapshot() else {
+        return;
+    };
+
+    // Create DepthRenderData
+    let render_data = DepthRenderData {
+        snapshot,
+        performance: performance.render_data(),
+    };
+
+    render_depth_ui(f, &render_data);
+}
+
+/// Draws the local order book dashboard for the most recently updated symbol
+pub fn draw_order_book(
+    f: &mut ratatui::Frame,
+    storage: &OrderBookStorage,
+    performance: &PerformanceMetrics,
+) {
+    let Some(sync) = storage.last_updated_book() else {
+        return;
+    };
+    let book = sync.book();
+    let snapshot = book.to_snapshot(LADDER_LEVELS);
+
+    // Quantity resting within 0.1% of the mid price
+    let mid_price = book.calculate_mid_price().unwrap_or(0.0);
+    let (bid_levels, ask_levels) = book.level_count();
+
+    // Create OrderBookRenderData
+    let render_data = OrderBookRenderData {
+        snapshot: &snapshot,
+        synced: sync.is_synced(),
+        buffered_count: sync.buffered_count(),
+        resync_count: sync.resync_count,
+        bid_levels,
+        ask_levels,
+        bid_depth_10bps: book.bid_depth_to(mid_price * 0.999),
+        ask_depth_10bps: book.ask_depth_to(mid_price * 1.001),
+        last_event_time: book.last_event_time,
+        performance: performance.render_data(),
+    };
+
+    render_order_book_ui(f, &render_data);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{spawn_http_server, spawn_ws_server};
+    use crate::websocket::handler::dispatcher::dispatch_frame;
+    use crate::websocket::message::parse_frame;
+    use futures_util::StreamExt;
+    use std::time::Duration;
+    use tokio_tungstenite::connect_async;
+
+    fn diff_frame(first_update_id: u64, final_update_id: u64, bid_price: &str) -> String {
+        format!(
+            r#"{{"stream":"btcusdt@depth@100ms","data":{{"e":"depthUpdate","E":1700000000000,"s":"BTCUSDT","U":{},"u":{},"b":[["{}","1.0"]],"a":[]}}}}"#,
+            first_update_id, final_update_id, bid_price
+        )
+    }
+
+    #[tokio::test]
+    async fn test_order_book_syncs_from_stream_and_snapshot() {
+        let ws_url = spawn_ws_server(vec![
+            diff_frame(1, 5, "99.0"),
+            diff_frame(6, 8, "100.0"),
+            diff_frame(9, 10, "101.0"),
+        ])
+        .await;
+        let snapshot = r#"{"lastUpdateId":7,"bids":[["98.0","2.0"]],"asks":[["102.0","1.5"]]}"#;
+        let rest_url = spawn_http_server(vec![(
+            "/api/v3/depth?symbol=BTCUSDT&limit=1000",
+            snapshot.to_string(),
+        )])
+        .await;
+
+        let storage = Arc::new(RwLock::new(MarketStorage::new(100)));
+        let (mut ws_stream, _) = connect_async(ws_url.as_str()).await.unwrap();
+        while let Some(Ok(message)) = ws_stream.next().await {
+            let Ok(text) = message.to_text() else {
+                continue;
+            };
+            if let Ok(Some(frame)) = parse_frame(text) {
+                dispatch_frame(frame, &storage);
+                request_snapshots(&storage, &format!("{}/api/v3", rest_url));
+            }
+        }
+
+        // Wait for the snapshot task to replay the buffered events
+        for _ in 0..100 {
+            if storage
+                .read()
+                .unwrap()
+                .order_books
+                .get_book("BTCUSDT")
+                .unwrap()
+                .is_synced()
+            {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let storage = storage.read().unwrap();
+        let sync = storage.order_books.get_book("BTCUSDT").unwrap();
+        assert!(sync.is_synced());
+        let book = sync.book();
+        assert_eq!(book.last_update_id, 10);
+        // The first event is older than the snapshot and must not be applied
+        assert_eq!(book.level_count(), (3, 1));
+        assert_eq!(book.best_bid().unwrap().price, 101.0);
+        assert_eq!(book.best_ask().unwrap().price, 102.0);
+    }
+}
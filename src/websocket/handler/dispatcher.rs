@@ -0,0 +1,136 @@
+use crate::storage::market_storage::MarketStorage;
+use crate::websocket::handler::aggtrade_handler::{draw_agg_trades, handle_agg_trade};
+use crate::websocket::handler::book_ticker_handler::{draw_book_tickers, handle_book_ticker};
+use crate::websocket::handler::depth_handler::{
+    draw_depth, draw_order_book, handle_depth_update, handle_partial_depth,
+};
+use crate::websocket::handler::futures_handler::{
+    draw_futures, handle_composite_index, handle_liquidation, handle_mark_price,
+};
+use crate::websocket::handler::kline_handler::{draw_klines, handle_kline};
+use crate::websocket::handler::performance::PerformanceMetrics;
+use crate::websocket::handler::ticker_handler::{draw_tickers, handle_ticker};
+use crate::websocket::handler::trade_handler::{draw_trades, handle_trade};
+use crate::websocket::handler::user_data_handler::draw_my_orders;
+use crate::websocket::handler::window_ticker_handler::{draw_window_tickers, handle_window_ticker};
+use crate::websocket::message::{MarketEvent, StreamFrame};
+use std::sync::{Arc, RwLock};
+
+/// Dashboard views, one per displayed stream type
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum View {
+    AggTrade,
+    Trade,
+    Kline,
+    Depth,
+    OrderBook,
+    BookTicker,
+    Market,
+    RollingWindow,
+    Futures,
+    /// The account's orders and balances from the user data stream
+    MyOrders,
+}
+
+/// Routes a decoded frame to the storage for its event type.
+/// Returns the view showing the event, if any.
+pub fn dispatch_frame(frame: StreamFrame, storage: &Arc<RwLock<MarketStorage>>) -> Option<View> {
+    let stream_symbol = frame.stream_symbol();
+    dispatch_event(frame.event, stream_symbol, storage)
+}
+
+fn dispatch_event(
+    event: MarketEvent,
+    stream_symbol: Option<String>,
+    storage: &Arc<RwLock<MarketStorage>>,
+) -> Option<View> {
+    match event {
+        MarketEvent::AggTrade(agg_trade) => {
+            handle_agg_trade(agg_trade, storage);
+            Some(View::AggTrade)
+        }
+        MarketEvent::Trade(trade) => {
+            handle_trade(trade, storage);
+            Some(View::Trade)
+        }
+        MarketEvent::Kline(event) => {
+            handle_kline(event.kline, storage);
+            Some(View::Kline)
+        }
+        MarketEvent::PartialDepth(snapshot) => {
+            // Without a stream name there is no way to tell which book this is
+            handle_partial_depth(stream_symbol?, snapshot, storage);
+            Some(View::Depth)
+        }
+        MarketEvent::DepthUpdate(update) => {
+            handle_depth_update(update, storage);
+            Some(View::OrderBook)
+        }
+        MarketEvent::BookTicker(book_ticker) => {
+            handle_book_ticker(book_ticker, storage);
+            Some(View::BookTicker)
+        }
+        MarketEvent::Ticker(ticker) => {
+            handle_ticker((&ticker).into(), storage);
+            Some(View::Market)
+        }
+        MarketEvent::MiniTicker(ticker) => {
+            handle_ticker((&ticker).into(), storage);
+            Some(View::Market)
+        }
+        MarketEvent::WindowTicker(ticker) => {
+            handle_window_ticker(ticker, storage);
+            Some(View::RollingWindow)
+        }
+        MarketEvent::MarkPrice(mark_price) => {
+            handle_mark_price(mark_price, storage);
+            Some(View::Futures)
+        }
+        MarketEvent::Liquidation(liquidation) => {
+            handle_liquidation(liquidation, storage);
+            Some(View::Futures)
+        }
+        MarketEvent::CompositeIndex(index) => {
+            handle_composite_index(index, storage);
+            Some(View::Futures)
+        }
+        MarketEvent::Batch(events) => events.into_iter().fold(None, |view, event| {
+            dispatch_event(event, stream_symbol.clone(), storage).or(view)
+        }),
+    }
+}
+
+/// Draws the dashboard for a view
+pub fn draw_view(
+    f: &mut ratatui::Frame,
+    view: View,
+    storage: &MarketStorage,
+    performance: &PerformanceMetrics,
+) {
+    match view {
+        View::AggTrade => {
+            let symbol = storage
+                .agg_trades
+                .get_trades()
+                .back()
+                .map(|trade| &trade.symbol);
+            let depth = symbol.and_then(|symbol| storage.depth.get_snapshot(symbol));
+            let quote = symbol.and_then(|symbol| storage.book_tickers.get_quote(symbol));
+            draw_agg_trades(f, &storage.agg_trades, depth, quote, performance)
+        }
+        View::Trade => {
+            let symbol = storage.trades.last_trade().map(|trade| &trade.symbol);
+            let depth = symbol.and_then(|symbol| storage.depth.get_snapshot(symbol));
+            let quote = symbol.and_then(|symbol| storage.book_tickers.get_quote(symbol));
+            draw_trades(f, &storage.trades, depth, quote, performance)
+        }
+        View::Kline => draw_klines(f, &storage.klines, performance),
+        View::Depth => draw_depth(f, &storage.depth, performance),
+        View::OrderBook => draw_order_book(f, &storage.order_books, performance),
+        View::BookTicker => draw_book_tickers(f, &storage.book_tickers, performance),
+        View::Market => draw_tickers(f, &storage.tickers, performance),
+        View::RollingWindow => draw_window_tickers(f, &storage.window_tickers, performance),
+        View::Futures => draw_futures(f, &storage.futures, performance),
+        View::MyOrders => draw_my_orders(f, &storage.user_data, performance),
+    }
+}
@@ -0,0 +1,55 @@
+use crate::storage::futures_storage::FuturesStorage;
+use crate::storage::market_storage::MarketStorage;
+use crate::ui::render::{render_futures_ui, FuturesRenderData};
+use crate::websocket::handler::performance::PerformanceMetrics;
+use crate::websocket::message::{CompositeIndex, Liquidation, MarkPrice};
+use chrono::Utc;
+use std::sync::{Arc, RwLock};
+
+/// Stores an incoming mark price and funding update
+pub fn handle_mark_price(mark_price: MarkPrice, storage: &Arc<RwLock<MarketStorage>>) {
+    let mut storage = storage.write().unwrap();
+    storage.futures.update_mark_price(mark_price);
+}
+
+/// Adds an incoming liquidation to the tape
+pub fn handle_liquidation(liquidation: Liquidation, storage: &Arc<RwLock<MarketStorage>>) {
+    let mut storage = storage.write().unwrap();
+    storage.futures.add_liquidation(liquidation);
+}
+
+/// Stores an incoming composite index update
+pub fn handle_composite_index(index: CompositeIndex, storage: &Arc<RwLock<MarketStorage>>) {
+    let mut storage = storage.write().unwrap();
+    storage.futures.update_index(index);
+}
+
+/// Draws the futures dashboard: mark prices and funding, liquidations and composite indexes
+pub fn draw_futures(
+    f: &mut ratatui::Frame,
+    storage: &FuturesStorage,
+    performance: &PerformanceMetrics,
+) {
+    let current = storage
+        .last_updated_symbol()
+        .and_then(|symbol| storage.get_mark_price(symbol));
+    let mark_history = current
+        .map(|mark_price| storage.mark_history(&mark_price.symbol))
+        .unwrap_or_default();
+    let mark_prices = storage.mark_prices_by_funding();
+    let liquidations: Vec<&Liquidation> = storage.liquidations().iter().rev().take(30).collect();
+
+    // Create FuturesRenderData
+    let render_data = FuturesRenderData {
+        current,
+        mark_prices: &mark_prices,
+        mark_history: &mark_history,
+        liquidations: &liquidations,
+        liquidated_notional: storage.liquidated_notional(),
+        index: storage.last_index(),
+        now: Utc::now(),
+        performance: performance.render_data(),
+    };
+
+    render_futures_ui(f, &render_data);
+}
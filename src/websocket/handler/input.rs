@@ -1,22 +1,153 @@
 use crossterm::event::{self, Event as CEvent, KeyCode};
 use tokio::sync::mpsc;
 
-pub async fn handle_input(shutdown_tx: &mpsc::Sender<()>) {
+/// Key presses the dashboard reacts to
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputEvent {
+    Quit,
+    NextView,
+    NextSort,
+    /// The stream prompt was opened, edited or cancelled
+    Prompt(Option<String>),
+    Subscribe(String),
+    Unsubscribe(String),
+    ListSubscriptions,
+    ShowCombined,
+}
+
+// Stream name being typed after pressing 'a' (subscribe) or 'u' (unsubscribe)
+struct StreamPrompt {
+    subscribe: bool,
+    text: String,
+}
+
+impl StreamPrompt {
+    // Text shown on the dashboard while typing
+    fn label(&self) -> String {
+        let action = if self.subscribe {
+            "Subscribe"
+        } else {
+            "Unsubscribe"
+        };
+        format!("{}: {}_", action, self.text)
+    }
+}
+
+// Apply a key press to the open prompt, closing it on Enter or Esc
+fn edit_prompt(prompt: &mut Option<StreamPrompt>, key: KeyCode) -> Option<InputEvent> {
+    let editing = prompt.as_mut()?;
+    match key {
+        KeyCode::Char(c) => editing.text.push(c),
+        KeyCode::Backspace => {
+            editing.text.pop();
+        }
+        KeyCode::Esc => {
+            *prompt = None;
+            return Some(InputEvent::Prompt(None));
+        }
+        KeyCode::Enter => {
+            let StreamPrompt { subscribe, text } = prompt.take()?;
+            let stream = text.trim().to_string();
+            if stream.is_empty() {
+                return Some(InputEvent::Prompt(None));
+            }
+            return Some(match subscribe {
+                true => InputEvent::Subscribe(stream),
+                false => InputEvent::Unsubscribe(stream),
+            });
+        }
+        _ => return None,
+    }
+    Some(InputEvent::Prompt(Some(editing.label())))
+}
+
+pub async fn handle_input(input_tx: &mpsc::Sender<InputEvent>) {
+    let mut prompt: Option<StreamPrompt> = None;
     loop {
         // Poll for events every 10 milliseconds
         if event::poll(std::time::Duration::from_millis(10)).unwrap() {
             // Read the event
             if let CEvent::Key(key) = event::read().unwrap() {
-                // Check if the 'q' key is pressed
-                if key.code == KeyCode::Char('q') {
-                    // Send shutdown signal
-                    shutdown_tx
-                        .send(())
-                        .await
-                        .expect("failed to send shutdown signal");
-                    break;
+                // While a stream name is typed every key belongs to the prompt
+                if prompt.is_some() {
+                    if let Some(event) = edit_prompt(&mut prompt, key.code) {
+                        if input_tx.send(event).await.is_err() {
+                            break;
+                        }
+                    }
+                    continue;
+                }
+                match key.code {
+                    // Check if the 'q' key is pressed
+                    KeyCode::Char('q') => {
+                        // Send shutdown signal
+                        input_tx
+                            .send(InputEvent::Quit)
+                            .await
+                            .expect("failed to send shutdown signal");
+                        break;
+                    }
+                    // Switch to the next dashboard view, stopping once the dashboard is gone
+                    KeyCode::Tab if input_tx.send(InputEvent::NextView).await.is_err() => break,
+                    // Change the order of the market overview table
+                    KeyCode::Char('s') if input_tx.send(InputEvent::NextSort).await.is_err() => {
+                        break
+                    }
+                    // Ask the server which streams and payload format are active
+                    KeyCode::Char('l')
+                        if input_tx.send(InputEvent::ListSubscriptions).await.is_err() =>
+                    {
+                        break
+                    }
+                    KeyCode::Char('p')
+                        if input_tx.send(InputEvent::ShowCombined).await.is_err() =>
+                    {
+                        break
+                    }
+                    // Start typing a stream to add or remove
+                    KeyCode::Char(c @ ('a' | 'u')) => {
+                        let opened = StreamPrompt {
+                            subscribe: c == 'a',
+                            text: String::new(),
+                        };
+                        let event = InputEvent::Prompt(Some(opened.label()));
+                        prompt = Some(opened);
+                        if input_tx.send(event).await.is_err() {
+                            break;
+                        }
+                    }
+                    _ => {}
                 }
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prompt_collects_stream_name() {
+        let mut prompt = Some(StreamPrompt {
+            subscribe: true,
+            text: String::new(),
+        });
+        for c in "ethusdq".chars() {
+            edit_prompt(&mut prompt, KeyCode::Char(c));
+        }
+        assert_eq!(
+            edit_prompt(&mut prompt, KeyCode::Backspace),
+            Some(InputEvent::Prompt(Some("Subscribe: ethusd_".to_string())))
+        );
+        edit_prompt(&mut prompt, KeyCode::Char('t'));
+        assert_eq!(
+            edit_prompt(&mut prompt, KeyCode::Enter),
+            Some(InputEvent::Subscribe("ethusdt".to_string()))
+        );
+        assert!(prompt.is_none());
+
+        // Keys are ignored once the prompt is closed
+        assert_eq!(edit_prompt(&mut prompt, KeyCode::Char('q')), None);
+    }
+}
@@ -0,0 +1,50 @@
+use crate::storage::kline_storage::{Kline, KlineStorage};
+use crate::storage::market_storage::MarketStorage;
+use crate::ui::render::{render_kline_ui, KlineRenderData};
+use crate::websocket::handler::performance::PerformanceMetrics;
+use std::sync::{Arc, RwLock};
+
+/// Stores an incoming kline update
+pub fn handle_kline(kline: Kline, storage: &Arc<RwLock<MarketStorage>>) {
+    let mut storage = storage.write().unwrap();
+    storage.klines.add_kline(kline);
+}
+
+/// Draws the candlestick dashboard for the most recently updated series
+pub fn draw_klines(
+    f: &mut ratatui::Frame,
+    storage: &KlineStorage,
+    performance: &PerformanceMetrics,
+) {
+    let Some(series) = storage.last_updated_series() else {
+        return;
+    };
+    let candles = series.get_candles().iter().cloned().collect::<Vec<_>>();
+    let (symbol, interval) = candles
+        .last()
+        .map(|kline| (kline.symbol.as_str(), kline.interval.as_str()))
+        .unwrap_or_default();
+    let in_contracts = storage.counts_contracts(symbol);
+
+    // Create KlineRenderData
+    let render_data = KlineRenderData {
+        candles: &candles,
+        current: series.open_candle().or(series.get_candles().back()),
+        symbol,
+        interval,
+        max_price: series.calculate_max_price().unwrap_or(0.0),
+        min_price: series.calculate_min_price().unwrap_or(0.0),
+        // Base volume rather than contracts for COIN-M
+        total_volume: if in_contracts {
+            series.total_quote_volume()
+        } else {
+            series.total_volume()
+        },
+        in_contracts,
+        taker_buy_ratio: series.calculate_taker_buy_ratio().unwrap_or(0.0),
+        closed_count: series.closed_candles().count(),
+        performance: performance.render_data(),
+    };
+
+    render_kline_ui(f, &render_data);
+}
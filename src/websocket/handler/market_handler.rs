@@ -0,0 +1,231 @@
+use crate::endpoints::Endpoints;
+use crate::exchange;
+use crate::storage::market_storage::MarketStorage;
+use crate::ui::render::render_connection_ui;
+use crate::user_data::message::UserEvent;
+use crate::websocket::client::connection::{ConnectionCommand, ConnectionEvent};
+use crate::websocket::handler::aggtrade_handler::request_backfills;
+use crate::websocket::handler::depth_handler::request_snapshots;
+use crate::websocket::handler::dispatcher::{dispatch_frame, draw_view, View};
+use crate::websocket::handler::input::{handle_input, InputEvent};
+use crate::websocket::handler::performance::PerformanceMetrics;
+use crate::websocket::handler::user_data_handler::handle_user_event;
+use crossterm::{
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::backend::CrosstermBackend;
+use ratatui::Terminal;
+use std::io::{stdout, Stdout};
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
+use tokio::sync::mpsc;
+
+pub async fn handle_market_messages(
+    endpoints: &Endpoints,
+    events: &mut mpsc::Receiver<ConnectionEvent>,
+    user_events: &mut mpsc::Receiver<UserEvent>,
+    commands: &mpsc::Sender<ConnectionCommand>,
+    streams: &[String],
+    storage: &Arc<RwLock<MarketStorage>>,
+    shutdown_rx: &mut mpsc::Receiver<()>,
+) {
+    let exchange = exchange::for_endpoints(endpoints);
+    let api_url = endpoints.api_url();
+
+    // Initialize terminal
+    let mut stdout = stdout();
+    execute!(stdout, EnterAlternateScreen).unwrap();
+    enable_raw_mode().unwrap();
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend).unwrap();
+    terminal.clear().unwrap();
+
+    // Channel for user input
+    let (input_tx, mut input_rx) = mpsc::channel(1);
+    tokio::spawn(async move {
+        handle_input(&input_tx).await;
+    });
+
+    let mut performance = PerformanceMetrics::new();
+    performance.record_streams(streams.to_vec());
+    // Views that have received data, in arrival order, and the one on screen
+    let mut views: Vec<View> = Vec::new();
+    let mut active_view: Option<View> = None;
+    // Whether SET_PROPERTY combined has been sent on this dashboard
+    let mut combined = false;
+
+    'main_loop: loop {
+        tokio::select! {
+            // Handle incoming messages
+            event = events.recv() => {
+                match event {
+                    Some(ConnectionEvent::Text(text)) => {
+                        performance.record_arrival();
+                        let start_processing = Instant::now();
+                        let mut frame = match exchange.parse_frame(&text) {
+                            Ok(Some(frame)) => frame,
+                            // Replies to control messages carry no market data
+                            Ok(None) => continue,
+                            Err(_) => {
+                                performance.record_decode_error();
+                                continue;
+                            }
+                        };
+
+                        // Raw connections carry a single stream and no envelope
+                        if frame.stream.is_none() && performance.streams.len() == 1 {
+                            frame.stream = Some(performance.streams[0].clone());
+                        }
+                        performance.record_source(frame.source());
+
+                        // Update the storage matching the event type
+                        let Some(kind) = dispatch_frame(frame, storage) else {
+                            continue;
+                        };
+                        if kind == View::OrderBook {
+                            request_snapshots(storage, &api_url);
+                        }
+                        if kind == View::AggTrade {
+                            request_backfills(storage, &api_url);
+                            let agg_trades = &storage.read().unwrap().agg_trades;
+                            performance.record_backfills(
+                                agg_trades.gap_count,
+                                agg_trades.backfilled_count,
+                                agg_trades.backfill_failures,
+                            );
+                        }
+                        if !views.contains(&kind) {
+                            views.push(kind);
+                        }
+                        let view = *active_view.get_or_insert(kind);
+
+                        // Calculate performance metrics
+                        performance.record_processing(start_processing.elapsed());
+
+                        // Draw UI
+                        let storage = storage.read().unwrap();
+                        terminal.draw(|f| draw_view(f, view, &storage, &performance)).unwrap();
+                    }
+                    Some(ConnectionEvent::State(state)) => {
+                        performance.record_connection_state(state);
+                        redraw(&mut terminal, active_view, storage, &performance);
+                    }
+                    Some(ConnectionEvent::Streams(streams)) => {
+                        performance.record_streams(streams);
+                        redraw(&mut terminal, active_view, storage, &performance);
+                    }
+                    Some(ConnectionEvent::Reply { command, result }) => {
+                        let listed = match (&command, &result) {
+                            (ConnectionCommand::ListSubscriptions, Ok(list)) => {
+                                serde_json::from_value::<Vec<String>>(list.clone()).ok()
+                            }
+                            _ => None,
+                        };
+                        match listed {
+                            // Kept in storage so the menu can show it once the dashboard closes
+                            Some(confirmed) => {
+                                storage.write().unwrap().subscriptions = confirmed.clone();
+                                performance.record_confirmed_streams(confirmed);
+                            }
+                            None => performance.record_reply(&command, &result),
+                        }
+                        redraw(&mut terminal, active_view, storage, &performance);
+                    }
+                    Some(ConnectionEvent::Deliveries(deliveries)) => {
+                        performance.record_deliveries(deliveries);
+                    }
+                    // The connection has shut down
+                    None => break 'main_loop,
+                }
+            },
+            // Show the account's orders next to the market data; closed without an API key
+            Some(event) = user_events.recv() => {
+                handle_user_event(event, storage);
+                if !views.contains(&View::MyOrders) {
+                    views.push(View::MyOrders);
+                }
+                active_view.get_or_insert(View::MyOrders);
+                redraw(&mut terminal, active_view, storage, &performance);
+            }
+            // Handle shutdown signal
+            _ = shutdown_rx.recv() => {
+                println!("Received shutdown signal.");
+                break 'main_loop;
+            },
+            // Handle user input
+            Some(input) = input_rx.recv() => match input {
+                InputEvent::Quit => {
+                    println!("Received input shutdown signal.");
+                    break 'main_loop;
+                }
+                InputEvent::NextView => {
+                    // Cycle through the views that have data
+                    if let Some(current) = active_view {
+                        let position = views.iter().position(|&kind| kind == current).unwrap_or(0);
+                        let view = views[(position + 1) % views.len()];
+                        active_view = Some(view);
+
+                        let storage = storage.read().unwrap();
+                        terminal.draw(|f| draw_view(f, view, &storage, &performance)).unwrap();
+                    }
+                }
+                InputEvent::NextSort => {
+                    storage.write().unwrap().tickers.cycle_sort();
+                    if let Some(view) = active_view {
+                        let storage = storage.read().unwrap();
+                        terminal.draw(|f| draw_view(f, view, &storage, &performance)).unwrap();
+                    }
+                }
+                InputEvent::Prompt(prompt) => {
+                    performance.record_prompt(prompt);
+                    redraw(&mut terminal, active_view, storage, &performance);
+                }
+                InputEvent::Subscribe(stream) => {
+                    performance.record_prompt(None);
+                    // With several streams every payload needs its stream name to be routed;
+                    // venues that always send it have no such request
+                    let set_combined = ConnectionCommand::SetCombined(true);
+                    if !combined && exchange.request_message(&set_combined, 0).is_some() {
+                        combined = true;
+                        let _ = commands.send(set_combined).await;
+                    }
+                    let _ = commands.send(ConnectionCommand::Subscribe(vec![stream])).await;
+                    redraw(&mut terminal, active_view, storage, &performance);
+                }
+                InputEvent::ListSubscriptions => {
+                    let _ = commands.send(ConnectionCommand::ListSubscriptions).await;
+                }
+                InputEvent::ShowCombined => {
+                    let property = ConnectionCommand::GetProperty("combined".to_string());
+                    let _ = commands.send(property).await;
+                }
+                InputEvent::Unsubscribe(stream) => {
+                    performance.record_prompt(None);
+                    let _ = commands.send(ConnectionCommand::Unsubscribe(vec![stream])).await;
+                    redraw(&mut terminal, active_view, storage, &performance);
+                }
+            },
+        }
+    }
+
+    // Restore terminal
+    disable_raw_mode().unwrap();
+    execute!(terminal.backend_mut(), LeaveAlternateScreen).unwrap();
+    terminal.show_cursor().unwrap();
+}
+
+// Draw the active view again, or the connection state while no data has arrived yet
+fn redraw(
+    terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+    active_view: Option<View>,
+    storage: &Arc<RwLock<MarketStorage>>,
+    performance: &PerformanceMetrics,
+) {
+    let storage = storage.read().unwrap();
+    match active_view {
+        Some(view) => terminal.draw(|f| draw_view(f, view, &storage, performance)),
+        None => terminal.draw(|f| render_connection_ui(f, &performance.render_data())),
+    }
+    .unwrap();
+}
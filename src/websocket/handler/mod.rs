@@ -1,2 +1,13 @@
 pub mod aggtrade_handler;
+pub mod book_ticker_handler;
+pub mod depth_handler;
+pub mod dispatcher;
+pub mod futures_handler;
 pub mod input;
+pub mod kline_handler;
+pub mod market_handler;
+pub mod performance;
+pub mod ticker_handler;
+pub mod trade_handler;
+pub mod user_data_handler;
+pub mod window_ticker_handler;
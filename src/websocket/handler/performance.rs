@@ -0,0 +1,175 @@
+use crate::ui::render::PerformanceData;
+use crate::websocket::client::connection::{ConnectionCommand, ConnectionState};
+use crate::websocket::client::redundant::DeliveryStats;
+use crate::websocket::client::requests::RequestError;
+use serde_json::Value;
+use std::time::{Duration, Instant};
+
+/// Tracks message arrival intervals and processing times for the performance panels
+pub struct PerformanceMetrics {
+    pub message_count: u64,
+    pub decode_errors: u64,
+    pub last_source: String,
+    pub connection_state: ConnectionState,
+    pub reconnect_count: u64,
+    pub streams: Vec<String>,
+    pub prompt: Option<String>,
+    pub last_request: Option<String>,
+    pub confirmed_streams: Option<Vec<String>>,
+    pub deliveries: Vec<DeliveryStats>,
+    pub gap_count: u64,
+    pub backfilled_count: u64,
+    pub backfill_failures: u64,
+    connected_once: bool,
+    total_processing_time: Duration,
+    total_arrival_time: Duration,
+    last_message_time: Instant,
+    pub arrival_intervals: Vec<(f64, f64)>,
+    pub processing_times: Vec<(f64, f64)>,
+}
+
+impl PerformanceMetrics {
+    pub fn new() -> Self {
+        Self {
+            message_count: 0,
+            decode_errors: 0,
+            last_source: String::new(),
+            connection_state: ConnectionState::Connecting,
+            reconnect_count: 0,
+            streams: Vec::new(),
+            prompt: None,
+            last_request: None,
+            confirmed_streams: None,
+            deliveries: Vec::new(),
+            gap_count: 0,
+            backfilled_count: 0,
+            backfill_failures: 0,
+            connected_once: false,
+            total_processing_time: Duration::new(0, 0),
+            total_arrival_time: Duration::new(0, 0),
+            last_message_time: Instant::now(),
+            arrival_intervals: Vec::new(),
+            processing_times: Vec::new(),
+        }
+    }
+
+    // Record the time elapsed since the previous message arrived
+    pub fn record_arrival(&mut self) {
+        self.total_arrival_time += self.last_message_time.elapsed();
+        self.last_message_time = Instant::now();
+    }
+
+    // Record the processing time of a message and update the history
+    pub fn record_processing(&mut self, processing_time: Duration) {
+        self.total_processing_time += processing_time;
+        self.message_count += 1;
+
+        let avg_arrival_interval = self.avg_arrival_interval();
+        let avg_processing_time = self.avg_processing_time();
+        self.arrival_intervals
+            .push((self.message_count as f64, avg_arrival_interval));
+        self.processing_times
+            .push((self.message_count as f64, avg_processing_time));
+    }
+
+    // Record a frame that could not be decoded
+    pub fn record_decode_error(&mut self) {
+        self.decode_errors += 1;
+    }
+
+    // Record the stream the latest frame came from
+    pub fn record_source(&mut self, source: String) {
+        self.last_source = source;
+    }
+
+    // Record a change of the connection state, counting every connection after the first
+    pub fn record_connection_state(&mut self, state: ConnectionState) {
+        if state == ConnectionState::Connected {
+            if self.connected_once {
+                self.reconnect_count += 1;
+            }
+            self.connected_once = true;
+        }
+        self.connection_state = state;
+    }
+
+    // Record the streams the connection is subscribed to
+    pub fn record_streams(&mut self, streams: Vec<String>) {
+        self.streams = streams;
+    }
+
+    // Record the stream prompt being typed, if any
+    pub fn record_prompt(&mut self, prompt: Option<String>) {
+        self.prompt = prompt;
+    }
+
+    // Record the outcome of the latest request, with the value it returned if any
+    pub fn record_reply(
+        &mut self,
+        command: &ConnectionCommand,
+        result: &Result<Value, RequestError>,
+    ) {
+        let outcome = match result {
+            Ok(Value::Null) => "ok".to_string(),
+            Ok(value) => value.to_string(),
+            Err(error) => format!("failed, {}", error),
+        };
+        self.last_request = Some(format!("{}: {}", command, outcome));
+    }
+
+    // Record the streams the server listed as subscribed
+    pub fn record_confirmed_streams(&mut self, streams: Vec<String>) {
+        self.confirmed_streams = Some(streams);
+    }
+
+    // Record which redundant connection delivered events first
+    pub fn record_deliveries(&mut self, deliveries: Vec<DeliveryStats>) {
+        self.deliveries = deliveries;
+    }
+
+    // Record the aggTrade sequence gaps found and the trades fetched to fill them
+    pub fn record_backfills(&mut self, gaps: u64, backfilled: u64, failures: u64) {
+        self.gap_count = gaps;
+        self.backfilled_count = backfilled;
+        self.backfill_failures = failures;
+    }
+
+    // Average interval between messages in milliseconds
+    pub fn avg_arrival_interval(&self) -> f64 {
+        if self.message_count == 0 {
+            return 0.0;
+        }
+        self.total_arrival_time.as_secs_f64() * 1000.0 / self.message_count as f64
+    }
+
+    // Average processing time per message in milliseconds
+    pub fn avg_processing_time(&self) -> f64 {
+        if self.message_count == 0 {
+            return 0.0;
+        }
+        self.total_processing_time.as_secs_f64() * 1000.0 / self.message_count as f64
+    }
+
+    // Borrow the metrics in the shape expected by the renderer
+    pub fn render_data(&self) -> PerformanceData<'_> {
+        PerformanceData {
+            message_count: self.message_count,
+            decode_errors: self.decode_errors,
+            last_source: &self.last_source,
+            connection_state: &self.connection_state,
+            reconnect_count: self.reconnect_count,
+            streams: &self.streams,
+            prompt: self.prompt.as_deref(),
+            last_request: self.last_request.as_deref(),
+            confirmed_streams: self.confirmed_streams.as_deref(),
+            deliveries: &self.deliveries,
+            gap_count: self.gap_count,
+            backfilled_count: self.backfilled_count,
+            backfill_failures: self.backfill_failures,
+            avg_arrival_interval: self.avg_arrival_interval(),
+            avg_processing_time: self.avg_processing_time(),
+            arrival_intervals: &self.arrival_intervals,
+            processing_times: &self.processing_times,
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use crate::storage::market_storage::MarketStorage;
+use crate::storage::ticker_storage::{TickerStorage, TickerSummary};
+use crate::ui::render::{render_ticker_ui, TickerRenderData};
+use crate::websocket::handler::performance::PerformanceMetrics;
+use std::sync::{Arc, RwLock};
+
+/// Stores the latest 24h statistics for a symbol
+pub fn handle_ticker(summary: TickerSummary, storage: &Arc<RwLock<MarketStorage>>) {
+    let mut storage = storage.write().unwrap();
+    storage.tickers.update(summary);
+}
+
+/// Draws the market overview table in the selected sort order
+pub fn draw_tickers(
+    f: &mut ratatui::Frame,
+    storage: &TickerStorage,
+    performance: &PerformanceMetrics,
+) {
+    let tickers = storage.sorted_tickers();
+
+    // Calculate market breadth
+    let gainers = tickers
+        .iter()
+        .filter(|ticker| ticker.price_change_percent > 0.0)
+        .count();
+    let losers = tickers
+        .iter()
+        .filter(|ticker| ticker.price_change_percent < 0.0)
+        .count();
+
+    // Create TickerRenderData
+    let render_data = TickerRenderData {
+        tickers: &tickers,
+        sort_label: storage.sort().label(),
+        quote_filter: storage.quote_filter(),
+        gainers,
+        losers,
+        total_quote_volume: tickers.iter().map(|ticker| ticker.quote_volume).sum(),
+        last_event_time: tickers.iter().map(|ticker| ticker.event_time).max(),
+        performance: performance.render_data(),
+    };
+
+    render_ticker_ui(f, &render_data);
+}
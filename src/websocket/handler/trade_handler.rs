@@ -0,0 +1,56 @@
+use crate::storage::book_ticker_storage::QuoteSeries;
+use crate::storage::depth_storage::DepthSnapshot;
+use crate::storage::market_storage::MarketStorage;
+use crate::storage::trade_storage::{Trade, TradeStorage};
+use crate::ui::render::{render_trade_ui, TradeRenderData};
+use crate::websocket::handler::book_ticker_handler::quote_data;
+use crate::websocket::handler::performance::PerformanceMetrics;
+use std::sync::{Arc, RwLock};
+
+/// Stores an incoming trade
+pub fn handle_trade(trade: Trade, storage: &Arc<RwLock<MarketStorage>>) {
+    let mut storage = storage.write().unwrap();
+    storage.trades.add_trade(trade);
+}
+
+/// Draws the trade dashboard from the current storage contents
+pub fn draw_trades(
+    f: &mut ratatui::Frame,
+    storage: &TradeStorage,
+    depth: Option<&DepthSnapshot>,
+    quote: Option<&QuoteSeries>,
+    performance: &PerformanceMetrics,
+) {
+    // Calculate statistics
+    let last_price = storage.last_trade().map(|trade| trade.price).unwrap_or(0.0);
+    let trades = storage
+        .get_trades()
+        .iter()
+        .rev()
+        .take(20)
+        .cloned()
+        .collect::<Vec<_>>();
+    let prices = storage
+        .get_trades()
+        .iter()
+        .map(|trade| (trade.timestamp.timestamp_millis() as f64, trade.price))
+        .collect::<Vec<_>>();
+
+    // Create TradeRenderData
+    let render_data = TradeRenderData {
+        trades: &trades,
+        avg_price: storage.calculate_average_price().unwrap_or(0.0),
+        total_volume: storage.total_volume(),
+        volume_weighted_avg_price: storage.calculate_vwap().unwrap_or(0.0),
+        max_price: storage.calculate_max_price().unwrap_or(0.0),
+        min_price: storage.calculate_min_price().unwrap_or(0.0),
+        last_price,
+        prices: &prices,
+        buyer_maker_count: storage.calculate_buyer_maker_count(),
+        depth,
+        quote: quote.map(quote_data),
+        performance: performance.render_data(),
+    };
+
+    render_trade_ui(f, &render_data);
+}
@@ -0,0 +1,42 @@
+use crate::storage::market_storage::MarketStorage;
+use crate::storage::user_data_storage::UserDataStorage;
+use crate::ui::render::{render_my_orders_ui, UserDataRenderData};
+use crate::user_data::message::{BalanceUpdate, ExecutionReport, UserEvent};
+use crate::websocket::handler::performance::PerformanceMetrics;
+use std::sync::{Arc, RwLock};
+
+/// Stores an incoming order, balance or account event from the user data stream
+pub fn handle_user_event(event: UserEvent, storage: &Arc<RwLock<MarketStorage>>) {
+    let mut storage = storage.write().unwrap();
+    match event {
+        UserEvent::ExecutionReport(report) => storage.user_data.add_execution_report(report),
+        UserEvent::AccountPosition(position) => storage.user_data.update_account(position),
+        UserEvent::BalanceUpdate(update) => storage.user_data.add_balance_update(update),
+        // Handled by the stream, which replaces the listenKey
+        UserEvent::ListenKeyExpired => {}
+    }
+}
+
+/// Draws the "My Orders" dashboard: open orders, fills and balances
+pub fn draw_my_orders(
+    f: &mut ratatui::Frame,
+    storage: &UserDataStorage,
+    performance: &PerformanceMetrics,
+) {
+    let open_orders = storage.open_orders();
+    let fills: Vec<&ExecutionReport> = storage.fills().iter().rev().take(30).collect();
+    let balances = storage.balances();
+    let balance_updates: Vec<&BalanceUpdate> =
+        storage.balance_updates().iter().rev().take(30).collect();
+
+    // Create UserDataRenderData
+    let render_data = UserDataRenderData {
+        open_orders: &open_orders,
+        fills: &fills,
+        balances: &balances,
+        balance_updates: &balance_updates,
+        performance: performance.render_data(),
+    };
+
+    render_my_orders_ui(f, &render_data);
+}
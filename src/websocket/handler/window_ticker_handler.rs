@@ -0,0 +1,33 @@
+use crate::storage::market_storage::MarketStorage;
+use crate::storage::window_ticker_storage::WindowTickerStorage;
+use crate::ui::render::{render_window_ticker_ui, WindowTickerRenderData};
+use crate::websocket::handler::performance::PerformanceMetrics;
+use crate::websocket::message::WindowTicker;
+use std::sync::{Arc, RwLock};
+
+/// Stores incoming rolling window statistics
+pub fn handle_window_ticker(ticker: WindowTicker, storage: &Arc<RwLock<MarketStorage>>) {
+    let mut storage = storage.write().unwrap();
+    storage.window_tickers.add_ticker(ticker);
+}
+
+/// Draws the window comparison dashboard for the most recently updated symbol
+pub fn draw_window_tickers(
+    f: &mut ratatui::Frame,
+    storage: &WindowTickerStorage,
+    performance: &PerformanceMetrics,
+) {
+    let Some(symbol) = storage.last_updated_symbol() else {
+        return;
+    };
+    let windows = storage.get_windows(symbol);
+
+    // Create WindowTickerRenderData
+    let render_data = WindowTickerRenderData {
+        symbol,
+        windows: &windows,
+        performance: performance.render_data(),
+    };
+
+    render_window_ticker_ui(f, &render_data);
+}
@@ -1,21 +1,620 @@
 use crate::storage::aggtrade_storage::AggTrade;
-use chrono::{TimeZone, Utc};
+use crate::storage::depth_storage::DepthSnapshot;
+use crate::storage::kline_storage::Kline;
+use crate::storage::trade_storage::Trade;
+use crate::websocket::deserialize::{
+    datetime_from_millis, f64_from_str, optional_f64_from_str, PriceLevel,
+};
+use chrono::{DateTime, Utc};
+use serde::de::{self, Deserializer};
+use serde::Deserialize;
 use serde_json::Value;
 
-pub fn parse_agg_trade(data: &Value) -> Option<AggTrade> {
-    // Extract timestamp and convert to DateTime
-    let timestamp = data.get("T")?.as_u64()?;
-    let datetime = Utc.timestamp_millis_opt(timestamp as i64).single()?;
-
-    // Create and return AggTrade struct
-    Some(AggTrade {
-        symbol: data.get("s")?.as_str()?.to_string(),  // Symbol
-        trade_id: data.get("a")?.as_u64()?,            // Trade ID
-        price: data.get("p")?.as_str()?.parse().ok()?, // Price
-        quantity: data.get("q")?.as_str()?.parse().ok()?, // Quantity
-        first_trade_id: data.get("f")?.as_u64()?,      // First trade ID
-        last_trade_id: data.get("l")?.as_u64()?,       // Last trade ID
-        timestamp: datetime,                           // Timestamp
-        is_buyer_maker: data.get("m")?.as_bool()?,     // Buyer maker flag
-    })
+/// A single decoded frame: the event and, for combined streams, the stream it came from
+#[derive(Debug, Clone)]
+pub struct StreamFrame {
+    pub stream: Option<String>,
+    pub event: MarketEvent,
+}
+
+impl StreamFrame {
+    // Name of the stream the frame came from, falling back to the event symbol
+    pub fn source(&self) -> String {
+        self.stream
+            .clone()
+            .or_else(|| self.event.symbol().map(str::to_lowercase))
+            .unwrap_or_default()
+    }
+
+    // Symbol of the frame's stream, e.g. `BTCUSDT` for `btcusdt@depth5@100ms`
+    pub fn stream_symbol(&self) -> Option<String> {
+        let stream = self.stream.as_deref()?;
+        let (symbol, _) = stream.split_once('@')?;
+        Some(symbol.to_uppercase())
+    }
+}
+
+/// Every market data event the client can decode
+#[derive(Debug, Clone)]
+pub enum MarketEvent {
+    AggTrade(AggTrade),
+    Trade(Trade),
+    Kline(KlineEvent),
+    DepthUpdate(DepthUpdate),
+    PartialDepth(DepthSnapshot),
+    BookTicker(BookTicker),
+    Ticker(Ticker),
+    MiniTicker(MiniTicker),
+    WindowTicker(WindowTicker),
+    MarkPrice(MarkPrice),
+    Liquidation(Liquidation),
+    CompositeIndex(CompositeIndex),
+    // All-market streams such as `!ticker@arr` deliver an array of events
+    Batch(Vec<MarketEvent>),
+}
+
+impl MarketEvent {
+    // Symbol the event refers to; partial depth payloads and batches do not carry one
+    pub fn symbol(&self) -> Option<&str> {
+        match self {
+            MarketEvent::AggTrade(event) => Some(&event.symbol),
+            MarketEvent::Trade(event) => Some(&event.symbol),
+            MarketEvent::Kline(event) => Some(&event.kline.symbol),
+            MarketEvent::DepthUpdate(event) => Some(&event.symbol),
+            MarketEvent::PartialDepth(_) => None,
+            MarketEvent::BookTicker(event) => Some(&event.symbol),
+            MarketEvent::Ticker(event) => Some(&event.symbol),
+            MarketEvent::MiniTicker(event) => Some(&event.symbol),
+            MarketEvent::WindowTicker(event) => Some(&event.symbol),
+            MarketEvent::MarkPrice(event) => Some(&event.symbol),
+            MarketEvent::Liquidation(event) => Some(&event.symbol),
+            MarketEvent::CompositeIndex(event) => Some(&event.symbol),
+            MarketEvent::Batch(_) => None,
+        }
+    }
+}
+
+/// Payloads tagged with an `e` event type
+#[derive(Deserialize)]
+#[serde(tag = "e")]
+enum TaggedEvent {
+    #[serde(rename = "aggTrade")]
+    AggTrade(AggTrade),
+    #[serde(rename = "trade")]
+    Trade(Trade),
+    #[serde(rename = "kline")]
+    Kline(KlineEvent),
+    #[serde(rename = "depthUpdate")]
+    DepthUpdate(DepthUpdate),
+    #[serde(rename = "bookTicker")]
+    BookTicker(BookTicker),
+    #[serde(rename = "24hrTicker")]
+    Ticker(Ticker),
+    #[serde(rename = "24hrMiniTicker")]
+    MiniTicker(MiniTicker),
+    #[serde(rename = "markPriceUpdate")]
+    MarkPrice(MarkPrice),
+    #[serde(rename = "forceOrder")]
+    ForceOrder(ForceOrder),
+    #[serde(rename = "continuous_kline")]
+    ContinuousKline(ContinuousKlineEvent),
+    #[serde(rename = "compositeIndex")]
+    CompositeIndex(CompositeIndex),
+}
+
+impl From<TaggedEvent> for MarketEvent {
+    fn from(event: TaggedEvent) -> Self {
+        match event {
+            TaggedEvent::AggTrade(event) => MarketEvent::AggTrade(event),
+            TaggedEvent::Trade(event) => MarketEvent::Trade(event),
+            TaggedEvent::Kline(event) => MarketEvent::Kline(event),
+            TaggedEvent::DepthUpdate(event) => MarketEvent::DepthUpdate(event),
+            TaggedEvent::BookTicker(event) => MarketEvent::BookTicker(event),
+            TaggedEvent::Ticker(event) => MarketEvent::Ticker(event),
+            TaggedEvent::MiniTicker(event) => MarketEvent::MiniTicker(event),
+            TaggedEvent::MarkPrice(event) => MarketEvent::MarkPrice(event),
+            TaggedEvent::ForceOrder(event) => MarketEvent::Liquidation(event.order),
+            // Continuous contract candles are stored like any other, keyed by pair and contract
+            TaggedEvent::ContinuousKline(mut event) => {
+                event.kline.symbol = format!("{}_{}", event.pair, event.contract_type);
+                MarketEvent::Kline(KlineEvent { kline: event.kline })
+            }
+            TaggedEvent::CompositeIndex(event) => MarketEvent::CompositeIndex(event),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for MarketEvent {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = Value::deserialize(deserializer)?;
+
+        if let Value::Array(events) = value {
+            return events
+                .into_iter()
+                .map(|event| MarketEvent::deserialize(event).map_err(de::Error::custom))
+                .collect::<Result<_, _>>()
+                .map(MarketEvent::Batch);
+        }
+
+        // Rolling window tickers name the window in the event type, e.g. `4hTicker`,
+        // unlike `24hrTicker`
+        let is_window_ticker = value
+            .get("e")
+            .and_then(Value::as_str)
+            .and_then(|event_type| event_type.strip_suffix("Ticker"))
+            .and_then(|window| window.strip_suffix(['m', 'h', 'd']))
+            .is_some_and(|count| !count.is_empty() && count.bytes().all(|b| b.is_ascii_digit()));
+
+        // Spot partial depth and bookTicker payloads carry no `e` field
+        let event = if is_window_ticker {
+            WindowTicker::deserialize(value).map(MarketEvent::WindowTicker)
+        } else if value.get("e").is_some() {
+            TaggedEvent::deserialize(value).map(MarketEvent::from)
+        } else if value.get("lastUpdateId").is_some() {
+            DepthSnapshot::deserialize(value).map(MarketEvent::PartialDepth)
+        } else if value.get("u").is_some() && value.get("b").is_some() {
+            BookTicker::deserialize(value).map(MarketEvent::BookTicker)
+        } else {
+            return Err(de::Error::custom("missing event type `e`"));
+        };
+        event.map_err(de::Error::custom)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct KlineEvent {
+    #[serde(rename = "k")]
+    pub kline: Kline,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DepthUpdate {
+    #[serde(rename = "E", deserialize_with = "datetime_from_millis")]
+    pub event_time: DateTime<Utc>,
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "U")]
+    pub first_update_id: u64,
+    #[serde(rename = "u")]
+    pub final_update_id: u64,
+    /// Final update id of the previous event, only sent by the futures streams
+    #[serde(rename = "pu", default)]
+    pub previous_final_update_id: Option<u64>,
+    #[serde(rename = "b")]
+    pub bids: Vec<PriceLevel>,
+    #[serde(rename = "a")]
+    pub asks: Vec<PriceLevel>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BookTicker {
+    #[serde(rename = "u")]
+    pub update_id: u64,
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "b", deserialize_with = "f64_from_str")]
+    pub bid_price: f64,
+    #[serde(rename = "B", deserialize_with = "f64_from_str")]
+    pub bid_quantity: f64,
+    #[serde(rename = "a", deserialize_with = "f64_from_str")]
+    pub ask_price: f64,
+    #[serde(rename = "A", deserialize_with = "f64_from_str")]
+    pub ask_quantity: f64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Ticker {
+    #[serde(rename = "E", deserialize_with = "datetime_from_millis")]
+    pub event_time: DateTime<Utc>,
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "P", deserialize_with = "f64_from_str")]
+    pub price_change_percent: f64,
+    #[serde(rename = "c", deserialize_with = "f64_from_str")]
+    pub last_price: f64,
+    #[serde(rename = "h", deserialize_with = "f64_from_str")]
+    pub high_price: f64,
+    #[serde(rename = "l", deserialize_with = "f64_from_str")]
+    pub low_price: f64,
+    #[serde(rename = "v", deserialize_with = "f64_from_str")]
+    pub volume: f64,
+    #[serde(rename = "q", deserialize_with = "f64_from_str")]
+    pub quote_volume: f64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MiniTicker {
+    #[serde(rename = "E", deserialize_with = "datetime_from_millis")]
+    pub event_time: DateTime<Utc>,
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "c", deserialize_with = "f64_from_str")]
+    pub close_price: f64,
+    #[serde(rename = "o", deserialize_with = "f64_from_str")]
+    pub open_price: f64,
+    #[serde(rename = "h", deserialize_with = "f64_from_str")]
+    pub high_price: f64,
+    #[serde(rename = "l", deserialize_with = "f64_from_str")]
+    pub low_price: f64,
+    #[serde(rename = "v", deserialize_with = "f64_from_str")]
+    pub volume: f64,
+    #[serde(rename = "q", deserialize_with = "f64_from_str")]
+    pub quote_volume: f64,
+}
+
+/// Statistics over a rolling window such as `1h`, `4h` or `1d`
+#[derive(Debug, Clone, Deserialize)]
+pub struct WindowTicker {
+    #[serde(rename = "e")]
+    pub event_type: String,
+    #[serde(rename = "E", deserialize_with = "datetime_from_millis")]
+    pub event_time: DateTime<Utc>,
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "p", deserialize_with = "f64_from_str")]
+    pub price_change: f64,
+    #[serde(rename = "P", deserialize_with = "f64_from_str")]
+    pub price_change_percent: f64,
+    #[serde(rename = "o", deserialize_with = "f64_from_str")]
+    pub open_price: f64,
+    #[serde(rename = "h", deserialize_with = "f64_from_str")]
+    pub high_price: f64,
+    #[serde(rename = "l", deserialize_with = "f64_from_str")]
+    pub low_price: f64,
+    #[serde(rename = "c", deserialize_with = "f64_from_str")]
+    pub last_price: f64,
+    #[serde(rename = "w", deserialize_with = "f64_from_str")]
+    pub weighted_avg_price: f64,
+    #[serde(rename = "v", deserialize_with = "f64_from_str")]
+    pub volume: f64,
+    #[serde(rename = "q", deserialize_with = "f64_from_str")]
+    pub quote_volume: f64,
+    #[serde(rename = "O", deserialize_with = "datetime_from_millis")]
+    pub open_time: DateTime<Utc>,
+    #[serde(rename = "n")]
+    pub trade_count: u64,
+}
+
+impl WindowTicker {
+    // Window size taken from the event type, e.g. `4h` for `4hTicker`
+    pub fn window(&self) -> &str {
+        self.event_type.trim_end_matches("Ticker")
+    }
+}
+
+/// Mark price, index price and funding of a futures contract, from `@markPrice`
+#[derive(Debug, Clone, Deserialize)]
+pub struct MarkPrice {
+    #[serde(rename = "E", deserialize_with = "datetime_from_millis")]
+    pub event_time: DateTime<Utc>,
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "p", deserialize_with = "f64_from_str")]
+    pub mark_price: f64,
+    // Not sent by the COIN-M streams
+    #[serde(rename = "i", default, deserialize_with = "optional_f64_from_str")]
+    pub index_price: Option<f64>,
+    #[serde(rename = "P", deserialize_with = "f64_from_str")]
+    pub estimated_settle_price: f64,
+    // Delivery contracts pay no funding and send an empty rate
+    #[serde(rename = "r", deserialize_with = "optional_f64_from_str")]
+    pub funding_rate: Option<f64>,
+    #[serde(rename = "T", deserialize_with = "datetime_from_millis")]
+    pub next_funding_time: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ForceOrder {
+    #[serde(rename = "o")]
+    order: Liquidation,
+}
+
+/// A liquidation order, nested under `o` in `@forceOrder` events
+#[derive(Debug, Clone, Deserialize)]
+pub struct Liquidation {
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "S")]
+    pub side: String,
+    #[serde(rename = "ap", deserialize_with = "f64_from_str")]
+    pub average_price: f64,
+    #[serde(rename = "z", deserialize_with = "f64_from_str")]
+    pub filled_quantity: f64,
+    #[serde(rename = "T", deserialize_with = "datetime_from_millis")]
+    pub trade_time: DateTime<Utc>,
+}
+
+impl Liquidation {
+    // Quote value of the filled part of the order
+    pub fn notional(&self) -> f64 {
+        self.filled_quantity * self.average_price
+    }
+}
+
+/// Candle of a perpetual or delivery contract, from `@continuousKline`.
+/// The candle itself carries no symbol; the pair and contract type name it.
+#[derive(Debug, Clone, Deserialize)]
+struct ContinuousKlineEvent {
+    #[serde(rename = "ps")]
+    pair: String,
+    #[serde(rename = "ct")]
+    contract_type: String,
+    #[serde(rename = "k")]
+    kline: Kline,
+}
+
+/// Price of a composite index and its constituents, from `@compositeIndex`
+#[derive(Debug, Clone, Deserialize)]
+pub struct CompositeIndex {
+    #[serde(rename = "E", deserialize_with = "datetime_from_millis")]
+    pub event_time: DateTime<Utc>,
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "p", deserialize_with = "f64_from_str")]
+    pub price: f64,
+    #[serde(rename = "c")]
+    pub components: Vec<IndexComponent>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IndexComponent {
+    #[serde(rename = "b")]
+    pub base_asset: String,
+    #[serde(rename = "q")]
+    pub quote_asset: String,
+    #[serde(rename = "W", deserialize_with = "f64_from_str")]
+    pub weight_percent: f64,
+    #[serde(rename = "i", deserialize_with = "f64_from_str")]
+    pub index_price: f64,
+}
+
+/// Parses a text frame into a market event.
+/// Returns `Ok(None)` for replies to control messages such as SUBSCRIBE.
+pub fn parse_frame(text: &str) -> Result<Option<StreamFrame>, serde_json::Error> {
+    let mut json = serde_json::from_str::<Value>(text)?;
+
+    // Control replies look like {"result": null, "id": 1}
+    if json.get("id").is_some() && (json.get("result").is_some() || json.get("error").is_some()) {
+        return Ok(None);
+    }
+
+    // Combined streams wrap the payload as {"stream": "...", "data": {...}}
+    let stream = json
+        .get("stream")
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    let data = match (&stream, json.get_mut("data")) {
+        (Some(_), Some(data)) => data.take(),
+        _ => json,
+    };
+
+    Ok(Some(StreamFrame {
+        stream,
+        event: MarketEvent::deserialize(data)?,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_event(text: &str) -> MarketEvent {
+        parse_frame(text).unwrap().unwrap().event
+    }
+
+    #[test]
+    fn test_parse_agg_trade() {
+        let event = parse_event(
+            r#"{"e":"aggTrade","E":1672515782136,"s":"BNBBTC","a":12345,"p":"0.001","q":"100","f":100,"l":105,"T":1672515782136,"m":true,"M":true}"#,
+        );
+        let MarketEvent::AggTrade(agg_trade) = event else {
+            panic!("expected aggTrade, got {:?}", event);
+        };
+        assert_eq!(agg_trade.symbol, "BNBBTC");
+        assert_eq!(agg_trade.trade_id, 12345);
+        assert_eq!(agg_trade.price, 0.001);
+        assert_eq!(agg_trade.quantity, 100.0);
+        assert_eq!(agg_trade.first_trade_id, 100);
+        assert_eq!(agg_trade.last_trade_id, 105);
+        assert!(agg_trade.is_buyer_maker);
+    }
+
+    #[test]
+    fn test_parse_trade() {
+        let event = parse_event(
+            r#"{"e":"trade","E":1672515782136,"s":"BNBBTC","t":12345,"p":"0.001","q":"100","b":88,"a":50,"T":1672515782136,"m":true,"M":true}"#,
+        );
+        let MarketEvent::Trade(trade) = event else {
+            panic!("expected trade, got {:?}", event);
+        };
+        assert_eq!(trade.symbol, "BNBBTC");
+        assert_eq!(trade.trade_id, 12345);
+        assert_eq!(trade.price, 0.001);
+        assert_eq!(trade.quantity, 100.0);
+        assert_eq!(trade.buyer_order_id, Some(88));
+        assert_eq!(trade.seller_order_id, Some(50));
+        assert_eq!(trade.timestamp.timestamp_millis(), 1672515782136);
+        assert!(trade.is_buyer_maker);
+    }
+
+    #[test]
+    fn test_parse_kline() {
+        let event = parse_event(
+            r#"{"e":"kline","E":1672515782136,"s":"BNBBTC","k":{"t":1672515780000,"T":1672515839999,"s":"BNBBTC","i":"1m","f":100,"L":200,"o":"0.0010","c":"0.0020","h":"0.0025","l":"0.0015","v":"1000","n":100,"x":false,"q":"1.0000","V":"500","Q":"0.500","B":"123456"}}"#,
+        );
+        let MarketEvent::Kline(KlineEvent { kline }) = event else {
+            panic!("expected kline, got {:?}", event);
+        };
+        assert_eq!(kline.symbol, "BNBBTC");
+        assert_eq!(kline.interval, "1m");
+        assert_eq!(kline.open_time.timestamp_millis(), 1672515780000);
+        assert_eq!(kline.high, 0.0025);
+        assert_eq!(kline.low, 0.0015);
+        assert_eq!(kline.trade_count, 100);
+        assert_eq!(kline.taker_buy_base_volume, 500.0);
+        assert!(!kline.is_closed);
+    }
+
+    #[test]
+    fn test_parse_depth_update_and_book_ticker() {
+        let event = parse_event(
+            r#"{"e":"depthUpdate","E":1672515782136,"s":"BNBBTC","U":157,"u":160,"b":[["0.0024","10"]],"a":[["0.0026","100"]]}"#,
+        );
+        let MarketEvent::DepthUpdate(depth) = event else {
+            panic!("expected depthUpdate, got {:?}", event);
+        };
+        assert_eq!((depth.first_update_id, depth.final_update_id), (157, 160));
+        assert_eq!(depth.bids[0].price, 0.0024);
+        assert_eq!(depth.asks[0].quantity, 100.0);
+
+        // Spot bookTicker payloads carry no event type
+        let event = parse_event(
+            r#"{"u":400900217,"s":"BNBUSDT","b":"25.35190000","B":"31.21000000","a":"25.36520000","A":"40.66000000"}"#,
+        );
+        let MarketEvent::BookTicker(book_ticker) = event else {
+            panic!("expected bookTicker, got {:?}", event);
+        };
+        assert_eq!(book_ticker.bid_price, 25.3519);
+        assert_eq!(book_ticker.ask_quantity, 40.66);
+    }
+
+    #[test]
+    fn test_parse_partial_depth() {
+        let frame = parse_frame(
+            r#"{"stream":"bnbbtc@depth5@100ms","data":{"lastUpdateId":160,"bids":[["0.0024","10"],["0.0023","5"]],"asks":[["0.0026","100"]]}}"#,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(frame.stream_symbol().as_deref(), Some("BNBBTC"));
+        let MarketEvent::PartialDepth(depth) = frame.event else {
+            panic!("expected partial depth, got {:?}", frame.event);
+        };
+        assert_eq!(depth.last_update_id, 160);
+        assert_eq!(depth.bids.len(), 2);
+        assert_eq!(depth.asks[0].price, 0.0026);
+    }
+
+    #[test]
+    fn test_parse_ticker_array() {
+        let frame = parse_frame(
+            r#"[{"e":"24hrTicker","E":1672515782136,"s":"BNBBTC","p":"0.0015","P":"250.00","w":"0.0018","x":"0.0009","c":"0.0025","Q":"10","b":"0.0024","B":"10","a":"0.0026","A":"100","o":"0.0010","h":"0.0025","l":"0.0010","v":"10000","q":"18","O":0,"C":86400000,"F":0,"L":18150,"n":18151},{"e":"24hrMiniTicker","E":1672515782136,"s":"ETHBTC","c":"0.0025","o":"0.0010","h":"0.0025","l":"0.0010","v":"10000","q":"18"}]"#,
+        )
+        .unwrap()
+        .unwrap();
+        let MarketEvent::Batch(events) = frame.event else {
+            panic!("expected batch, got {:?}", frame.event);
+        };
+        assert_eq!(events.len(), 2);
+        assert!(matches!(&events[0], MarketEvent::Ticker(t) if t.price_change_percent == 250.0));
+        assert!(matches!(&events[1], MarketEvent::MiniTicker(t) if t.symbol == "ETHBTC"));
+    }
+
+    #[test]
+    fn test_parse_window_ticker() {
+        let event = parse_event(
+            r#"{"e":"4hTicker","E":1672515782136,"s":"BNBBTC","p":"0.0015","P":"250.00","o":"0.0010","h":"0.0025","l":"0.0010","c":"0.0025","w":"0.0018","v":"10000","q":"18","O":0,"C":1675216573749,"F":0,"L":18150,"n":18151}"#,
+        );
+        let MarketEvent::WindowTicker(ticker) = event else {
+            panic!("expected window ticker, got {:?}", event);
+        };
+        assert_eq!(ticker.window(), "4h");
+        assert_eq!(ticker.price_change_percent, 250.0);
+        assert_eq!(ticker.trade_count, 18151);
+    }
+
+    #[test]
+    fn test_parse_futures_events() {
+        let event = parse_event(
+            r#"{"e":"markPriceUpdate","E":1562305380000,"s":"BTCUSDT","p":"11794.15000000","i":"11784.62659091","P":"11784.25641265","r":"0.00038167","T":1562306400000}"#,
+        );
+        let MarketEvent::MarkPrice(mark) = event else {
+            panic!("expected mark price, got {:?}", event);
+        };
+        assert_eq!(mark.mark_price, 11794.15);
+        assert_eq!(mark.funding_rate, Some(0.00038167));
+        assert_eq!(mark.next_funding_time.timestamp_millis(), 1562306400000);
+
+        // Delivery contracts have no funding rate
+        let event = parse_event(
+            r#"{"e":"markPriceUpdate","E":1562305380000,"s":"BTCUSDT_240628","p":"1.0","i":"1.0","P":"1.0","r":"","T":0}"#,
+        );
+        assert!(matches!(event, MarketEvent::MarkPrice(ref mark) if mark.funding_rate.is_none()));
+
+        // COIN-M mark prices come without an index price
+        let event = parse_event(
+            r#"{"e":"markPriceUpdate","E":1596095725000,"s":"BTCUSD_PERP","p":"11012.31","P":"10989.97","r":"0.0001","T":1596096000000}"#,
+        );
+        assert!(matches!(event, MarketEvent::MarkPrice(ref mark) if mark.index_price.is_none()));
+
+        let event = parse_event(
+            r#"{"e":"forceOrder","E":1568014460893,"o":{"s":"BTCUSDT","S":"SELL","o":"LIMIT","f":"IOC","q":"0.014","p":"9910","ap":"9910","X":"FILLED","l":"0.014","z":"0.014","T":1568014460893}}"#,
+        );
+        let MarketEvent::Liquidation(liquidation) = event else {
+            panic!("expected liquidation, got {:?}", event);
+        };
+        assert_eq!(liquidation.symbol, "BTCUSDT");
+        assert_eq!(liquidation.side, "SELL");
+        assert_eq!(liquidation.notional(), 0.014 * 9910.0);
+
+        let event = parse_event(
+            r#"{"e":"continuous_kline","E":1607443058651,"ps":"BTCUSDT","ct":"PERPETUAL","k":{"t":1607443020000,"T":1607443079999,"i":"1m","f":116467658886,"L":116468012423,"o":"18787.00","c":"18804.04","h":"18804.04","l":"18786.54","v":"197.664","n":543,"x":false,"q":"3715253.19494","V":"184.769","Q":"3472925.84746","B":"0"}}"#,
+        );
+        let MarketEvent::Kline(KlineEvent { kline }) = event else {
+            panic!("expected kline, got {:?}", event);
+        };
+        assert_eq!(kline.symbol, "BTCUSDT_PERPETUAL");
+        assert_eq!(kline.close, 18804.04);
+
+        let event = parse_event(
+            r#"{"e":"compositeIndex","E":1602310596000,"s":"DEFIUSDT","p":"554.41604065","C":"baseAsset","c":[{"b":"BAL","q":"USDT","w":"1.04884844","W":"0.01457800","i":"24.33521021"},{"b":"BAND","q":"USDT","w":"3.53782729","W":"0.03935200","i":"7.26420084"}]}"#,
+        );
+        let MarketEvent::CompositeIndex(index) = event else {
+            panic!("expected composite index, got {:?}", event);
+        };
+        assert_eq!(index.price, 554.41604065);
+        assert_eq!(index.components.len(), 2);
+        assert_eq!(index.components[1].base_asset, "BAND");
+    }
+
+    #[test]
+    fn test_parse_frame_unwraps_combined_envelope() {
+        let frame = parse_frame(
+            r#"{"stream":"btcusdt@aggTrade","data":{"e":"aggTrade","s":"BTCUSDT","a":1,"p":"1.0","q":"2.0","f":1,"l":1,"T":1672515782136,"m":false}}"#,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(frame.stream.as_deref(), Some("btcusdt@aggTrade"));
+        assert!(matches!(frame.event, MarketEvent::AggTrade(ref t) if t.symbol == "BTCUSDT"));
+    }
+
+    #[test]
+    fn test_parse_frame_skips_control_replies() {
+        assert!(parse_frame(r#"{"result":null,"id":1}"#).unwrap().is_none());
+        assert!(
+            parse_frame(r#"{"error":{"code":2,"msg":"Invalid request"},"id":1}"#)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_invalid_messages_are_errors() {
+        // Missing trade id
+        assert!(parse_frame(
+            r#"{"e":"aggTrade","s":"BTCUSDT","p":"1.0","q":"2.0","f":1,"l":1,"T":1672515782136,"m":false}"#
+        )
+        .is_err());
+        // Price that is not a decimal
+        assert!(parse_frame(
+            r#"{"e":"trade","s":"BTCUSDT","t":1,"p":"abc","q":"2.0","T":1672515782136,"m":false}"#
+        )
+        .is_err());
+        // Unknown event type
+        assert!(parse_frame(r#"{"e":"somethingElse","s":"BTCUSDT"}"#).is_err());
+        assert!(parse_frame(r#"{"s":"BTCUSDT"}"#).is_err());
+        assert!(parse_frame("not json").is_err());
+    }
 }
@@ -1,4 +1,5 @@
 pub mod client;
+pub mod deserialize;
 pub mod handler;
 pub mod message;
 pub mod ping;
@@ -1,25 +1,98 @@
 use futures_util::SinkExt;
 use std::fmt::Debug;
+use std::time::Instant;
 use tokio::sync::oneshot;
 use tokio::time::{interval, Duration};
 use tokio_tungstenite::tungstenite::protocol::Message;
 
-pub async fn start_ping<W>(write: &mut W, mut shutdown_rx: oneshot::Receiver<()>)
+/// When the connection last showed signs of life, used to spot a silent connection
+pub struct Liveness {
+    last_message: Instant,
+    last_pong: Option<Instant>,
+}
+
+impl Liveness {
+    // Create a Liveness for a connection that has just opened
+    pub fn new(now: Instant) -> Self {
+        Self {
+            last_message: now,
+            last_pong: None,
+        }
+    }
+
+    // Record a data frame or server ping
+    pub fn record_message(&mut self, now: Instant) {
+        self.last_message = now;
+    }
+
+    // Record a pong answering one of our pings
+    pub fn record_pong(&mut self, now: Instant) {
+        self.last_pong = Some(now);
+    }
+
+    // Get the most recent sign of life of any kind
+    pub fn last_activity(&self) -> Instant {
+        self.last_pong
+            .map_or(self.last_message, |pong| pong.max(self.last_message))
+    }
+
+    // Check whether nothing, not even a pong, has arrived within `silence`
+    pub fn is_stale(&self, now: Instant, silence: Duration) -> bool {
+        now.duration_since(self.last_activity()) >= silence
+    }
+}
+
+/// Records an incoming frame and answers server pings with the identical payload.
+/// Returns the text of data frames.
+pub async fn handle_incoming<W>(
+    write: &mut W,
+    message: Message,
+    liveness: &mut Liveness,
+) -> Result<Option<String>, W::Error>
+where
+    W: SinkExt<Message> + Unpin,
+{
+    let now = Instant::now();
+    match message {
+        Message::Text(text) => {
+            liveness.record_message(now);
+            Ok(Some(text))
+        }
+        Message::Ping(payload) => {
+            liveness.record_message(now);
+            // Binance disconnects clients that do not echo its pings
+            write.send(Message::Pong(payload)).await?;
+            Ok(None)
+        }
+        Message::Pong(_) => {
+            liveness.record_pong(now);
+            Ok(None)
+        }
+        _ => {
+            liveness.record_message(now);
+            Ok(None)
+        }
+    }
+}
+
+/// Sends a ping every `period` and a close frame once `shutdown_rx` fires
+pub async fn start_ping<W>(write: &mut W, period: Duration, mut shutdown_rx: oneshot::Receiver<()>)
 where
     W: SinkExt<Message> + Unpin,
     <W as futures_util::Sink<Message>>::Error: Debug,
 {
-    let mut ping_interval = interval(Duration::from_secs(180));
+    let mut ping_interval = interval(period);
     loop {
         tokio::select! {
             _ = ping_interval.tick() => {
-                // Send ping message at regular intervals
-                write.send(Message::Ping(Vec::new())).await.unwrap();
+                // Send ping message at regular intervals; a failed send means the connection is gone
+                if write.send(Message::Ping(Vec::new())).await.is_err() {
+                    break;
+                }
             },
             _ = &mut shutdown_rx => {
                 // Shutdown WebSocket connection
-                println!("Shutting down WebSocket...");
-                write.send(Message::Close(None)).await.unwrap();
+                let _ = write.send(Message::Close(None)).await;
                 break;
             }
         }
@@ -29,58 +102,18 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::MockSink;
     use std::time::Duration;
-    use tokio::sync::mpsc;
     use tokio::time::timeout;
     use tokio_tungstenite::tungstenite::protocol::Message;
 
-    struct MockSink {
-        tx: mpsc::UnboundedSender<Message>,
-    }
-
-    impl MockSink {
-        fn new() -> (Self, mpsc::UnboundedReceiver<Message>) {
-            let (tx, rx) = mpsc::unbounded_channel();
-            (MockSink { tx }, rx)
-        }
-    }
-
-    impl futures_util::Sink<Message> for MockSink {
-        type Error = mpsc::error::SendError<Message>;
-
-        fn poll_ready(
-            self: std::pin::Pin<&mut Self>,
-            _: &mut std::task::Context<'_>,
-        ) -> std::task::Poll<Result<(), Self::Error>> {
-            std::task::Poll::Ready(Ok(()))
-        }
-
-        fn start_send(self: std::pin::Pin<&mut Self>, item: Message) -> Result<(), Self::Error> {
-            self.tx.send(item).map_err(|e| e.into())
-        }
-
-        fn poll_flush(
-            self: std::pin::Pin<&mut Self>,
-            _: &mut std::task::Context<'_>,
-        ) -> std::task::Poll<Result<(), Self::Error>> {
-            std::task::Poll::Ready(Ok(()))
-        }
-
-        fn poll_close(
-            self: std::pin::Pin<&mut Self>,
-            _: &mut std::task::Context<'_>,
-        ) -> std::task::Poll<Result<(), Self::Error>> {
-            std::task::Poll::Ready(Ok(()))
-        }
-    }
-
     #[tokio::test]
     async fn test_start_ping() {
         let (mut mock_sink, mut rx) = MockSink::new();
         let (shutdown_tx, shutdown_rx) = oneshot::channel();
 
         tokio::spawn(async move {
-            start_ping(&mut mock_sink, shutdown_rx).await;
+            start_ping(&mut mock_sink, Duration::from_secs(180), shutdown_rx).await;
         });
         tokio::time::sleep(Duration::from_secs(1)).await;
         shutdown_tx.send(()).unwrap();
@@ -98,4 +131,50 @@ mod tests {
         assert!(messages.iter().any(|msg| matches!(msg, Message::Ping(_))));
         assert!(messages.iter().any(|msg| matches!(msg, Message::Close(_))));
     }
+
+    #[tokio::test]
+    async fn test_server_pings_are_answered() {
+        let (mut mock_sink, mut rx) = MockSink::new();
+        let start = Instant::now();
+        let mut liveness = Liveness::new(start);
+
+        let text = handle_incoming(&mut mock_sink, Message::Ping(vec![1, 2, 3]), &mut liveness)
+            .await
+            .unwrap();
+        assert_eq!(text, None);
+        // The pong echoes the payload of the ping
+        assert_eq!(rx.recv().await.unwrap(), Message::Pong(vec![1, 2, 3]));
+
+        let text = handle_incoming(
+            &mut mock_sink,
+            Message::Text("{}".to_string()),
+            &mut liveness,
+        )
+        .await
+        .unwrap();
+        assert_eq!(text.as_deref(), Some("{}"));
+        handle_incoming(&mut mock_sink, Message::Pong(Vec::new()), &mut liveness)
+            .await
+            .unwrap();
+        assert!(rx.try_recv().is_err());
+        assert!(liveness.last_activity() >= start);
+    }
+
+    #[test]
+    fn test_silent_connection_is_stale() {
+        let silence = Duration::from_secs(60);
+        let start = Instant::now();
+        let mut liveness = Liveness::new(start);
+        assert!(!liveness.is_stale(start + Duration::from_secs(59), silence));
+        assert!(liveness.is_stale(start + silence, silence));
+
+        // A pong keeps a connection without data alive
+        liveness.record_pong(start + Duration::from_secs(50));
+        assert!(!liveness.is_stale(start + Duration::from_secs(100), silence));
+        assert!(liveness.is_stale(start + Duration::from_secs(110), silence));
+
+        liveness.record_message(start + Duration::from_secs(120));
+        assert_eq!(liveness.last_activity(), start + Duration::from_secs(120));
+        assert!(!liveness.is_stale(start + Duration::from_secs(150), silence));
+    }
 }
@@ -18,6 +18,35 @@ pub fn unsubscribe_message(streams: &Vec<String>, id: u64) -> String {
     .to_string()
 }
 
+pub fn list_subscriptions_message(id: u64) -> String {
+    // Create list subscriptions message as a JSON string
+    serde_json::json!({
+        "method": "LIST_SUBSCRIPTIONS",
+        "id": id
+    })
+    .to_string()
+}
+
+pub fn set_property_message(property: &str, value: serde_json::Value, id: u64) -> String {
+    // Create set property message as a JSON string
+    serde_json::json!({
+        "method": "SET_PROPERTY",
+        "params": [property, value],
+        "id": id
+    })
+    .to_string()
+}
+
+pub fn get_property_message(property: &str, id: u64) -> String {
+    // Create get property message as a JSON string
+    serde_json::json!({
+        "method": "GET_PROPERTY",
+        "params": [property],
+        "id": id
+    })
+    .to_string()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -52,4 +81,25 @@ mod tests {
         let message_json: serde_json::Value = serde_json::from_str(&message).unwrap();
         assert_eq!(message_json, expected_message);
     }
+
+    #[test]
+    fn test_property_messages() {
+        // Test for list subscriptions, set property and get property message creation
+        let list: serde_json::Value = serde_json::from_str(&list_subscriptions_message(3)).unwrap();
+        assert_eq!(list, json!({"method": "LIST_SUBSCRIPTIONS", "id": 3}));
+
+        let set: serde_json::Value =
+            serde_json::from_str(&set_property_message("combined", json!(true), 5)).unwrap();
+        assert_eq!(
+            set,
+            json!({"method": "SET_PROPERTY", "params": ["combined", true], "id": 5})
+        );
+
+        let get: serde_json::Value =
+            serde_json::from_str(&get_property_message("combined", 2)).unwrap();
+        assert_eq!(
+            get,
+            json!({"method": "GET_PROPERTY", "params": ["combined"], "id": 2})
+        );
+    }
 }